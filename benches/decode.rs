@@ -0,0 +1,92 @@
+//! Compares the table-driven compactSize decoder with the previous per-byte reassembly,
+//! on values of all sizes and on mostly single byte values.
+//!
+//! Run with `cargo bench --bench decode`.
+
+use bitcoin_varint::VarInt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// The decoder as it was before the lookup table: one match arm and one reassembly per prefix class.
+fn decode_match(bytes: &[u8]) -> u64 {
+    match bytes[0] {
+        x if x < 0xfd => u64::from_le_bytes([bytes[0], 0, 0, 0, 0, 0, 0, 0]),
+        0xfd => u64::from_le_bytes([bytes[1], bytes[2], 0, 0, 0, 0, 0, 0]),
+        0xfe => u64::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4], 0, 0, 0, 0]),
+        _ => u64::from_le_bytes([
+            bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7], bytes[8],
+        ]),
+    }
+}
+
+/// Values spread over the four prefix classes, with a simple xorshift so the branch predictor can't learn them.
+fn sample(count: usize) -> Vec<u8> {
+    sample_with(count, |state| match state % 4 {
+        0 => state % 0xfd,
+        1 => state % 0x10000,
+        2 => state % 0x100000000,
+        _ => state,
+    })
+}
+
+/// 95% of single byte values, as in the lengths and counts of typical transactions.
+fn sample_small(count: usize) -> Vec<u8> {
+    sample_with(count, |state| match state % 20 {
+        0 => state % 0x10000,
+        _ => state % 0xfd,
+    })
+}
+
+fn sample_with(count: usize, value: impl Fn(u64) -> u64) -> Vec<u8> {
+    let mut state = 0x9e3779b97f4a7c15u64;
+    let mut bytes = Vec::new();
+    for _ in 0..count {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        bytes.extend_from_slice(&VarInt::encode(value(state)).unwrap());
+    }
+    bytes
+}
+
+fn run(name: &str, bytes: &[u8], count: usize, decode: impl Fn(&[u8]) -> (u64, usize)) {
+    let mut best = Duration::MAX;
+    for _ in 0..20 {
+        let start = Instant::now();
+        let mut pos = 0;
+        let mut sum = 0u64;
+        while pos < bytes.len() {
+            let (value, len) = decode(black_box(&bytes[pos..]));
+            sum = sum.wrapping_add(value);
+            pos += len;
+        }
+        black_box(sum);
+        best = best.min(start.elapsed());
+    }
+    println!(
+        "{:<12} {:>8.2} ns/value",
+        name,
+        best.as_nanos() as f64 / count as f64
+    );
+}
+
+fn main() {
+    let count = 1_000_000;
+    for (distribution, bytes) in [("uniform", sample(count)), ("small", sample_small(count))] {
+        println!("{}:", distribution);
+        run("match", &bytes, count, |b| {
+            let value = decode_match(b);
+            let len = match b[0] {
+                0xfd => 3,
+                0xfe => 5,
+                0xff => 9,
+                _ => 1,
+            };
+            (value, len)
+        });
+        run("table", &bytes, count, |b| {
+            let decoded = VarInt::decode_full(b).unwrap();
+            (decoded.value, decoded.len)
+        });
+    }
+}
@@ -0,0 +1,102 @@
+//! Compares the `leading_zeros` based size computation and encoder with the previous range comparisons, and
+//! the loop over [`VarInt::encode_append`] with the block encoder of [`encode_all_into`].
+//!
+//! Run with `cargo bench --bench encode`.
+
+use bitcoin_varint::bulk::encode_all_into;
+use bitcoin_varint::VarInt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// The size computation as a ladder of range comparisons (with the boundaries fixed).
+fn size_ladder(value: u64) -> usize {
+    match value {
+        x if x <= 252 => 1,
+        x if x <= 0xffff => 3,
+        x if x <= 0xffffffff => 5,
+        _ => 9,
+    }
+}
+
+/// The encoder as it was before, one branch per prefix class.
+fn encode_ladder(value: u64, out: &mut Vec<u8>) -> usize {
+    let bytes = value.to_le_bytes();
+    let (prefix, payload) = match value {
+        x if x <= 252 => {
+            out.push(bytes[0]);
+            return 1;
+        }
+        x if x <= 0xffff => (0xfd, 2),
+        x if x <= 0xffffffff => (0xfe, 4),
+        _ => (0xff, 8),
+    };
+    out.push(prefix);
+    out.extend_from_slice(&bytes[..payload]);
+    1 + payload
+}
+
+/// Values spread over the four prefix classes, with a simple xorshift so the branch predictor can't learn them.
+fn sample(count: usize) -> Vec<u64> {
+    let mut state = 0x9e3779b97f4a7c15u64;
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            match state % 4 {
+                0 => state % 0xfd,
+                1 => state % 0x10000,
+                2 => state % 0x100000000,
+                _ => state,
+            }
+        })
+        .collect()
+}
+
+fn run(name: &str, values: &[u64], f: impl Fn(u64, &mut Vec<u8>) -> usize) {
+    let mut best = Duration::MAX;
+    let mut out = Vec::with_capacity(values.len() * 9);
+    for _ in 0..20 {
+        out.clear();
+        let start = Instant::now();
+        let mut sum = 0usize;
+        for value in values {
+            sum += f(black_box(*value), &mut out);
+        }
+        black_box(sum);
+        best = best.min(start.elapsed());
+    }
+    println!(
+        "{:<16} {:>8.2} ns/value",
+        name,
+        best.as_nanos() as f64 / values.len() as f64
+    );
+}
+
+fn run_bulk(name: &str, values: &[u64], f: impl Fn(&[u64], &mut Vec<u8>) -> usize) {
+    let mut best = Duration::MAX;
+    let mut out = Vec::with_capacity(values.len() * 9 + 8);
+    for _ in 0..20 {
+        out.clear();
+        let start = Instant::now();
+        black_box(f(black_box(values), &mut out));
+        best = best.min(start.elapsed());
+    }
+    println!(
+        "{:<16} {:>8.2} ns/value",
+        name,
+        best.as_nanos() as f64 / values.len() as f64
+    );
+}
+
+fn main() {
+    let values = sample(1_000_000);
+    run("size ladder", &values, |v, _| size_ladder(v));
+    run("size lzcnt", &values, |v, _| VarInt::encoded_len(v));
+    run("encode ladder", &values, encode_ladder);
+    run("encode lzcnt", &values, VarInt::encode_append);
+    run_bulk("bulk scalar", &values, |values, out| {
+        values.iter().map(|v| VarInt::encode_append(*v, out)).sum()
+    });
+    run_bulk("bulk blocks", &values, encode_all_into);
+}
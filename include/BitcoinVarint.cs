@@ -0,0 +1,127 @@
+// C# bindings to the C API of the bitcoin-varint crate, built with its `ffi` feature. See src/ffi.rs and
+// bitcoin_varint.h, which this file mirrors.
+//
+// The native library is built with:
+//
+//     cargo rustc --release --lib --features ffi --crate-type cdylib
+//
+// and goes next to the assembly, or under Assets/Plugins in Unity. The wrappers use pointers, so the
+// project must allow unsafe code.
+
+using System;
+using System.Runtime.InteropServices;
+
+namespace BitcoinVarint
+{
+    /// <summary>Error codes of the C API, stable across releases.</summary>
+    public enum VarIntStatus
+    {
+        Ok = 0,
+        Truncated = 1,
+        NonCanonical = 2,
+        Malformed = 3,
+        Overflow = 4,
+        TooLarge = 5,
+        OutOfOrder = 6,
+        UnknownEvenType = 7,
+        /// <summary>The output buffer can't hold the encoding, which takes at most 9 bytes.</summary>
+        BufferTooSmall = 64,
+    }
+
+    /// <summary>A call of the C API failed.</summary>
+    public sealed class VarIntException : Exception
+    {
+        public VarIntStatus Status { get; }
+
+        public VarIntException(VarIntStatus status) : base("varint error: " + status)
+        {
+            Status = status;
+        }
+    }
+
+    /// <summary>The functions of bitcoin_varint.h.</summary>
+    public static class Native
+    {
+        private const string Library = "bitcoin_varint";
+
+        [DllImport(Library, EntryPoint = "varint_encoded_len", CallingConvention = CallingConvention.Cdecl)]
+        public static extern UIntPtr EncodedLen(ulong value);
+
+        [DllImport(Library, EntryPoint = "varint_encode", CallingConvention = CallingConvention.Cdecl)]
+        public static extern unsafe int Encode(ulong value, byte* output, UIntPtr capacity, out UIntPtr written);
+
+        [DllImport(Library, EntryPoint = "varint_decode", CallingConvention = CallingConvention.Cdecl)]
+        public static extern unsafe int Decode(byte* bytes, UIntPtr len, out ulong value, out UIntPtr read);
+    }
+
+    /// <summary>compactSize encoding and decoding, throwing <see cref="VarIntException"/> on failure.</summary>
+    public static class CompactSize
+    {
+        /// <summary>Longest encoding of a value.</summary>
+        public const int MaxLen = 9;
+
+        public static int EncodedLen(ulong value)
+        {
+            return (int)Native.EncodedLen(value);
+        }
+
+        public static byte[] Encode(ulong value)
+        {
+            var output = new byte[EncodedLen(value)];
+            Encode(value, output, 0);
+            return output;
+        }
+
+        /// <summary>Writes the encoding of <paramref name="value"/> at <paramref name="offset"/>, returning its length.</summary>
+        public static unsafe int Encode(ulong value, byte[] output, int offset)
+        {
+            if (offset < 0 || offset > output.Length)
+            {
+                throw new ArgumentOutOfRangeException(nameof(offset));
+            }
+            fixed (byte* start = output)
+            {
+                var status = Native.Encode(value, start + offset, (UIntPtr)(output.Length - offset), out var written);
+                Check(status);
+                return (int)written;
+            }
+        }
+
+        /// <summary>Decodes the value at <paramref name="offset"/>, rejecting non-canonical encodings.</summary>
+        public static unsafe ulong Decode(byte[] bytes, int offset, out int read)
+        {
+            if (offset < 0 || offset > bytes.Length)
+            {
+                throw new ArgumentOutOfRangeException(nameof(offset));
+            }
+            fixed (byte* start = bytes)
+            {
+                var status = Native.Decode(start + offset, (UIntPtr)(bytes.Length - offset), out var value, out var len);
+                Check(status);
+                read = (int)len;
+                return value;
+            }
+        }
+
+        /// <summary>Decodes every value of a buffer of concatenated compactSizes.</summary>
+        public static ulong[] DecodeAll(byte[] bytes)
+        {
+            var values = new System.Collections.Generic.List<ulong>();
+            var offset = 0;
+            while (offset < bytes.Length)
+            {
+                values.Add(Decode(bytes, offset, out var read));
+                offset += read;
+            }
+            return values.ToArray();
+        }
+
+        private static void Check(int status)
+        {
+            if (status != (int)VarIntStatus.Ok)
+            {
+                throw new VarIntException((VarIntStatus)status);
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/* C API of the bitcoin-varint crate, built with its `ffi` feature. See src/ffi.rs. */
+#ifndef BITCOIN_VARINT_H
+#define BITCOIN_VARINT_H
+
+#include <stdint.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define VARINT_OK 0
+/* Error codes, stable across releases. */
+#define VARINT_TRUNCATED 1
+#define VARINT_NON_CANONICAL 2
+#define VARINT_MALFORMED 3
+#define VARINT_OVERFLOW 4
+#define VARINT_TOO_LARGE 5
+#define VARINT_OUT_OF_ORDER 6
+#define VARINT_UNKNOWN_EVEN_TYPE 7
+/* The output buffer can't hold the encoding, which takes at most 9 bytes. */
+#define VARINT_BUFFER_TOO_SMALL 64
+
+/* Number of bytes of the encoding of `value`. */
+size_t varint_encoded_len(uint64_t value);
+
+/* Writes the encoding of `value` to the `capacity` bytes of `out`, and its length to `written`. */
+int32_t varint_encode(uint64_t value, uint8_t *out, size_t capacity, size_t *written);
+
+/* Decodes the compactSize at the start of the `len` bytes of `bytes`, rejecting non-canonical encodings. */
+int32_t varint_decode(const uint8_t *bytes, size_t len, uint64_t *value, size_t *read);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,190 @@
+//! LEB128 for integers wider than a u64, such as 128 or 256 bits amounts and hashes, and for i128 with zigzag.
+//!
+//! Values are taken as little-endian byte strings of any length, so that any big integer type converts to
+//! them without this crate depending on it. Encodings are the same as [`Leb128`](crate::Leb128) for the
+//! values that fit a u64.
+//!
+//! ```
+//! use bitcoin_varint::bigint::{decode_u256, encode_u256};
+//!
+//! let mut value = [0u8; 32];
+//! value[0] = 0x80; // 2^255, big-endian
+//! let bytes = encode_u256(&value);
+//! assert_eq!(bytes.len(), 37);
+//! assert_eq!(decode_u256(&bytes), Ok((value, 37)));
+//! ```
+
+use crate::VarIntError;
+
+/// Encodes the unsigned integer held by little-endian `value`.
+pub fn encode_le(value: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(value.len() * 8 / 7 + 1);
+    encode_le_append(value, &mut result);
+    result
+}
+
+/// Appends the encoding of little-endian `value` to `out`, returning the number of bytes written.
+pub fn encode_le_append(value: &[u8], out: &mut Vec<u8>) -> usize {
+    let groups = significant_bits(value).div_ceil(7).max(1);
+    for i in 0..groups {
+        let (byte, shift) = (7 * i / 8, 7 * i % 8);
+        let low = value.get(byte).map_or(0, |b| b >> shift);
+        let high = value
+            .get(byte + 1)
+            .map_or(0, |next| next.checked_shl(8 - shift as u32).unwrap_or(0));
+        let more = if i + 1 < groups { 0x80 } else { 0 };
+        out.push((low | high) & 0x7f | more);
+    }
+    groups
+}
+
+/// Decodes a value of at most `max_len` bytes, returning it little-endian without high zero bytes,
+/// with the number of bytes read.
+///
+/// As with [`Leb128::decode`](crate::Leb128::decode), padded encodings are accepted up to the length a value
+/// of `max_len` bytes could take. Values needing more bytes fail with [`VarIntError::Overflow`].
+pub fn decode_le(bytes: &[u8], max_len: usize) -> Result<(Vec<u8>, usize), VarIntError> {
+    let max_groups = (max_len * 8).div_ceil(7);
+    let mut value = vec![0u8; max_len];
+    for (i, group) in bytes.iter().take(max_groups).enumerate() {
+        let bits = (group & 0x7f) as u16;
+        let (byte, shift) = (7 * i / 8, 7 * i % 8);
+        let shifted = bits << shift;
+        for (offset, part) in [shifted as u8, (shifted >> 8) as u8]
+            .into_iter()
+            .enumerate()
+        {
+            match value.get_mut(byte + offset) {
+                Some(slot) => *slot |= part,
+                None if part != 0 => return Err(VarIntError::Overflow),
+                None => {}
+            }
+        }
+        if group & 0x80 == 0 {
+            let len = value.len() - value.iter().rev().take_while(|b| **b == 0).count();
+            value.truncate(len);
+            return Ok((value, i + 1));
+        }
+    }
+    if bytes.len() >= max_groups {
+        Err(VarIntError::Overflow)
+    } else {
+        Err(VarIntError::Truncated)
+    }
+}
+
+pub fn encode_u128(value: u128) -> Vec<u8> {
+    encode_le(&value.to_le_bytes())
+}
+
+/// Decodes a u128, returning it with the number of bytes read.
+pub fn decode_u128(bytes: &[u8]) -> Result<(u128, usize), VarIntError> {
+    let (value, len) = decode_le(bytes, 16)?;
+    let mut buf = [0u8; 16];
+    buf[..value.len()].copy_from_slice(&value);
+    Ok((u128::from_le_bytes(buf), len))
+}
+
+/// Maps signed values to unsigned ones of the same magnitude, `0, -1, 1, -2...` to `0, 1, 2, 3...`, so that small
+/// negative values stay short once encoded, as protobuf's `sint64` does.
+pub fn zigzag_i128(value: i128) -> u128 {
+    ((value << 1) ^ (value >> 127)) as u128
+}
+
+/// Inverse of [`zigzag_i128`].
+pub fn unzigzag_i128(value: u128) -> i128 {
+    (value >> 1) as i128 ^ -((value & 1) as i128)
+}
+
+/// Encodes an i128 with zigzag, in at most 19 bytes.
+pub fn encode_i128(value: i128) -> Vec<u8> {
+    encode_u128(zigzag_i128(value))
+}
+
+/// Decodes a zigzag i128, returning it with the number of bytes read.
+pub fn decode_i128(bytes: &[u8]) -> Result<(i128, usize), VarIntError> {
+    decode_u128(bytes).map(|(value, len)| (unzigzag_i128(value), len))
+}
+
+/// Encodes a 256 bits value given big-endian, as most U256 types and hashes print.
+pub fn encode_u256(value: &[u8; 32]) -> Vec<u8> {
+    let mut le = *value;
+    le.reverse();
+    encode_le(&le)
+}
+
+/// Decodes a 256 bits value into big-endian bytes, returning it with the number of bytes read.
+pub fn decode_u256(bytes: &[u8]) -> Result<([u8; 32], usize), VarIntError> {
+    let (value, len) = decode_le(bytes, 32)?;
+    let mut buf = [0u8; 32];
+    buf[..value.len()].copy_from_slice(&value);
+    buf.reverse();
+    Ok((buf, len))
+}
+
+/// Number of bits up to the highest one of little-endian `value`.
+fn significant_bits(value: &[u8]) -> usize {
+    match value.iter().rposition(|b| *b != 0) {
+        Some(i) => 8 * i + 8 - value[i].leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Leb128;
+
+    #[test]
+    fn test_bigint_matches_leb128() {
+        for value in [0, 1, 0x7f, 0x80, 300, 1 << 56, u64::MAX] {
+            let encoded = encode_le(&value.to_le_bytes());
+            assert_eq!(encoded, Leb128::encode(value), "{}", value);
+            assert_eq!(decode_u128(&encoded), Ok((value as u128, encoded.len())));
+        }
+        assert_eq!(encode_le(&[]), vec![0x00]);
+        assert_eq!(decode_le(&[0x80, 0x00], 1), Ok((vec![], 2)));
+    }
+
+    #[test]
+    fn test_bigint_wide_values() {
+        let value = u128::MAX - 12345;
+        let encoded = encode_u128(value);
+        assert_eq!(encoded.len(), 19);
+        assert_eq!(decode_u128(&encoded), Ok((value, 19)));
+
+        let max = [0xff; 32];
+        let encoded = encode_u256(&max);
+        assert_eq!(*encoded.last().unwrap(), 0x0f);
+        assert_eq!(decode_u256(&encoded), Ok((max, 37)));
+        // One bit above 256.
+        let mut above = encoded.clone();
+        *above.last_mut().unwrap() = 0x1f;
+        assert_eq!(decode_u256(&above), Err(VarIntError::Overflow));
+        assert_eq!(decode_u256(&encoded[..36]), Err(VarIntError::Truncated));
+        assert_eq!(decode_u128(&[0x80; 20]), Err(VarIntError::Overflow));
+    }
+
+    #[test]
+    fn test_zigzag_i128() {
+        let vectors = [
+            (0, 0),
+            (-1, 1),
+            (1, 2),
+            (-2, 3),
+            (i128::MAX, u128::MAX - 1),
+            (i128::MIN, u128::MAX),
+        ];
+        for (signed, unsigned) in vectors {
+            assert_eq!(zigzag_i128(signed), unsigned);
+            assert_eq!(unzigzag_i128(unsigned), signed);
+        }
+        // -1.00 as a fixed-point amount with 18 decimals.
+        let amount = -1_000_000_000_000_000_000i128;
+        let encoded = encode_i128(amount);
+        assert_eq!(encoded.len(), 9);
+        assert_eq!(decode_i128(&encoded), Ok((amount, 9)));
+        assert_eq!(encode_i128(-64), vec![0x7f]);
+        assert_eq!(decode_i128(&encode_i128(i128::MIN)), Ok((i128::MIN, 19)));
+    }
+}
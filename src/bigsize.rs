@@ -0,0 +1,105 @@
+use crate::VarIntError;
+
+/// Lightning Network BigSize
+///
+/// Same layout as Bitcoin's compactSize (a 0xfd, 0xfe or 0xff prefix for 2, 4 or 8 byte payloads)
+/// but the payload is in big-endian order, and values must be minimally encoded.
+///
+/// https://github.com/lightning/bolts/blob/master/01-messaging.md#appendix-a-bigsize-test-vectors
+pub struct BigSize;
+
+impl BigSize {
+    pub fn encode(value: u64) -> Vec<u8> {
+        match value {
+            x if x < 0xfd => vec![x as u8],
+            x if x <= 0xffff => {
+                let mut v = vec![0xfd];
+                v.extend_from_slice(&(x as u16).to_be_bytes());
+                v
+            }
+            x if x <= 0xffffffff => {
+                let mut v = vec![0xfe];
+                v.extend_from_slice(&(x as u32).to_be_bytes());
+                v
+            }
+            x => {
+                let mut v = vec![0xff];
+                v.extend_from_slice(&x.to_be_bytes());
+                v
+            }
+        }
+    }
+
+    /// Decodes a BigSize, returning the value with the number of bytes read.
+    pub fn decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let prefix = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let (size, min) = match prefix {
+            x if x < 0xfd => return Ok((x as u64, 1)),
+            0xfd => (2, 0xfd),
+            0xfe => (4, 0x10000),
+            _ => (8, 0x100000000),
+        };
+        let payload = bytes.get(1..1 + size).ok_or(VarIntError::Truncated)?;
+        let mut buf = [0u8; 8];
+        buf[8 - size..].copy_from_slice(payload);
+        let value = u64::from_be_bytes(buf);
+        if value < min {
+            return Err(VarIntError::NonCanonical);
+        }
+        Ok((value, 1 + size))
+    }
+
+    /// Returns the bytes needed to encode this value
+    pub fn get_size(value: u64) -> usize {
+        match value {
+            x if x < 0xfd => 1,
+            x if x <= 0xffff => 3,
+            x if x <= 0xffffffff => 5,
+            _ => 9,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::hex;
+
+    // BOLT #1 Appendix A
+    const VALID: &[(u64, &str)] = &[
+        (0, "00"),
+        (252, "fc"),
+        (253, "fd00fd"),
+        (65535, "fdffff"),
+        (65536, "fe00010000"),
+        (4294967295, "feffffffff"),
+        (4294967296, "ff0000000100000000"),
+        (18446744073709551615, "ffffffffffffffffff"),
+    ];
+
+    #[test]
+    fn test_bigsize_vectors() {
+        for (value, bytes) in VALID {
+            assert_eq!(BigSize::encode(*value), hex(bytes));
+            assert_eq!(
+                BigSize::decode(&hex(bytes)).unwrap(),
+                (*value, bytes.len() / 2)
+            );
+            assert_eq!(BigSize::get_size(*value), bytes.len() / 2);
+        }
+    }
+
+    #[test]
+    fn test_bigsize_not_canonical() {
+        for bytes in ["fd00fc", "fe0000ffff", "ff00000000ffffffff"] {
+            assert_eq!(BigSize::decode(&hex(bytes)), Err(VarIntError::NonCanonical));
+        }
+    }
+
+    #[test]
+    fn test_bigsize_short_read() {
+        for bytes in ["fd00", "feffff", "ffffffffff", "", "fd", "fe", "ff"] {
+            assert_eq!(BigSize::decode(&hex(bytes)), Err(VarIntError::Truncated));
+        }
+    }
+}
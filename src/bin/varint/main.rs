@@ -0,0 +1,693 @@
+//! Command line tool to encode and decode variable-length integers.
+//!
+//! ```text
+//! varint encode 515        # fd0302
+//! varint decode fd0302     # 515
+//! echo 1 2 3 | varint --codec leb128 encode
+//! varint transcode --from leb128 --to compactsize < in.bin > out.bin
+//! tail -f values.bin | varint stream decode --json
+//! varint diff old.bin new.bin
+//! varint --codec quic gen-vectors --count 1000 --seed 42 > quic.json
+//! varint bench --synthetic small
+//! varint annotate-tx 0100000001...
+//! ```
+
+use bitcoin_varint::bulk::decode_iter_with;
+use bitcoin_varint::delimited::{write_delimited_with, DelimitedReader};
+use bitcoin_varint::explain::{explain_with, Prefix};
+use bitcoin_varint::stats::Stats;
+use bitcoin_varint::tx::{walk_transaction, FieldKind};
+use bitcoin_varint::{transcode_stream, Codec, VarIntError};
+use std::io::{self, BufRead, BufWriter, IsTerminal, Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: varint [--codec <codec>] <command> [args...]
+
+Commands:
+  encode [VALUE...]   encode decimal (or 0x-prefixed hex) values, printing one hex string per value
+  decode [HEX...]     decode hex strings of concatenated values, printing one value per line
+  inspect [HEX...]    explain how the first value of each hex string is encoded
+  annotate-tx [HEX...]
+                      print raw transactions with every compactSize field highlighted and decoded
+  transcode --from <codec> --to <codec>
+                      re-encode the binary values of stdin to stdout
+  stream decode [--framed] [--json] [--max-len <n>]
+                      print the binary values of stdin as they arrive, one per line or as JSON lines
+                      with their offsets; with --framed, the hex payloads of length-prefixed frames
+                      (16 MiB at most by default), or JSON lines with their lengths
+  stream encode [--framed]
+                      write the values of stdin as binary as they arrive; with --framed, each line is
+                      a hex payload written length-prefixed
+  stats [FILE...]     summarize the binary values of each file (or stdin), with their size in other codecs
+  diff <FILE> <FILE>  compare the binary values of two files, exiting with an error when they differ
+  gen-vectors [--count <n>] [--seed <n>] [--format json|csv]
+                      print reproducible (value, hex) pairs, boundary values first, then random ones of
+                      every length (1000 and seed 0 by default)
+  bench <FILE> | --synthetic <uniform|small|large> [--count <n>]
+                      time encoding and decoding the values of a file, or of a generated sample
+                      (1000000 by default), with every codec
+
+Values are read from stdin when none is given on the command line.
+
+Codecs: compactsize (default), bigsize, leb128, vlq, quic";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("varint: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut codec = Codec::CompactSize;
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--codec" => {
+                let name = args.next().ok_or("--codec needs a value")?;
+                codec = parse_codec(&name)?;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => rest.push(arg),
+        }
+    }
+    if rest.is_empty() {
+        return Err(format!("missing command\n\n{}", USAGE));
+    }
+    let command = rest.remove(0);
+    match command.as_str() {
+        "transcode" => return transcode(&rest),
+        "stream" => return stream(codec, &rest),
+        "stats" => return stats(codec, &rest),
+        "diff" => return diff(codec, &rest),
+        "gen-vectors" => return gen_vectors(codec, &rest),
+        "bench" => return bench(codec, &rest),
+        _ => {}
+    }
+    let inputs = if rest.is_empty() { read_stdin()? } else { rest };
+
+    match command.as_str() {
+        "encode" => encode(codec, &inputs),
+        "decode" => decode(codec, &inputs),
+        "inspect" => inspect(codec, &inputs),
+        "annotate-tx" => annotate_tx(&inputs),
+        _ => Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    }
+}
+
+fn encode(codec: Codec, inputs: &[String]) -> Result<(), String> {
+    for input in inputs {
+        let value = parse_value(input)?;
+        let bytes = codec
+            .encode(value)
+            .map_err(|e| format!("cannot encode '{}': {}", input, e))?;
+        println!("{}", to_hex(&bytes));
+    }
+    Ok(())
+}
+
+fn decode(codec: Codec, inputs: &[String]) -> Result<(), String> {
+    for input in inputs {
+        let bytes = from_hex(input)?;
+        for value in decode_iter_with(&codec, &bytes) {
+            let value = value.map_err(|e| format!("cannot decode '{}': {}", input, e))?;
+            println!("{}", value);
+        }
+    }
+    Ok(())
+}
+
+fn inspect(codec: Codec, inputs: &[String]) -> Result<(), String> {
+    for (i, input) in inputs.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let bytes = from_hex(input)?;
+        let explanation =
+            explain_with(codec, &bytes).map_err(|e| format!("cannot decode '{}': {}", input, e))?;
+
+        let order = if codec == Codec::BigSize {
+            "big-endian"
+        } else {
+            "little-endian"
+        };
+        match explanation.prefix {
+            Prefix::Direct => println!(
+                "prefix     {:02x} (value stored in the prefix itself)",
+                bytes[0]
+            ),
+            Prefix::Marker { payload_len } => {
+                println!(
+                    "prefix     {:02x} ({}-byte {} payload follows)",
+                    bytes[0], payload_len, order
+                );
+                println!("payload    {}", to_hex(explanation.payload));
+            }
+            Prefix::Unprefixed => println!("bytes      {}", to_hex(explanation.payload)),
+            Prefix::LengthBits { len } => println!(
+                "prefix     {:02x} (top bits {:02b}: {}-byte big-endian value)",
+                bytes[0],
+                bytes[0] >> 6,
+                len
+            ),
+        }
+        let value = explanation.value;
+        println!("value      {} (0x{:x})", value, value);
+        println!("length     {} bytes", explanation.len);
+        if explanation.canonical {
+            println!("canonical  yes");
+        } else {
+            println!(
+                "canonical  no (shortest form is {})",
+                to_hex(&explanation.shortest)
+            );
+        }
+        println!("remaining  {} bytes", bytes.len() - explanation.len);
+    }
+    Ok(())
+}
+
+fn annotate_tx(inputs: &[String]) -> Result<(), String> {
+    let (highlight, reset) = if io::stdout().is_terminal() {
+        ("\x1b[1;33m", "\x1b[0m")
+    } else {
+        ("", "")
+    };
+    for (i, input) in inputs.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let tx = from_hex(input)?;
+        let layout =
+            walk_transaction(&tx).map_err(|e| format!("cannot walk transaction: {}", e))?;
+        let kind = if layout.segwit { "segwit" } else { "legacy" };
+        println!("{} transaction, {} bytes", kind, layout.len);
+        let mut pos = 0;
+        for field in &layout.fields {
+            if field.offset > pos {
+                println!("{:>6}  {}", pos, to_hex(&tx[pos..field.offset]));
+            }
+            let hex = to_hex(&tx[field.offset..field.offset + field.len]);
+            println!(
+                "{:>6}  {}{:<18}{}  {} = {}",
+                field.offset,
+                highlight,
+                hex,
+                reset,
+                field_name(field.kind),
+                field.value
+            );
+            pos = field.offset + field.len;
+        }
+        println!("{:>6}  {}", pos, to_hex(&tx[pos..layout.len]));
+        if layout.len < tx.len() {
+            println!(
+                "{:>6}  {}  (after the end of the transaction)",
+                layout.len,
+                to_hex(&tx[layout.len..])
+            );
+        }
+    }
+    Ok(())
+}
+
+fn field_name(kind: FieldKind) -> String {
+    match kind {
+        FieldKind::InputCount => "input count".to_string(),
+        FieldKind::ScriptSigLength { input } => format!("input {} scriptSig length", input),
+        FieldKind::OutputCount => "output count".to_string(),
+        FieldKind::ScriptPubKeyLength { output } => {
+            format!("output {} scriptPubKey length", output)
+        }
+        FieldKind::WitnessItemCount { input } => format!("input {} witness item count", input),
+        FieldKind::WitnessItemLength { input, item } => {
+            format!("input {} witness item {} length", input, item)
+        }
+    }
+}
+
+fn transcode(args: &[String]) -> Result<(), String> {
+    let (mut from, mut to) = (None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let codec = match arg.as_str() {
+            "--from" => &mut from,
+            "--to" => &mut to,
+            _ => return Err(format!("unexpected argument '{}' for transcode", arg)),
+        };
+        let name = args.next().ok_or(format!("{} needs a value", arg))?;
+        *codec = Some(parse_codec(name)?);
+    }
+    let from = from.ok_or("transcode needs --from")?;
+    let to = to.ok_or("transcode needs --to")?;
+
+    let mut output = BufWriter::new(io::stdout().lock());
+    transcode_stream(&mut io::stdin().lock(), &mut output, from, to)
+        .and_then(|_| output.flush())
+        .map_err(|e| format!("cannot transcode: {}", e))
+}
+
+fn stream(codec: Codec, args: &[String]) -> Result<(), String> {
+    let (mut framed, mut json, mut max_len) = (false, false, 16 << 20);
+    let mut args = args.iter();
+    let direction = args.next().ok_or("stream needs decode or encode")?;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--framed" => framed = true,
+            "--json" => json = true,
+            "--max-len" => {
+                max_len = parse_value(args.next().ok_or("--max-len needs a value")?)? as usize
+            }
+            _ => return Err(format!("unexpected argument '{}' for stream", arg)),
+        }
+    }
+    let mut output = BufWriter::new(io::stdout().lock());
+    let result = match (direction.as_str(), framed) {
+        ("decode", false) => stream_decode(codec, json, &mut output),
+        ("decode", true) => stream_decode_frames(codec, json, max_len, &mut output),
+        ("encode", _) => stream_encode(codec, framed, &mut output),
+        _ => return Err(format!("unknown stream direction '{}'", direction)),
+    };
+    match result {
+        // The reader of the pipe is gone, as with `| head`.
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| format!("cannot stream: {}", e)),
+    }
+}
+
+/// Prints the values of stdin, flushing after every read so that values show up as soon as they arrive.
+fn stream_decode(codec: Codec, json: bool, output: &mut impl Write) -> io::Result<()> {
+    let mut input = io::stdin().lock();
+    let mut buf = vec![0u8; 1 << 16];
+    let (mut filled, mut offset) = (0, 0u64);
+    loop {
+        let read = match input.read(&mut buf[filled..]) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        filled += read;
+        let mut pos = 0;
+        while pos < filled {
+            let (value, len) = match codec.decode(&buf[pos..filled]) {
+                Ok(decoded) => decoded,
+                // The rest of the value is in the next read.
+                Err(VarIntError::Truncated) if read > 0 => break,
+                Err(e) => {
+                    let message = format!("at byte {}: {}", offset, e);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                }
+            };
+            match json {
+                true => writeln!(output, "{{\"offset\":{},\"value\":{}}}", offset, value)?,
+                false => writeln!(output, "{}", value)?,
+            }
+            pos += len;
+            offset += len as u64;
+        }
+        output.flush()?;
+        buf.copy_within(pos..filled, 0);
+        filled -= pos;
+        if read == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Prints the payloads of the length-prefixed frames of stdin in hex, one per line.
+fn stream_decode_frames(
+    codec: Codec,
+    json: bool,
+    max_len: usize,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut reader = DelimitedReader::new(codec, io::stdin().lock(), max_len);
+    while let Some(payload) = reader.read_message()? {
+        match json {
+            true => writeln!(
+                output,
+                "{{\"len\":{},\"payload\":\"{}\"}}",
+                payload.len(),
+                to_hex(payload)
+            )?,
+            false => writeln!(output, "{}", to_hex(payload))?,
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Encodes the values of stdin, or its hex payloads with `framed`, one line at a time.
+fn stream_encode(codec: Codec, framed: bool, output: &mut impl Write) -> io::Result<()> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if framed {
+            let payload = from_hex(line.trim()).map_err(invalid)?;
+            write_delimited_with(&codec, &payload, output)?;
+        } else {
+            for input in line.split_whitespace() {
+                let value = parse_value(input).map_err(invalid)?;
+                let bytes = codec
+                    .encode(value)
+                    .map_err(|e| invalid(format!("cannot encode '{}': {}", input, e)))?;
+                output.write_all(&bytes)?;
+            }
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn stats(codec: Codec, files: &[String]) -> Result<(), String> {
+    if files.is_empty() {
+        let stats = Stats::read_from(&codec, &mut io::stdin().lock())
+            .map_err(|e| format!("cannot read stdin: {}", e))?;
+        print_stats(codec, &stats);
+    }
+    for (i, path) in files.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let stats = std::fs::File::open(path)
+            .and_then(|file| Stats::read_from(&codec, &mut io::BufReader::new(file)))
+            .map_err(|e| format!("cannot read '{}': {}", path, e))?;
+        println!("file       {}", path);
+        print_stats(codec, &stats);
+    }
+    Ok(())
+}
+
+fn print_stats(codec: Codec, stats: &Stats) {
+    println!("values     {}", stats.count);
+    println!("size       {} bytes", stats.total_len);
+    if let (Some(min), Some(max)) = (stats.min, stats.max) {
+        println!("min        {}", min);
+        println!("max        {}", max);
+    }
+    for (len, count) in &stats.len_histogram {
+        let share = *count as f64 * 100.0 / stats.count as f64;
+        println!("{:>2} bytes   {} ({:.1}%)", len, count, share);
+    }
+    for &other in Codec::ALL {
+        let projected = match stats.projected_len(other) {
+            Some(len) => format!("{} bytes", len),
+            None => "values out of range".to_string(),
+        };
+        let current = if other == codec { " (current)" } else { "" };
+        println!("{:<11} {}{}", codec_name(other), projected, current);
+    }
+}
+
+fn diff(codec: Codec, files: &[String]) -> Result<(), String> {
+    let [a, b] = files else {
+        return Err("diff needs two files".to_string());
+    };
+    let (bytes_a, bytes_b) = (read_file(a)?, read_file(b)?);
+    let (values_a, values_b) = (
+        decode_file(codec, a, &bytes_a)?,
+        decode_file(codec, b, &bytes_b)?,
+    );
+    let width = a.len().max(b.len()).max(9);
+
+    println!("{:<width$}  {} values", a, values_a.len());
+    println!("{:<width$}  {} values", b, values_b.len());
+    let first = values_a.iter().zip(&values_b).position(|(x, y)| x.0 != y.0);
+    if let Some(index) = first {
+        println!("first difference at value {}", index);
+        for (path, values) in [(a, &values_a), (b, &values_b)] {
+            let (value, offset) = values[index];
+            println!("{:<width$}  {} at byte {}", path, value, offset);
+        }
+    }
+    let common = values_a.len().min(values_b.len());
+    for (path, values, bytes) in [(a, &values_a, &bytes_a), (b, &values_b, &bytes_b)] {
+        if let Some((_, offset)) = values.get(common) {
+            let extra = values.len() - common;
+            let size = bytes.len() - offset;
+            println!(
+                "{:<width$}  {} extra values from value {}, {} bytes from byte {}",
+                path, extra, common, size, offset
+            );
+        }
+    }
+    if first.is_none() && values_a.len() == values_b.len() {
+        println!("identical values");
+        return Ok(());
+    }
+    Err("the values differ".to_string())
+}
+
+fn gen_vectors(codec: Codec, args: &[String]) -> Result<(), String> {
+    let (mut count, mut seed, mut json) = (1000, 0, true);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--count" => count = parse_value(value)? as usize,
+            "--seed" => seed = parse_value(value)?,
+            "--format" => {
+                json = match value.as_str() {
+                    "json" => true,
+                    "csv" => false,
+                    _ => return Err(format!("unknown format '{}'", value)),
+                }
+            }
+            _ => return Err(format!("unexpected argument '{}' for gen-vectors", arg)),
+        }
+    }
+
+    // Both sides of every power of two, and of the compactSize prefixes.
+    let mut values: Vec<u64> = (1..64).flat_map(|k| [(1 << k) - 1, 1 << k]).collect();
+    values.extend([0, 0xfc, 0xfd, u64::MAX]);
+    values.sort_unstable();
+    values.dedup();
+    let mut vectors: Vec<(u64, String)> = values
+        .into_iter()
+        .filter_map(|value| Some((value, to_hex(&codec.encode(value).ok()?))))
+        .take(count)
+        .collect();
+    let mut rng = SplitMix64(seed);
+    while vectors.len() < count {
+        // A width first, so that every encoded length comes up.
+        let bits = 1 + rng.next() % 64;
+        let value = rng.next() >> (64 - bits);
+        if let Ok(bytes) = codec.encode(value) {
+            vectors.push((value, to_hex(&bytes)));
+        }
+    }
+    let mut output = BufWriter::new(io::stdout().lock());
+    let name = codec_name(codec);
+    let result = if json {
+        writeln!(output, "[").and_then(|_| {
+            for (i, (value, hex)) in vectors.iter().enumerate() {
+                let comma = if i + 1 < vectors.len() { "," } else { "" };
+                writeln!(
+                    output,
+                    r#"  {{"codec": "{}", "value": "{}", "hex": "{}"}}{}"#,
+                    name, value, hex, comma
+                )?;
+            }
+            writeln!(output, "]")
+        })
+    } else {
+        writeln!(output, "codec,value,hex").and_then(|_| {
+            for (value, hex) in &vectors {
+                writeln!(output, "{},{},{}", name, value, hex)?;
+            }
+            Ok(())
+        })
+    };
+    result
+        .and_then(|_| output.flush())
+        .map_err(|e| format!("cannot write vectors: {}", e))
+}
+
+fn bench(codec: Codec, args: &[String]) -> Result<(), String> {
+    let (mut path, mut synthetic, mut count) = (None, None, 1_000_000);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--synthetic" => synthetic = Some(args.next().ok_or("--synthetic needs a value")?),
+            "--count" => count = parse_value(args.next().ok_or("--count needs a value")?)? as usize,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(format!("unexpected argument '{}' for bench", arg)),
+        }
+    }
+    let values: Vec<u64> = match (path, synthetic) {
+        (Some(path), None) => decode_file(codec, path, &read_file(path)?)?
+            .into_iter()
+            .map(|(value, _)| value)
+            .collect(),
+        (None, Some(dist)) => {
+            let mut rng = SplitMix64(0);
+            let value: fn(&mut SplitMix64) -> u64 = match dist.as_str() {
+                // Spread over every width, as gen-vectors does.
+                "uniform" => |rng| {
+                    let bits = 1 + rng.next() % 64;
+                    rng.next() >> (64 - bits)
+                },
+                // Mostly single byte values, as the lengths and counts of typical data.
+                "small" => |rng| match rng.next() % 20 {
+                    0 => rng.next() % 0x10000,
+                    _ => rng.next() % 0x7f,
+                },
+                "large" => |rng| rng.next(),
+                _ => return Err(format!("unknown distribution '{}'", dist)),
+            };
+            (0..count).map(|_| value(&mut rng)).collect()
+        }
+        _ => return Err("bench needs a file or --synthetic".to_string()),
+    };
+    if values.is_empty() {
+        return Err("no values to benchmark".to_string());
+    }
+
+    println!("{} values", values.len());
+    println!("codec         size  encode (M/s)  decode (M/s)     MB/s");
+    for &other in Codec::ALL {
+        let mut bytes = Vec::new();
+        let encoded = values
+            .iter()
+            .try_for_each(|&value| other.encode_append(value, &mut bytes).map(|_| ()));
+        if encoded.is_err() {
+            println!("{:<11}   values out of range", codec_name(other));
+            continue;
+        }
+        let encode = best_of(|| {
+            let mut out = Vec::with_capacity(bytes.len());
+            for &value in &values {
+                let _ = other.encode_append(std::hint::black_box(value), &mut out);
+            }
+            std::hint::black_box(out);
+        });
+        let decode = best_of(|| {
+            let (mut pos, mut sum) = (0, 0u64);
+            while let Ok((value, len)) = other.decode(std::hint::black_box(&bytes[pos..])) {
+                sum = sum.wrapping_add(value);
+                pos += len;
+            }
+            std::hint::black_box(sum);
+        });
+        let per_second = |secs: f64| values.len() as f64 / secs / 1e6;
+        println!(
+            "{:<11} {:>6}  {:>12.1}  {:>12.1}  {:>7.1}",
+            codec_name(other),
+            bytes.len(),
+            per_second(encode),
+            per_second(decode),
+            bytes.len() as f64 / decode / 1e6
+        );
+    }
+    Ok(())
+}
+
+/// The shortest of a few runs of `run`, in seconds, the least disturbed by the rest of the system.
+fn best_of(mut run: impl FnMut()) -> f64 {
+    (0..5)
+        .map(|_| {
+            let start = std::time::Instant::now();
+            run();
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::MAX, f64::min)
+}
+
+/// splitmix64, which spreads any seed, 0 included.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Decodes every value of a file, with the offset of each.
+fn decode_file(codec: Codec, path: &str, bytes: &[u8]) -> Result<Vec<(u64, usize)>, String> {
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let (value, len) = codec.decode(&bytes[pos..]).map_err(|e| {
+            format!(
+                "cannot decode '{}' at value {}, byte {}: {}",
+                path,
+                values.len(),
+                pos,
+                e
+            )
+        })?;
+        values.push((value, pos));
+        pos += len;
+    }
+    Ok(values)
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("cannot read '{}': {}", path, e))
+}
+
+fn codec_name(codec: Codec) -> &'static str {
+    match codec {
+        Codec::CompactSize => "compactsize",
+        Codec::BigSize => "bigsize",
+        Codec::Leb128 => "leb128",
+        Codec::Vlq => "vlq",
+        Codec::Quic => "quic",
+        _ => "unknown",
+    }
+}
+
+/// Parses a decimal value, or a hexadecimal one when prefixed by `0x`.
+fn parse_value(input: &str) -> Result<u64, String> {
+    match input.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => input.parse(),
+    }
+    .map_err(|e| format!("invalid value '{}': {}", input, e))
+}
+
+fn parse_codec(name: &str) -> Result<Codec, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "compactsize" | "compact-size" | "varint" => Ok(Codec::CompactSize),
+        "bigsize" => Ok(Codec::BigSize),
+        "leb128" => Ok(Codec::Leb128),
+        "vlq" => Ok(Codec::Vlq),
+        "quic" => Ok(Codec::Quic),
+        _ => Err(format!("unknown codec '{}'", name)),
+    }
+}
+
+fn read_stdin() -> Result<Vec<String>, String> {
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| format!("cannot read stdin: {}", e))?;
+    Ok(input.split_whitespace().map(String::from).collect())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex '{}'", hex));
+    }
+    Ok((0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect())
+}
@@ -0,0 +1,222 @@
+use crate::VarInt;
+use std::io::{self, Read};
+use std::ops::Range;
+
+/// Streaming reader over a serialized block, yielding the byte span of each transaction.
+///
+/// Transactions are skipped by following their compactSize fields, so only a few bytes are buffered
+/// at a time whatever the size of the block. This is enough to index `blk*.dat` files
+/// (after stripping their magic and size prefix) without deserializing anything.
+///
+/// ```
+/// use bitcoin_varint::block::BlockReader;
+///
+/// # fn spans(block: &[u8]) -> std::io::Result<()> {
+/// let mut reader = BlockReader::new(block)?;
+/// for span in reader.by_ref() {
+///     let span = span?;
+///     println!("transaction at {}..{}", span.start, span.end);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct BlockReader<R> {
+    reader: Counting<R>,
+    header: [u8; 80],
+    tx_count: u64,
+    remaining: u64,
+}
+
+/// Keeps track of the position in the block.
+struct Counting<R> {
+    inner: R,
+    pos: u64,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read> BlockReader<R> {
+    /// Reads the 80 bytes header and the transaction count.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 80];
+        reader.read_exact(&mut header)?;
+        let mut block = BlockReader {
+            reader: Counting {
+                inner: reader,
+                pos: 80,
+            },
+            header,
+            tx_count: 0,
+            remaining: 0,
+        };
+        block.tx_count = block.read_compact_size()?;
+        block.remaining = block.tx_count;
+        Ok(block)
+    }
+
+    pub fn header(&self) -> &[u8; 80] {
+        &self.header
+    }
+
+    /// Number of transactions announced by the block.
+    pub fn tx_count(&self) -> u64 {
+        self.tx_count
+    }
+
+    /// Returns the underlying reader, positioned after the last transaction read.
+    pub fn into_inner(self) -> R {
+        self.reader.inner
+    }
+
+    fn read_compact_size(&mut self) -> io::Result<u64> {
+        VarInt::read_from(&mut self.reader)
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.reader).take(len), &mut io::sink())?;
+        if skipped < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8];
+        self.reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    /// Skips one transaction, following the same steps as Bitcoin Core's `UnserializeTransaction`.
+    fn skip_transaction(&mut self) -> io::Result<()> {
+        self.skip(4)?;
+        let mut inputs = self.read_compact_size()?;
+        let mut flags = 0;
+        if inputs == 0 {
+            // Either the segwit marker or a transaction without inputs.
+            flags = self.read_byte()?;
+            if flags != 0 {
+                inputs = self.read_compact_size()?;
+                self.skip_inputs(inputs)?;
+                self.skip_outputs()?;
+            }
+        } else {
+            self.skip_inputs(inputs)?;
+            self.skip_outputs()?;
+        }
+        if flags & 1 != 0 {
+            for _ in 0..inputs {
+                let items = self.read_compact_size()?;
+                for _ in 0..items {
+                    let len = self.read_compact_size()?;
+                    self.skip(len)?;
+                }
+            }
+        }
+        self.skip(4)
+    }
+
+    fn skip_inputs(&mut self, inputs: u64) -> io::Result<()> {
+        for _ in 0..inputs {
+            self.skip(36)?;
+            let len = self.read_compact_size()?;
+            self.skip(len.saturating_add(4))?;
+        }
+        Ok(())
+    }
+
+    fn skip_outputs(&mut self) -> io::Result<()> {
+        let outputs = self.read_compact_size()?;
+        for _ in 0..outputs {
+            self.skip(8)?;
+            let len = self.read_compact_size()?;
+            self.skip(len)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for BlockReader<R> {
+    /// Span of the transaction, in bytes from the start of the block.
+    type Item = io::Result<Range<u64>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let start = self.reader.pos;
+        match self.skip_transaction() {
+            Ok(()) => {
+                self.remaining -= 1;
+                Some(Ok(start..self.reader.pos))
+            }
+            Err(e) => {
+                self.remaining = 0;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::hex;
+
+    fn legacy_tx() -> String {
+        let input = format!("{}ffffffff", "00".repeat(32)) + "0403ffff00" + "ffffffff";
+        let output = "00f2052a01000000".to_string() + "0151";
+        format!("01000000{}{}{}{}00000000", "01", input, "01", output)
+    }
+
+    fn segwit_tx() -> String {
+        let input = format!("{}00000000", "11".repeat(32)) + "00" + "ffffffff";
+        let output = "e803000000000000".to_string() + "00";
+        let witness = "0120".to_string() + &"aa".repeat(32);
+        format!(
+            "020000000001{}{}{}{}{}00000000",
+            "01", input, "01", output, witness
+        )
+    }
+
+    #[test]
+    fn test_block_reader_spans() {
+        let block = hex(&format!(
+            "{}02{}{}",
+            "00".repeat(80),
+            legacy_tx(),
+            segwit_tx()
+        ));
+        let mut reader = BlockReader::new(block.as_slice()).unwrap();
+        assert_eq!(reader.tx_count(), 2);
+        let spans: Vec<_> = reader.by_ref().map(|span| span.unwrap()).collect();
+        let legacy_len = legacy_tx().len() as u64 / 2;
+        assert_eq!(spans[0], 81..81 + legacy_len);
+        assert_eq!(spans[1], 81 + legacy_len..block.len() as u64);
+        assert!(reader.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_block_reader_non_canonical_count() {
+        let block = hex(&format!("{}fd0100{}", "00".repeat(80), legacy_tx()));
+        let spans: Vec<_> = BlockReader::new(block.as_slice()).unwrap().collect();
+        assert_eq!(spans[0].as_ref().unwrap().start, 83);
+    }
+
+    #[test]
+    fn test_block_reader_truncated() {
+        let mut block = hex(&format!("{}02{}", "00".repeat(80), legacy_tx()));
+        block.extend_from_slice(&[0x01, 0x00, 0x00]);
+        let mut reader = BlockReader::new(block.as_slice()).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+        assert!(BlockReader::new(&[0u8; 79][..]).is_err());
+    }
+}
@@ -0,0 +1,1189 @@
+use crate::codec::{for_each_value, WINDOW_LEN};
+use crate::{DecodeError, VarCodec, VarInt, VarIntError};
+use std::fmt;
+use std::io::{self, Read};
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+
+/// Encodes every value of `values` as concatenated compactSizes.
+pub fn encode_all(values: &[u64]) -> Vec<u8> {
+    let mut result = Vec::new();
+    encode_all_into(values, &mut result);
+    result
+}
+
+/// Appends the compactSize encodings of `values` to `out`, returning the number of bytes written.
+///
+/// Values are taken in blocks: the length classes of a block are computed first, in a loop the compiler
+/// can vectorize, then every value is stored as a full 9-byte word (prefix and little-endian payload) at its
+/// offset, the next value overwriting the bytes it didn't use. This avoids a branch per value, which
+/// mispredicts on mixed sizes; `cargo bench --bench encode` compares it with the loop over
+/// [`VarInt::encode_append`]. wasm32 builds enabling simd128 (`-C target-feature=+simd128`) classify two
+/// values per instruction, other builds fall back to the scalar loop.
+///
+/// ```
+/// use bitcoin_varint::bulk::encode_all_into;
+///
+/// let mut out = vec![0x02];
+/// assert_eq!(encode_all_into(&[1, 300], &mut out), 4);
+/// assert_eq!(out, [0x02, 0x01, 0xfd, 0x2c, 0x01]);
+/// ```
+pub fn encode_all_into(values: &[u64], out: &mut Vec<u8>) -> usize {
+    const BLOCK: usize = 8;
+    let total = total_encoded_size_slice(values) as usize;
+    let mut pos = out.len();
+    // Room for the unused bytes of the word of the last value.
+    out.resize(pos + total + 8, 0);
+    for block in values.chunks(BLOCK) {
+        let mut classes = [0u8; BLOCK];
+        length_classes(block, &mut classes);
+        for (class, value) in classes.iter().zip(block) {
+            let single = 0u8.wrapping_sub((*class == 0) as u8);
+            let word = &mut out[pos..pos + 9];
+            word[0] = (*value as u8 & single) | ((0xfc + class) & !single);
+            word[1..].copy_from_slice(&value.to_le_bytes());
+            pos += 1 + 2 * *class as usize + 2 * (*class == 3) as usize;
+        }
+    }
+    out.truncate(pos);
+    total
+}
+
+/// Sets every class to 0, 1, 2 or 3 for the encoded lengths 1, 3, 5 and 9 of the value at its index.
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+#[inline]
+fn length_classes(values: &[u64], classes: &mut [u8]) {
+    for (class, value) in classes.iter_mut().zip(values) {
+        *class = (*value > 0xfc) as u8 + (*value > 0xffff) as u8 + (*value > 0xffff_ffff) as u8;
+    }
+}
+
+/// [`length_classes`] comparing two values at a time. simd128 only has signed 64-bit comparisons, so the
+/// values and the limits are shifted by `i64::MIN` first.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[inline]
+fn length_classes(values: &[u64], classes: &mut [u8]) {
+    use core::arch::wasm32::*;
+
+    let bias = u64x2_splat(1 << 63);
+    let limits = [0xfc, 0xffff, 0xffff_ffff].map(|limit| v128_xor(u64x2_splat(limit), bias));
+    let mut pairs = values.chunks_exact(2);
+    let mut outs = classes.chunks_exact_mut(2);
+    for (pair, out) in (&mut pairs).zip(&mut outs) {
+        let shifted = v128_xor(u64x2(pair[0], pair[1]), bias);
+        let mut class = i64x2_splat(0);
+        for limit in limits {
+            // Lanes above the limit are all ones, -1.
+            class = i64x2_sub(class, i64x2_gt(shifted, limit));
+        }
+        out[0] = i64x2_extract_lane::<0>(class) as u8;
+        out[1] = i64x2_extract_lane::<1>(class) as u8;
+    }
+    for (class, value) in outs.into_remainder().iter_mut().zip(pairs.remainder()) {
+        *class = (*value > 0xfc) as u8 + (*value > 0xffff) as u8 + (*value > 0xffff_ffff) as u8;
+    }
+}
+
+/// Number of single-byte compactSizes at the start of `bytes`, looking at 16 bytes at most.
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+#[inline]
+fn single_byte_run(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(16)
+        .take_while(|byte| **byte < 0xfd)
+        .count()
+}
+
+/// [`single_byte_run`] comparing the 16 bytes at once.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[inline]
+fn single_byte_run(bytes: &[u8]) -> usize {
+    use core::arch::wasm32::*;
+
+    let Some(chunk) = bytes.get(..16) else {
+        return bytes.iter().take_while(|byte| **byte < 0xfd).count();
+    };
+    // SAFETY: `chunk` holds 16 bytes, and `v128_load` has no alignment requirement.
+    let chunk = unsafe { v128_load(chunk.as_ptr().cast::<v128>()) };
+    let singles = u8x16_bitmask(u8x16_lt(chunk, u8x16_splat(0xfd)));
+    (!singles).trailing_zeros() as usize
+}
+
+/// Encodes the values of `values` as concatenated compactSizes into an uninitialized buffer, so that large
+/// buffers don't have to be zeroed first.
+///
+/// Stops before the first value that doesn't fit, returning the initialized prefix of `out` with the number of
+/// values written. [`total_encoded_size_slice`] gives the length needed to write them all.
+///
+/// ```
+/// use bitcoin_varint::bulk::encode_uninit;
+///
+/// let mut buffer = Vec::with_capacity(1 << 20);
+/// let (written, count) = encode_uninit(&[1, 300], buffer.spare_capacity_mut());
+/// assert_eq!((&*written, count), (&[0x01, 0xfd, 0x2c, 0x01][..], 2));
+/// ```
+pub fn encode_uninit<'a>(values: &[u64], out: &'a mut [MaybeUninit<u8>]) -> (&'a mut [u8], usize) {
+    let mut pos = 0;
+    let mut count = 0;
+    for value in values {
+        let encoded = EncodedVarInt::new(*value);
+        let Some(dst) = out.get_mut(pos..pos + encoded.len()) else {
+            break;
+        };
+        for (dst, src) in dst.iter_mut().zip(encoded.iter()) {
+            dst.write(*src);
+        }
+        pos += encoded.len();
+        count += 1;
+    }
+    // SAFETY: every byte of `out[..pos]` was written above, and `MaybeUninit<u8>` has the layout of `u8`.
+    let written = unsafe { std::slice::from_raw_parts_mut(out.as_mut_ptr().cast::<u8>(), pos) };
+    (written, count)
+}
+
+/// Total number of bytes of the compactSize encodings of `values`, to reserve buffers or write headers
+/// with exact sizes before encoding.
+pub fn total_encoded_size(values: impl IntoIterator<Item = u64>) -> u64 {
+    values.into_iter().map(encoded_len_branchless).sum()
+}
+
+/// [`total_encoded_size`] over a slice, which the compiler can vectorize.
+pub fn total_encoded_size_slice(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .map(|value| encoded_len_branchless(*value))
+        .sum()
+}
+
+/// [`VarInt::encoded_len`] as a sum of comparisons.
+#[inline]
+fn encoded_len_branchless(value: u64) -> u64 {
+    1 + 2 * (value > 0xfc) as u64 + 2 * (value > 0xffff) as u64 + 4 * (value > 0xffff_ffff) as u64
+}
+
+/// Decodes a buffer of concatenated compactSizes.
+///
+/// Errors tell where the failing value starts, see [`DecodeError`].
+pub fn decode_all(bytes: &[u8]) -> Result<Vec<u64>, DecodeError> {
+    decode_all_with(&VarInt, bytes)
+}
+
+/// Encodes every value of `values` with `codec`, concatenated.
+pub fn encode_all_with<C: VarCodec + ?Sized>(
+    codec: &C,
+    values: &[u64],
+) -> Result<Vec<u8>, VarIntError> {
+    let mut result = Vec::with_capacity(values.len());
+    for value in values {
+        codec.encode_append(*value, &mut result)?;
+    }
+    Ok(result)
+}
+
+/// Decodes a buffer of values concatenated with `codec`.
+pub fn decode_all_with<C: VarCodec + ?Sized>(
+    codec: &C,
+    bytes: &[u8],
+) -> Result<Vec<u64>, DecodeError> {
+    decode_iter_with(codec, bytes).collect()
+}
+
+/// Decodes concatenated compactSizes into `out` without allocating, until either is exhausted.
+///
+/// Returns the number of values written with the number of bytes read, so that a buffer longer than `out`
+/// can be decoded in batches by calling again on the rest.
+///
+/// Runs of single-byte values are copied without decoding each one, 16 bytes at a time with simd128 on
+/// wasm32 builds enabling it (`-C target-feature=+simd128`).
+pub fn decode_into(bytes: &[u8], out: &mut [u64]) -> Result<(usize, usize), DecodeError> {
+    let mut iter = decode_iter(bytes);
+    let mut filled = 0;
+    loop {
+        let run = single_byte_run(&bytes[iter.pos..]).min(out.len() - filled);
+        if run > 0 {
+            for (slot, byte) in out[filled..filled + run].iter_mut().zip(&bytes[iter.pos..]) {
+                *slot = *byte as u64;
+            }
+            iter.pos += run;
+            iter.index += run;
+            filled += run;
+            continue;
+        }
+        let Some(slot) = out.get_mut(filled) else {
+            break;
+        };
+        let Some(value) = iter.next() else {
+            break;
+        };
+        *slot = value?;
+        filled += 1;
+    }
+    Ok((filled, iter.pos))
+}
+
+/// [`decode_into`] with values encoded by `codec`.
+pub fn decode_into_with<C: VarCodec + ?Sized>(
+    codec: &C,
+    bytes: &[u8],
+    out: &mut [u64],
+) -> Result<(usize, usize), DecodeError> {
+    let mut iter = decode_iter_with(codec, bytes);
+    let mut filled = 0;
+    // `out` comes first so that no value is decoded once it is full.
+    for (slot, value) in out.iter_mut().zip(&mut iter) {
+        *slot = value?;
+        filled += 1;
+    }
+    Ok((filled, iter.pos))
+}
+
+/// Decodes the first `N` compactSizes of a buffer into an array, returning it with the number of bytes read,
+/// for messages starting with a fixed number of fields.
+///
+/// A buffer holding fewer than `N` values fails with [`VarIntError::Truncated`] at its end.
+///
+/// ```
+/// use bitcoin_varint::bulk::{decode_n, encode_all};
+///
+/// let bytes = encode_all(&[70016, 1, 1700000000, 42]);
+/// let ([version, services, timestamp], len) = decode_n(&bytes).unwrap();
+/// assert_eq!((version, services, timestamp, len), (70016, 1, 1700000000, 11));
+/// ```
+pub fn decode_n<const N: usize>(bytes: &[u8]) -> Result<([u64; N], usize), DecodeError> {
+    decode_n_with(&VarInt, bytes)
+}
+
+/// [`decode_n`] with values encoded by `codec`.
+pub fn decode_n_with<const N: usize, C: VarCodec + ?Sized>(
+    codec: &C,
+    bytes: &[u8],
+) -> Result<([u64; N], usize), DecodeError> {
+    let mut values = [0u64; N];
+    let (filled, read) = decode_into_with(codec, bytes, &mut values)?;
+    if filled < N {
+        return Err(DecodeError::new(
+            VarIntError::Truncated,
+            bytes,
+            read,
+            filled,
+        ));
+    }
+    Ok((values, read))
+}
+
+/// Folds the values of a buffer of concatenated compactSizes into an accumulator, in one pass and without
+/// collecting them.
+///
+/// ```
+/// use bitcoin_varint::bulk::{encode_all, fold};
+///
+/// let bytes = encode_all(&[7, 300, 2]);
+/// assert_eq!(fold(&bytes, 0u128, |sum, v| sum + v as u128), Ok(309));
+/// assert_eq!(fold(&bytes, None, |max: Option<u64>, v| max.max(Some(v))), Ok(Some(300)));
+/// ```
+pub fn fold<B>(bytes: &[u8], init: B, f: impl FnMut(B, u64) -> B) -> Result<B, DecodeError> {
+    fold_with(&VarInt, bytes, init, f)
+}
+
+/// [`fold`] over values concatenated with `codec`.
+pub fn fold_with<C: VarCodec + ?Sized, B>(
+    codec: &C,
+    bytes: &[u8],
+    init: B,
+    mut f: impl FnMut(B, u64) -> B,
+) -> Result<B, DecodeError> {
+    decode_iter_with(codec, bytes).try_fold(init, |acc, value| Ok(f(acc, value?)))
+}
+
+/// [`fold`] over the values read from `reader`, encoded with `codec`, through a fixed-size buffer.
+///
+/// Invalid input is an [`io::ErrorKind::InvalidData`] error, and input ending within a value an
+/// [`io::ErrorKind::UnexpectedEof`] one.
+pub fn fold_stream_with<C: VarCodec + ?Sized, R: Read, B>(
+    codec: &C,
+    reader: &mut R,
+    init: B,
+    mut f: impl FnMut(B, u64) -> B,
+) -> io::Result<B> {
+    let mut acc = Some(init);
+    for_each_value(codec, reader, |value, _| {
+        acc = acc.take().map(|acc| f(acc, value));
+        Ok(())
+    })?;
+    Ok(acc.expect("the accumulator is put back after every value"))
+}
+
+/// Lazily decodes a buffer of concatenated compactSizes.
+pub fn decode_iter(bytes: &[u8]) -> DecodeIter<'_, VarInt> {
+    decode_iter_with(&VarInt, bytes)
+}
+
+/// Lazily decodes a buffer of values concatenated with `codec`.
+pub fn decode_iter_with<'a, C: VarCodec + ?Sized>(
+    codec: &'a C,
+    bytes: &'a [u8],
+) -> DecodeIter<'a, C> {
+    DecodeIter {
+        codec,
+        bytes,
+        pos: 0,
+        index: 0,
+    }
+}
+
+/// Iterator over concatenated values, see [`decode_iter`].
+///
+/// Stops after the first error.
+pub struct DecodeIter<'a, C: ?Sized> {
+    codec: &'a C,
+    bytes: &'a [u8],
+    pos: usize,
+    index: usize,
+}
+
+impl<C: VarCodec + ?Sized> Iterator for DecodeIter<'_, C> {
+    type Item = Result<u64, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        match self.codec.decode(&self.bytes[self.pos..]) {
+            Ok((value, len)) => {
+                self.pos += len;
+                self.index += 1;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                let error = DecodeError::new(e, self.bytes, self.pos, self.index)
+                    .with_expected(self.codec.encoded_len_at(&self.bytes[self.pos..]));
+                self.pos = self.bytes.len();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl<C: VarCodec + ?Sized> DecodeIter<'_, C> {
+    /// Byte offset of the next value.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves past the next `n` values without decoding them, see [`skip_varints_with`].
+    ///
+    /// Like [`next`](Iterator::next), stops the iterator on an error.
+    pub fn skip_values(&mut self, n: usize) -> Result<(), DecodeError> {
+        match skip_at(self.codec, self.bytes, self.pos, self.index, n) {
+            Ok(pos) => {
+                self.pos = pos;
+                self.index += n;
+                Ok(())
+            }
+            Err(e) => {
+                self.pos = self.bytes.len();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Lazily decodes a buffer of concatenated compactSizes, yielding the byte offset, value and encoded length of
+/// each, to index the values of a buffer by position.
+///
+/// ```
+/// use bitcoin_varint::bulk::{encode_all, iter_with_offsets};
+///
+/// let bytes = encode_all(&[1, 300, 2]);
+/// let values: Vec<_> = iter_with_offsets(&bytes).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(values, [(0, 1, 1), (1, 300, 3), (4, 2, 1)]);
+/// ```
+pub fn iter_with_offsets(bytes: &[u8]) -> OffsetIter<'_, VarInt> {
+    iter_with_offsets_with(&VarInt, bytes)
+}
+
+/// [`iter_with_offsets`] over values concatenated with `codec`.
+pub fn iter_with_offsets_with<'a, C: VarCodec + ?Sized>(
+    codec: &'a C,
+    bytes: &'a [u8],
+) -> OffsetIter<'a, C> {
+    OffsetIter {
+        inner: decode_iter_with(codec, bytes),
+    }
+}
+
+/// Iterator over concatenated values with their positions, see [`iter_with_offsets`].
+///
+/// Stops after the first error.
+pub struct OffsetIter<'a, C: ?Sized> {
+    inner: DecodeIter<'a, C>,
+}
+
+impl<C: VarCodec + ?Sized> Iterator for OffsetIter<'_, C> {
+    /// Byte offset, value and encoded length.
+    type Item = Result<(usize, u64, usize), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.inner.pos;
+        let value = self.inner.next()?;
+        Some(value.map(|value| (offset, value, self.inner.pos - offset)))
+    }
+}
+
+/// Number of bytes taken by the first `n` of concatenated compactSizes, found from the prefix byte of every
+/// value without reading their payloads.
+///
+/// Non-canonical encodings are skipped like any other: decode the values to reject them.
+///
+/// ```
+/// use bitcoin_varint::bulk::{encode_all, skip_varints};
+///
+/// let bytes = encode_all(&[1, 300, 70_000, 2]);
+/// assert_eq!(skip_varints(&bytes, 3), Ok(1 + 3 + 5));
+/// ```
+pub fn skip_varints(bytes: &[u8], n: usize) -> Result<usize, DecodeError> {
+    skip_varints_with(&VarInt, bytes, n)
+}
+
+/// [`skip_varints`] with values encoded by `codec`, whose lengths are given by [`VarCodec::encoded_len_at`]
+/// or, when it can't tell, by decoding them.
+pub fn skip_varints_with<C: VarCodec + ?Sized>(
+    codec: &C,
+    bytes: &[u8],
+    n: usize,
+) -> Result<usize, DecodeError> {
+    skip_at(codec, bytes, 0, 0, n)
+}
+
+/// Offset past the `n` values starting at `pos`, the first of them being value `index`.
+fn skip_at<C: VarCodec + ?Sized>(
+    codec: &C,
+    bytes: &[u8],
+    mut pos: usize,
+    index: usize,
+    n: usize,
+) -> Result<usize, DecodeError> {
+    for i in 0..n {
+        let rest = &bytes[pos..];
+        let len = match codec.encoded_len_at(rest) {
+            Some(len) if len <= rest.len() => Ok(len),
+            Some(_) => Err(VarIntError::Truncated),
+            None => codec.decode(rest).map(|(_, len)| len),
+        };
+        pos += len.map_err(|e| {
+            DecodeError::new(e, bytes, pos, index + i).with_expected(codec.encoded_len_at(rest))
+        })?;
+    }
+    Ok(pos)
+}
+
+/// Advances `reader` past `n` concatenated compactSizes, returning the number of bytes skipped.
+///
+/// See [`skip_varints_stream_with`].
+pub fn skip_varints_stream<R: Read>(reader: &mut R, n: u64) -> io::Result<u64> {
+    skip_varints_stream_with(&VarInt, reader, n)
+}
+
+/// Advances `reader` past `n` values encoded with `codec`, returning the number of bytes skipped.
+///
+/// Once the first bytes of a value tell its length, the rest is discarded unread; only the values of codecs
+/// for which [`VarCodec::encoded_len_at`] can't tell are decoded. Nothing past the last value is consumed,
+/// and an end of input before it fails with [`io::ErrorKind::UnexpectedEof`].
+pub fn skip_varints_stream_with<C: VarCodec + ?Sized, R: Read>(
+    codec: &C,
+    reader: &mut R,
+    n: u64,
+) -> io::Result<u64> {
+    let mut prefix = [0u8; WINDOW_LEN];
+    let max_len = codec.max_len().min(WINDOW_LEN);
+    let mut skipped = 0;
+    for _ in 0..n {
+        reader.read_exact(&mut prefix[..1])?;
+        let mut read = 1;
+        let len = loop {
+            if let Some(len) = codec.encoded_len_at(&prefix[..read]) {
+                break len.max(read);
+            }
+            match codec.decode(&prefix[..read]) {
+                Ok((_, len)) => break len,
+                Err(VarIntError::Truncated) if read < max_len => {
+                    reader.read_exact(&mut prefix[read..read + 1])?;
+                    read += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        let rest = (len - read) as u64;
+        if io::copy(&mut reader.by_ref().take(rest), &mut io::sink())? < rest {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        skipped += len as u64;
+    }
+    Ok(skipped)
+}
+
+/// A compactSize encoded on the stack, dereferencing to its bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodedVarInt {
+    bytes: [u8; 9],
+    len: u8,
+}
+
+impl EncodedVarInt {
+    pub fn new(value: u64) -> Self {
+        let len = VarInt::encoded_len(value);
+        let mut bytes = [0u8; 9];
+        match len {
+            1 => bytes[0] = value as u8,
+            3 => {
+                bytes[0] = 0xfd;
+                bytes[1..3].copy_from_slice(&(value as u16).to_le_bytes());
+            }
+            5 => {
+                bytes[0] = 0xfe;
+                bytes[1..5].copy_from_slice(&(value as u32).to_le_bytes());
+            }
+            _ => {
+                bytes[0] = 0xff;
+                bytes[1..].copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        EncodedVarInt {
+            bytes,
+            len: len as u8,
+        }
+    }
+
+    /// The encoded value.
+    pub fn value(&self) -> u64 {
+        match self.len {
+            1 => self.bytes[0] as u64,
+            // The bytes past the encoding are zero.
+            _ => u64::from_le_bytes(self.bytes[1..].try_into().unwrap()),
+        }
+    }
+}
+
+/// The encoded bytes in hex, after `0x` with `{:#x}`.
+impl fmt::LowerHex for EncodedVarInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl fmt::UpperHex for EncodedVarInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.iter().try_for_each(|byte| write!(f, "{:02X}", byte))
+    }
+}
+
+/// The value with its encoding, as in `515 (0xfd0302, 3 bytes)`.
+impl fmt::Display for EncodedVarInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = if self.len == 1 { "byte" } else { "bytes" };
+        write!(f, "{} ({:#x}, {} {})", self.value(), self, self.len, unit)
+    }
+}
+
+impl Deref for EncodedVarInt {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl AsRef<[u8]> for EncodedVarInt {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Lazily encodes the values of `values` as compactSizes, one at a time and without allocating.
+pub fn encode_iter<I: IntoIterator<Item = u64>>(values: I) -> impl Iterator<Item = EncodedVarInt> {
+    values.into_iter().map(EncodedVarInt::new)
+}
+
+/// [`Read`] adapter yielding the compactSize encoding of the values of an iterator as it is read,
+/// so that a long sequence can be piped to a sink with [`io::copy`] without being buffered.
+///
+/// [`EncodeReader::with_codec`] encodes the values with another [`VarCodec`].
+///
+/// ```
+/// use bitcoin_varint::bulk::EncodeReader;
+///
+/// let mut out = Vec::new();
+/// std::io::copy(&mut EncodeReader::new(250..254u64), &mut out).unwrap();
+/// assert_eq!(out, vec![0xfa, 0xfb, 0xfc, 0xfd, 0xfd, 0x00]);
+/// ```
+pub struct EncodeReader<I, C = VarInt> {
+    values: I,
+    codec: C,
+    /// The encoding of the value being read, and how much of it has already been returned.
+    current: Vec<u8>,
+    pos: usize,
+    /// A value the codec failed to encode after a read had returned bytes, reported by the next read.
+    error: Option<VarIntError>,
+}
+
+impl<I: Iterator<Item = u64>> EncodeReader<I> {
+    pub fn new(values: impl IntoIterator<IntoIter = I>) -> Self {
+        EncodeReader::with_codec(VarInt, values)
+    }
+}
+
+impl<I: Iterator<Item = u64>, C: VarCodec> EncodeReader<I, C> {
+    /// Encodes the values with `codec`. A value it can't represent fails the read that reaches it with
+    /// [`io::ErrorKind::InvalidInput`], and reading on skips it.
+    pub fn with_codec(codec: C, values: impl IntoIterator<IntoIter = I>) -> Self {
+        let current = Vec::with_capacity(codec.max_len());
+        EncodeReader {
+            values: values.into_iter(),
+            codec,
+            current,
+            pos: 0,
+            error: None,
+        }
+    }
+}
+
+impl<I: Iterator<Item = u64>, C: VarCodec> Read for EncodeReader<I, C> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(e) = self.error.take() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, e));
+        }
+        let mut written = 0;
+        while written < buf.len() {
+            if self.pos == self.current.len() {
+                let Some(value) = self.values.next() else {
+                    break;
+                };
+                self.current.clear();
+                self.pos = 0;
+                if let Err(e) = self.codec.encode_append(value, &mut self.current) {
+                    self.current.clear();
+                    if written == 0 {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, e));
+                    }
+                    self.error = Some(e);
+                    break;
+                }
+            }
+            let pending = &self.current[self.pos..];
+            let n = pending.len().min(buf.len() - written);
+            buf[written..written + n].copy_from_slice(&pending[..n]);
+            written += n;
+            self.pos += n;
+        }
+        Ok(written)
+    }
+}
+
+/// Multi-threaded versions of the bulk functions, built on scoped threads from the standard library.
+///
+/// Each call splits its input in up to as many chunks as there are cores, with no work stealing between calls
+/// as a rayon pool would do: callers running several of them at once get more threads than cores.
+#[cfg(feature = "parallel")]
+pub mod parallel {
+    use super::*;
+    use std::thread;
+
+    /// Below this many values per thread, spawning costs more than it saves.
+    const MIN_CHUNK: usize = 16 * 1024;
+
+    fn threads(len: usize) -> usize {
+        let available = thread::available_parallelism().map_or(1, |n| n.get());
+        available.min(len / MIN_CHUNK).max(1)
+    }
+
+    /// Parallel [`encode_all`]: each thread encodes a contiguous chunk of the values and the outputs are concatenated.
+    pub fn par_encode_all(values: &[u64]) -> Vec<u8> {
+        let threads = threads(values.len());
+        if threads == 1 {
+            return encode_all(values);
+        }
+        let chunk = values.len().div_ceil(threads);
+        let parts: Vec<Vec<u8>> = thread::scope(|s| {
+            let handles: Vec<_> = values
+                .chunks(chunk)
+                .map(|values| s.spawn(move || encode_all(values)))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        parts.concat()
+    }
+
+    /// Parallel [`decode_all`].
+    ///
+    /// A first sequential pass only looks at prefix bytes to count the values and find where each chunk starts,
+    /// then every thread decodes its chunk straight into its part of the output.
+    pub fn par_decode_all(bytes: &[u8]) -> Result<Vec<u64>, DecodeError> {
+        // Offsets of every MIN_CHUNK-th value, and the total number of values.
+        let mut starts = Vec::new();
+        let mut count = 0;
+        let mut pos = 0;
+        let mut last = 0;
+        while pos < bytes.len() {
+            if count % MIN_CHUNK == 0 {
+                starts.push(pos);
+            }
+            last = pos;
+            pos += match bytes[pos] {
+                0xfd => 3,
+                0xfe => 5,
+                0xff => 9,
+                _ => 1,
+            };
+            count += 1;
+        }
+        if pos > bytes.len() {
+            let error = DecodeError::new(VarIntError::Truncated, bytes, last, count - 1);
+            return Err(error.with_expected(VarInt.encoded_len_at(&bytes[last..])));
+        }
+
+        let threads = threads(count);
+        if threads == 1 {
+            return decode_all(bytes);
+        }
+        let blocks_per_thread = starts.len().div_ceil(threads);
+        let mut result = vec![0u64; count];
+        thread::scope(|s| {
+            let mut output = result.as_mut_slice();
+            let mut handles = Vec::new();
+            for (i, block) in starts.chunks(blocks_per_thread).enumerate() {
+                let values = (block.len() * MIN_CHUNK).min(output.len());
+                let (out, rest) = output.split_at_mut(values);
+                output = rest;
+                let start = block[0];
+                let end = starts
+                    .get((i + 1) * blocks_per_thread)
+                    .copied()
+                    .unwrap_or(bytes.len());
+                let input = &bytes[start..end];
+                let first = i * blocks_per_thread * MIN_CHUNK;
+                handles.push(s.spawn(move || {
+                    let mut pos = 0;
+                    for (j, value) in out.iter_mut().enumerate() {
+                        let decoded = VarInt::decode_full(&input[pos..])
+                            .map_err(|e| DecodeError::new(e, bytes, start + pos, first + j))?;
+                        *value = decoded.value;
+                        pos += decoded.len;
+                    }
+                    Ok(())
+                }));
+            }
+            handles.into_iter().try_for_each(|h| h.join().unwrap())
+        })?;
+        Ok(result)
+    }
+
+    /// Parallel [`encode_chunked`](crate::chunked::encode_chunked), every thread encoding chunks of
+    /// `chunk_len` values, at least 1.
+    pub fn par_encode_chunked(values: &[u64], chunk_len: usize) -> Vec<u8> {
+        let chunks: Vec<&[u64]> = values.chunks(chunk_len.max(1)).collect();
+        let threads = threads(values.len()).min(chunks.len()).max(1);
+        let per_thread = chunks.len().div_ceil(threads).max(1);
+        let parts: Vec<(usize, Vec<u8>)> = thread::scope(|s| {
+            let handles: Vec<_> = chunks
+                .chunks(per_thread)
+                .map(|chunks| {
+                    s.spawn(move || {
+                        chunks
+                            .iter()
+                            .map(|chunk| (chunk.len(), encode_all(chunk)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+        crate::chunked::assemble(&parts)
+    }
+
+    /// Parallel [`decode_chunked`](crate::chunked::decode_chunked): the boundaries recorded in the container
+    /// spare the sequential pass of [`par_decode_all`], and every thread decodes whole chunks.
+    pub fn par_decode_chunked(bytes: &[u8]) -> Result<Vec<u64>, DecodeError> {
+        use crate::chunked::{decode_chunk, read_chunks};
+
+        let chunks = read_chunks(bytes).map_err(|e| DecodeError::new(e, bytes, 0, 0))?;
+        let count = chunks.last().map_or(0, |chunk| chunk.first + chunk.count);
+        let threads = threads(count).min(chunks.len()).max(1);
+        let per_thread = chunks.len().div_ceil(threads).max(1);
+        let mut result = vec![0u64; count];
+        thread::scope(|s| {
+            let mut output = result.as_mut_slice();
+            let mut handles = Vec::new();
+            for group in chunks.chunks(per_thread) {
+                let values = group.iter().map(|chunk| chunk.count).sum();
+                let (mut out, rest) = output.split_at_mut(values);
+                output = rest;
+                handles.push(s.spawn(move || {
+                    for chunk in group {
+                        let (chunk_out, rest) = out.split_at_mut(chunk.count);
+                        out = rest;
+                        decode_chunk(bytes, chunk, chunk_out)?;
+                    }
+                    Ok(())
+                }));
+            }
+            handles.into_iter().try_for_each(|h| h.join().unwrap())
+        })?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_all() {
+        assert_eq!(
+            encode_all(&[1, 515, 0x10000]),
+            vec![1, 0xfd, 3, 2, 0xfe, 0, 0, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_encode_all_into() {
+        let sizes = [0xfc, 0xfd, 0xffff, 0x1_0000, u64::MAX, 3];
+        let values: Vec<u64> = (0..40).map(|i| sizes[i % 6] ^ (i as u64 % 2)).collect();
+        for count in 0..values.len() {
+            let mut expected = vec![0xaa];
+            for value in &values[..count] {
+                VarInt::encode_append(*value, &mut expected);
+            }
+            let mut out = vec![0xaa];
+            assert_eq!(
+                encode_all_into(&values[..count], &mut out),
+                expected.len() - 1
+            );
+            assert_eq!(out, expected, "{} values", count);
+        }
+    }
+
+    #[test]
+    fn test_encode_uninit() {
+        let values = [0, 0xfd, 1 << 32, 7];
+        let mut out = [MaybeUninit::uninit(); 32];
+        let (written, count) = encode_uninit(&values, &mut out);
+        assert_eq!((&*written, count), (&encode_all(&values)[..], 4));
+
+        // The 9 bytes value doesn't fit after the first 4 bytes, nor does anything after it.
+        let mut small = [MaybeUninit::uninit(); 12];
+        let (written, count) = encode_uninit(&values, &mut small);
+        assert_eq!((&*written, count), (&[0x00, 0xfd, 0xfd, 0x00][..], 2));
+        assert_eq!(encode_uninit(&values, &mut []).1, 0);
+    }
+
+    #[test]
+    fn test_total_encoded_size() {
+        let values = [
+            0,
+            252,
+            253,
+            0xffff,
+            0x10000,
+            0xffff_ffff,
+            0x1_0000_0000,
+            u64::MAX,
+        ];
+        let expected = encode_all(&values).len() as u64;
+        assert_eq!(expected, 1 + 1 + 3 + 3 + 5 + 5 + 9 + 9);
+        assert_eq!(total_encoded_size(values), expected);
+        assert_eq!(total_encoded_size_slice(&values), expected);
+        assert_eq!(total_encoded_size_slice(&[]), 0);
+    }
+
+    #[test]
+    fn test_decode_all() {
+        assert_eq!(
+            decode_all(&[1, 0xfd, 3, 2, 0xfe, 0, 0, 1, 0]).unwrap(),
+            vec![1, 515, 0x10000]
+        );
+        let err = decode_all(&[1, 0xfd, 3]).unwrap_err();
+        assert_eq!(err.error, VarIntError::Truncated);
+        assert_eq!((err.offset, err.index), (1, 1));
+        assert_eq!(err.snippet, vec![0xfd, 3]);
+        assert_eq!(
+            err.to_string(),
+            "unexpected end of input at byte 1 (value 1): needs 3 bytes, 2 available, input there: fd03"
+        );
+    }
+
+    #[test]
+    fn test_decode_into() {
+        let values: Vec<u64> = (0..10).map(|i| i << (6 * i)).collect();
+        let bytes = encode_all(&values);
+        let mut out = [0u64; 4];
+        let mut decoded = Vec::new();
+        let mut rest = &bytes[..];
+        while !rest.is_empty() {
+            let (count, len) = decode_into(rest, &mut out).unwrap();
+            decoded.extend_from_slice(&out[..count]);
+            rest = &rest[len..];
+        }
+        assert_eq!(decoded, values);
+
+        let mut large = [0u64; 4];
+        assert_eq!(decode_into(&[0x01, 0x02], &mut large), Ok((2, 2)));
+        assert_eq!(large[..2], [1, 2]);
+        assert_eq!(decode_into(&[0x01], &mut []), Ok((0, 0)));
+        let err = decode_into(&[0x01, 0xfe, 0x00], &mut large).unwrap_err();
+        assert_eq!((err.error, err.offset), (VarIntError::Truncated, 1));
+
+        // Runs of single bytes longer and shorter than 16 around larger values.
+        let values: Vec<u64> = (0..100)
+            .map(|i| if i % 37 == 36 { 0x1234 } else { i % 0xfd })
+            .collect();
+        let mut bytes = encode_all(&values);
+        let mut out = vec![0u64; 120];
+        assert_eq!(decode_into(&bytes, &mut out), Ok((100, bytes.len())));
+        assert_eq!(out[..100], values);
+        assert_eq!(decode_into(&bytes, &mut out[..20]), Ok((20, 20)));
+        bytes.push(0xfd);
+        let err = decode_into(&bytes, &mut out).unwrap_err();
+        assert_eq!((err.error, err.index), (VarIntError::Truncated, 100));
+    }
+
+    #[test]
+    fn test_iter_with_offsets() {
+        let bytes = encode_all(&[0xfd, 0, 1 << 32]);
+        let mut iter = iter_with_offsets(&bytes);
+        assert_eq!(iter.next(), Some(Ok((0, 0xfd, 3))));
+        assert_eq!(iter.next(), Some(Ok((3, 0, 1))));
+        assert_eq!(iter.next(), Some(Ok((4, 1 << 32, 9))));
+        assert_eq!(iter.next(), None);
+
+        let mut iter = iter_with_offsets(&[0x05, 0xfe, 0x01]);
+        assert_eq!(iter.next(), Some(Ok((0, 5, 1))));
+        let err = iter.next().unwrap().unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::Truncated, 1, 1)
+        );
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_fold() {
+        let values: Vec<u64> = (0..100_000).map(|i| i * 0x1_0001).collect();
+        let bytes = encode_all(&values);
+        let sum: u128 = values.iter().map(|v| *v as u128).sum();
+        assert_eq!(fold(&bytes, 0u128, |sum, v| sum + v as u128), Ok(sum));
+        let (min, max, count) = fold_stream_with(
+            &VarInt,
+            &mut &bytes[..],
+            (u64::MAX, 0, 0),
+            |(min, max, count), v| (min.min(v), max.max(v), count + 1),
+        )
+        .unwrap();
+        assert_eq!((min, max, count), (0, 99_999 * 0x1_0001, values.len()));
+
+        let err = fold(&[0x01, 0xfd, 0x00], 0, |count, _| count + 1).unwrap_err();
+        assert_eq!((err.error, err.offset), (VarIntError::Truncated, 1));
+        let err = fold_stream_with(&VarInt, &mut &[0x01, 0xfd][..], 0, |count, _| count + 1);
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_decode_iter() {
+        let bytes = [0x2a, 0xfd, 0x03, 0x02, 0x80];
+        let mut iter = decode_iter_with(&crate::Leb128, &bytes);
+        assert_eq!(iter.next(), Some(Ok(42)));
+        assert_eq!(iter.next(), Some(Ok(509)));
+        assert_eq!(iter.next(), Some(Ok(2)));
+        let err = iter.next().unwrap().unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::Truncated, 4, 3)
+        );
+        assert_eq!(iter.next(), None);
+        assert_eq!(
+            decode_iter(&bytes[..4]).collect::<Result<Vec<_>, _>>(),
+            Ok(vec![42, 515])
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "leb128", feature = "quic"))]
+    fn test_bulk_with_codec() {
+        use crate::Codec;
+
+        let values = [0, 300, 1 << 40];
+        let bytes = encode_all_with(&Codec::Leb128, &values).unwrap();
+        assert_eq!(bytes.len(), 1 + 2 + 6);
+        assert_eq!(decode_all_with(&Codec::Leb128, &bytes).unwrap(), values);
+        assert_eq!(
+            encode_all_with(&Codec::Quic, &[u64::MAX]),
+            Err(VarIntError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_decode_n() {
+        let bytes = encode_all(&[1, 300, 70_000]);
+        assert_eq!(decode_n::<2>(&bytes), Ok(([1, 300], 4)));
+        assert_eq!(decode_n::<0>(&bytes), Ok(([], 0)));
+        let err = decode_n::<4>(&bytes).unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::Truncated, 9, 3)
+        );
+        let err = decode_n::<3>(&bytes[..7]).unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::Truncated, 4, 2)
+        );
+    }
+
+    #[test]
+    fn test_skip_varints() {
+        let values = [1, 300, 70_000, 1 << 40, 2];
+        let bytes = encode_all(&values);
+        assert_eq!(skip_varints(&bytes, 0), Ok(0));
+        assert_eq!(skip_varints(&bytes, 4), Ok(1 + 3 + 5 + 9));
+        assert_eq!(skip_varints(&bytes, 5), Ok(bytes.len()));
+        let err = skip_varints(&bytes[..10], 4).unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index, err.expected),
+            (VarIntError::Truncated, 9, 3, Some(9))
+        );
+        assert_eq!(skip_varints(&bytes, 6).unwrap_err().index, 5);
+
+        let mut iter = decode_iter(&bytes);
+        assert_eq!(iter.next(), Some(Ok(1)));
+        iter.skip_values(2).unwrap();
+        assert_eq!((iter.offset(), iter.next()), (9, Some(Ok(1 << 40))));
+        assert!(iter.skip_values(2).is_err());
+        assert_eq!(iter.next(), None);
+
+        let mut reader = &bytes[..];
+        assert_eq!(skip_varints_stream(&mut reader, 3).unwrap(), 9);
+        assert_eq!(reader.len(), bytes.len() - 9);
+        let err = skip_varints_stream(&mut &bytes[..12], 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_skip_varints_with() {
+        // LEB128 can't tell lengths from a first byte, so its values are decoded.
+        let bytes = [0x2a, 0xac, 0x02, 0x80, 0x80, 0x01, 0x05];
+        assert_eq!(skip_varints_with(&crate::Leb128, &bytes, 3), Ok(6));
+        let mut reader = &bytes[..];
+        assert_eq!(
+            skip_varints_stream_with(&crate::Leb128, &mut reader, 3).unwrap(),
+            6
+        );
+        assert_eq!(reader, [0x05]);
+        assert_eq!(
+            skip_varints_with(&crate::Leb128, &bytes[..5], 3)
+                .unwrap_err()
+                .offset,
+            3
+        );
+    }
+
+    #[test]
+    fn test_encode_iter() {
+        let values = [1, 515, 0x10000, u64::MAX];
+        let encoded: Vec<u8> = encode_iter(values).flat_map(|e| e.to_vec()).collect();
+        assert_eq!(encoded, encode_all(&values));
+
+        // Reads smaller than an encoding resume in its middle.
+        let mut reader = EncodeReader::new(values);
+        let mut streamed = Vec::new();
+        let mut buf = [0u8; 2];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            streamed.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(streamed, encoded);
+    }
+
+    #[test]
+    fn test_encoded_varint_fmt() {
+        let encoded = EncodedVarInt::new(515);
+        assert_eq!(encoded.value(), 515);
+        assert_eq!(format!("{:x}", encoded), "fd0302");
+        assert_eq!(format!("{:#X}", encoded), "0xFD0302");
+        assert_eq!(encoded.to_string(), "515 (0xfd0302, 3 bytes)");
+        assert_eq!(EncodedVarInt::new(7).to_string(), "7 (0x07, 1 byte)");
+        assert_eq!(EncodedVarInt::new(u64::MAX).value(), u64::MAX);
+    }
+
+    #[test]
+    #[cfg(all(feature = "leb128", feature = "quic"))]
+    fn test_encode_reader_with_codec() {
+        use crate::Codec;
+
+        let values = [300, 1 << 40, 7];
+        let mut out = Vec::new();
+        io::copy(
+            &mut EncodeReader::with_codec(Codec::Leb128, values),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(out, encode_all_with(&Codec::Leb128, &values).unwrap());
+
+        // The read reaching u64::MAX returns the bytes before it, the next one fails.
+        let mut reader = EncodeReader::with_codec(Codec::Quic, [1, u64::MAX, 2]);
+        let mut buf = [0u8; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!((reader.read(&mut buf).unwrap(), buf[0]), (1, 0x02));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_round_trip() {
+        use super::parallel::*;
+
+        let values: Vec<u64> = (0..200_000u64).map(|i| i * i * 7919).collect();
+        let bytes = par_encode_all(&values);
+        assert_eq!(bytes, encode_all(&values));
+        assert_eq!(par_decode_all(&bytes).unwrap(), values);
+        let err = par_decode_all(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.error, VarIntError::Truncated);
+        assert_eq!(err.index, values.len() - 1);
+        assert_eq!(err.offset, bytes.len() - 9);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_chunked() {
+        use super::parallel::*;
+        use crate::chunked::{decode_chunked, encode_chunked};
+
+        let values: Vec<u64> = (0..200_000u64).map(|i| i * i * 7919).collect();
+        let bytes = par_encode_chunked(&values, 10_000);
+        assert_eq!(bytes, encode_chunked(&values, 10_000));
+        assert_eq!(par_decode_chunked(&bytes).unwrap(), values);
+        assert_eq!(decode_chunked(&bytes).unwrap(), values);
+
+        let mut corrupt = bytes.clone();
+        let last = corrupt.len() - 9;
+        corrupt[last] = 0x01;
+        let err = par_decode_chunked(&corrupt).unwrap_err();
+        assert_eq!(
+            (err.error, err.index),
+            (VarIntError::Malformed, values.len() - 1)
+        );
+    }
+}
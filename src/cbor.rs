@@ -0,0 +1,213 @@
+use crate::VarIntError;
+
+/// CBOR major types, stored in the three high bits of the initial byte.
+///
+/// https://www.rfc-editor.org/rfc/rfc8949.html#name-major-types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MajorType {
+    Unsigned = 0,
+    Negative = 1,
+    Bytes = 2,
+    Text = 3,
+    Array = 4,
+    Map = 5,
+    Tag = 6,
+    Simple = 7,
+}
+
+impl MajorType {
+    fn from_bits(bits: u8) -> MajorType {
+        match bits & 0x07 {
+            0 => MajorType::Unsigned,
+            1 => MajorType::Negative,
+            2 => MajorType::Bytes,
+            3 => MajorType::Text,
+            4 => MajorType::Array,
+            5 => MajorType::Map,
+            6 => MajorType::Tag,
+            _ => MajorType::Simple,
+        }
+    }
+}
+
+/// A decoded CBOR head: the initial byte plus its argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CborHead {
+    pub major: MajorType,
+    /// The argument, or `None` for the indefinite-length marker (additional info 31).
+    pub argument: Option<u64>,
+    /// Number of bytes taken by the head.
+    pub len: usize,
+}
+
+/// CBOR data item head (major type + argument)
+///
+/// The low five bits of the initial byte ("additional information") either hold the argument directly (0 to 23),
+/// or announce that it follows in the next 1, 2, 4 or 8 bytes in network byte order (24 to 27).
+/// Value 31 marks indefinite-length items, 28 to 30 are reserved.
+///
+/// https://www.rfc-editor.org/rfc/rfc8949.html#name-specification-of-the-cbor-e
+pub struct Cbor;
+
+impl Cbor {
+    /// Encodes the head in its shortest form, which is the form required by deterministic (canonical) CBOR.
+    pub fn encode_head(major: MajorType, argument: u64) -> Vec<u8> {
+        let mt = (major as u8) << 5;
+        match argument {
+            x if x < 24 => vec![mt | x as u8],
+            x if x <= 0xff => vec![mt | 24, x as u8],
+            x if x <= 0xffff => {
+                let mut v = vec![mt | 25];
+                v.extend_from_slice(&(x as u16).to_be_bytes());
+                v
+            }
+            x if x <= 0xffffffff => {
+                let mut v = vec![mt | 26];
+                v.extend_from_slice(&(x as u32).to_be_bytes());
+                v
+            }
+            x => {
+                let mut v = vec![mt | 27];
+                v.extend_from_slice(&x.to_be_bytes());
+                v
+            }
+        }
+    }
+
+    /// Encodes the head of an indefinite-length byte string, text string, array or map, or the "break" stop code when `major` is [`MajorType::Simple`].
+    pub fn encode_indefinite(major: MajorType) -> Result<u8, VarIntError> {
+        match major {
+            MajorType::Unsigned | MajorType::Negative | MajorType::Tag => {
+                Err(VarIntError::Malformed)
+            }
+            _ => Ok(((major as u8) << 5) | 31),
+        }
+    }
+
+    /// Decodes a head, accepting arguments that are not in their shortest form.
+    pub fn decode_head(bytes: &[u8]) -> Result<CborHead, VarIntError> {
+        Self::decode(bytes, false)
+    }
+
+    /// Decodes a head following the deterministic encoding rules: the argument must be in its shortest form
+    /// and indefinite lengths are rejected. Floats, whose bits are the argument, are accepted at any width.
+    pub fn decode_head_canonical(bytes: &[u8]) -> Result<CborHead, VarIntError> {
+        Self::decode(bytes, true)
+    }
+
+    fn decode(bytes: &[u8], canonical: bool) -> Result<CborHead, VarIntError> {
+        let initial = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let major = MajorType::from_bits(initial >> 5);
+        let info = initial & 0x1f;
+
+        let (argument, len) = match info {
+            x if x < 24 => (x as u64, 1),
+            24..=27 => {
+                let size = 1usize << (info - 24);
+                let payload = bytes.get(1..1 + size).ok_or(VarIntError::Truncated)?;
+                let mut buf = [0u8; 8];
+                buf[8 - size..].copy_from_slice(payload);
+                (u64::from_be_bytes(buf), 1 + size)
+            }
+            31 if canonical => return Err(VarIntError::NonCanonical),
+            31 if matches!(
+                major,
+                MajorType::Unsigned | MajorType::Negative | MajorType::Tag
+            ) =>
+            {
+                return Err(VarIntError::Malformed)
+            }
+            31 => {
+                return Ok(CborHead {
+                    major,
+                    argument: None,
+                    len: 1,
+                })
+            }
+            _ => return Err(VarIntError::Malformed),
+        };
+
+        // Simple values below 32 must not use the one-byte extension.
+        if major == MajorType::Simple && info == 24 && argument < 32 {
+            return Err(VarIntError::Malformed);
+        }
+        // Floats (simple values with info 25 to 27) carry their bits, not an integer to shorten.
+        let float = major == MajorType::Simple && info >= 25;
+        if canonical && !float && len != Self::encode_head(major, argument).len() {
+            return Err(VarIntError::NonCanonical);
+        }
+
+        Ok(CborHead {
+            major,
+            argument: Some(argument),
+            len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_encode_head() {
+        assert_eq!(Cbor::encode_head(MajorType::Unsigned, 23), vec![0x17]);
+        assert_eq!(Cbor::encode_head(MajorType::Unsigned, 24), vec![0x18, 0x18]);
+        assert_eq!(
+            Cbor::encode_head(MajorType::Bytes, 500),
+            vec![0x59, 0x01, 0xf4]
+        );
+        assert_eq!(
+            Cbor::encode_head(MajorType::Unsigned, 1_000_000_000_000),
+            vec![0x1b, 0x00, 0x00, 0x00, 0xe8, 0xd4, 0xa5, 0x10, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_cbor_decode_head() {
+        let head = Cbor::decode_head(&[0x82, 0x01, 0x02]).unwrap();
+        assert_eq!(head.major, MajorType::Array);
+        assert_eq!(head.argument, Some(2));
+        assert_eq!(head.len, 1);
+        assert_eq!(Cbor::decode_head(&[0x5f]).unwrap().argument, None);
+        assert_eq!(
+            Cbor::decode_head(&[0x19, 0x01]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(Cbor::decode_head(&[0x1c]), Err(VarIntError::Malformed));
+    }
+
+    #[test]
+    fn test_cbor_decode_head_canonical() {
+        assert_eq!(Cbor::decode_head(&[0x18, 0x05]).unwrap().argument, Some(5));
+        assert_eq!(
+            Cbor::decode_head_canonical(&[0x18, 0x05]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(
+            Cbor::decode_head_canonical(&[0x9f]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(
+            Cbor::decode_head_canonical(&[0x19, 0x01, 0xf4])
+                .unwrap()
+                .argument,
+            Some(500)
+        );
+        // Half, single and double floats keep their width.
+        let head = Cbor::decode_head_canonical(&[0xfa, 0, 0, 0, 1]).unwrap();
+        assert_eq!(
+            (head.major, head.argument, head.len),
+            (MajorType::Simple, Some(1), 5)
+        );
+        let head = Cbor::decode_head_canonical(&[0xf9, 0x3c, 0x00]).unwrap();
+        assert_eq!((head.argument, head.len), (Some(0x3c00), 3));
+        assert_eq!(
+            Cbor::decode_head_canonical(&[0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0])
+                .unwrap()
+                .len,
+            9
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Concatenated compactSizes cut into chunks whose boundaries are recorded up front, so that every chunk can be
+//! decoded on its own, by another thread, without first scanning the chunks before it.
+//!
+//! The container starts with the number of chunks, then for every chunk its number of values and its length
+//! in bytes, all compactSizes, then the chunks themselves. Decoding checks that every chunk holds exactly the
+//! values and bytes its entry announces.
+//!
+//! ```
+//! use bitcoin_varint::chunked::{decode_chunked, encode_chunked, read_chunks};
+//!
+//! let values: Vec<u64> = (0..1000).map(|i| i * 1000).collect();
+//! let bytes = encode_chunked(&values, 256);
+//! assert_eq!(read_chunks(&bytes).unwrap().len(), 4);
+//! assert_eq!(decode_chunked(&bytes).unwrap(), values);
+//! ```
+
+use crate::bulk::encode_all;
+use crate::{DecodeError, VarInt, VarIntError};
+
+/// Where a chunk is in the container and the values it holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk {
+    /// Index of the first value of the chunk among all values.
+    pub first: usize,
+    pub count: usize,
+    /// Byte offset of the chunk in the container.
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Encodes `values` in chunks of `chunk_len` values, at least 1.
+pub fn encode_chunked(values: &[u64], chunk_len: usize) -> Vec<u8> {
+    let parts: Vec<(usize, Vec<u8>)> = values
+        .chunks(chunk_len.max(1))
+        .map(|chunk| (chunk.len(), encode_all(chunk)))
+        .collect();
+    assemble(&parts)
+}
+
+/// Writes the table of `parts`, their value count and their encoding, followed by the parts.
+pub(crate) fn assemble(parts: &[(usize, Vec<u8>)]) -> Vec<u8> {
+    let body: usize = parts.iter().map(|(_, part)| part.len()).sum();
+    let mut result = Vec::with_capacity(body + 9 + 2 * parts.len() * 5);
+    VarInt::encode_append(parts.len() as u64, &mut result);
+    for (count, part) in parts {
+        VarInt::encode_append(*count as u64, &mut result);
+        VarInt::encode_append(part.len() as u64, &mut result);
+    }
+    for (_, part) in parts {
+        result.extend_from_slice(part);
+    }
+    result
+}
+
+/// Reads the table of a container, checking that the chunks take all of its bytes.
+pub fn read_chunks(bytes: &[u8]) -> Result<Vec<Chunk>, VarIntError> {
+    let (len, mut pos) = VarInt::decode_len(bytes)?;
+    // Every entry takes at least two bytes.
+    if len > (bytes.len() - pos) / 2 {
+        return Err(VarIntError::Truncated);
+    }
+    let mut entries = Vec::with_capacity(len);
+    for _ in 0..len {
+        let (count, read) = VarInt::decode_len(&bytes[pos..])?;
+        pos += read;
+        let (chunk_len, read) = VarInt::decode_len(&bytes[pos..])?;
+        pos += read;
+        // Every value takes at least a byte and at most 9.
+        if count > chunk_len || count.saturating_mul(9) < chunk_len {
+            return Err(VarIntError::Malformed);
+        }
+        entries.push((count, chunk_len));
+    }
+    let (mut first, mut offset) = (0, pos);
+    let mut chunks = Vec::with_capacity(len);
+    for (count, len) in entries {
+        if len > bytes.len() - offset {
+            return Err(VarIntError::Truncated);
+        }
+        chunks.push(Chunk {
+            first,
+            count,
+            offset,
+            len,
+        });
+        first += count;
+        offset += len;
+    }
+    if offset != bytes.len() {
+        return Err(VarIntError::Malformed);
+    }
+    Ok(chunks)
+}
+
+/// Decodes `chunk` of the container `bytes` into `out`, which must hold `chunk.count` values.
+///
+/// Errors are located in the whole container. A chunk whose values end before or after its announced length
+/// fails with [`VarIntError::Malformed`] at the value where they part.
+pub fn decode_chunk(bytes: &[u8], chunk: &Chunk, out: &mut [u64]) -> Result<(), DecodeError> {
+    let input = &bytes[chunk.offset..chunk.offset + chunk.len];
+    let mut pos = 0;
+    for (i, value) in out.iter_mut().enumerate() {
+        let start = pos;
+        let error = |e| DecodeError::new(e, bytes, chunk.offset + start, chunk.first + i);
+        let decoded = VarInt::decode_full(&input[pos..]).map_err(|e| match e {
+            VarIntError::Truncated => error(VarIntError::Malformed),
+            e => error(e),
+        })?;
+        *value = decoded.value;
+        pos += decoded.len;
+        if (pos == input.len()) != (i + 1 == chunk.count) {
+            return Err(error(VarIntError::Malformed));
+        }
+    }
+    Ok(())
+}
+
+/// Decodes every value of a container, one chunk after the other.
+pub fn decode_chunked(bytes: &[u8]) -> Result<Vec<u64>, DecodeError> {
+    let chunks = read_chunks(bytes).map_err(|e| DecodeError::new(e, bytes, 0, 0))?;
+    let count = chunks.last().map_or(0, |chunk| chunk.first + chunk.count);
+    let mut result = vec![0u64; count];
+    for chunk in &chunks {
+        decode_chunk(bytes, chunk, &mut result[chunk.first..][..chunk.count])?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunked_layout() {
+        let bytes = encode_chunked(&[1, 300, 7], 2);
+        assert_eq!(
+            bytes,
+            vec![0x02, 0x02, 0x04, 0x01, 0x01, 0x01, 0xfd, 0x2c, 0x01, 0x07]
+        );
+        let chunks = read_chunks(&bytes).unwrap();
+        assert_eq!(
+            chunks[1],
+            Chunk {
+                first: 2,
+                count: 1,
+                offset: 9,
+                len: 1
+            }
+        );
+        assert_eq!(decode_chunked(&bytes).unwrap(), vec![1, 300, 7]);
+        assert_eq!(decode_chunked(&encode_chunked(&[], 8)).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_chunked_errors() {
+        assert_eq!(
+            read_chunks(&[0x02, 0x01, 0x01]),
+            Err(VarIntError::Truncated)
+        );
+        // More values than bytes, and bytes left after the last chunk.
+        assert_eq!(
+            read_chunks(&[0x01, 0x02, 0x01, 0x00]),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            read_chunks(&[0x01, 0x01, 0x01, 0x00, 0x00]),
+            Err(VarIntError::Malformed)
+        );
+
+        // Two values announced in 3 bytes, but the first takes them all.
+        let bytes = [0x01, 0x02, 0x03, 0xfd, 0x00, 0x01];
+        let err = decode_chunked(&bytes).unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::Malformed, 3, 0)
+        );
+        // Or in 4 bytes, the second value ending before.
+        let bytes = [0x01, 0x02, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let err = decode_chunked(&bytes).unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::Malformed, 4, 1)
+        );
+    }
+}
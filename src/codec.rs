@@ -0,0 +1,756 @@
+#[cfg(feature = "groups")]
+use crate::groups::GroupCodec;
+#[cfg(feature = "quic")]
+use crate::quic::Quic;
+#[cfg(feature = "vlq")]
+use crate::vlq::Vlq;
+#[cfg(feature = "bigsize")]
+use crate::BigSize;
+#[cfg(feature = "leb128")]
+use crate::Leb128;
+use crate::{DecodeError, VarInt, VarIntError};
+use std::io::{self, Read, Write};
+use std::num::NonZeroU64;
+use std::ops::Deref;
+
+/// A variable-length encoding of u64 values
+///
+/// Implemented by the encodings of this crate and by [`Codec`], so that [`transcode`], the
+/// [`bulk`](crate::bulk) functions and the [`delimited`](crate::delimited) framing work with any of them,
+/// including encodings defined in other crates.
+///
+/// ```
+/// use bitcoin_varint::bulk::decode_all_with;
+/// use bitcoin_varint::{VarCodec, VarIntError};
+///
+/// /// One byte for values below 0x80, else a marker and 4 big-endian bytes.
+/// struct Legacy;
+///
+/// impl VarCodec for Legacy {
+///     fn max_len(&self) -> usize {
+///         5
+///     }
+///
+///     fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+///         match value {
+///             0..=0x7f => out.push(value as u8),
+///             0x80..=0xffff_ffff => {
+///                 out.push(0x80);
+///                 out.extend_from_slice(&(value as u32).to_be_bytes());
+///                 return Ok(5);
+///             }
+///             _ => return Err(VarIntError::Overflow),
+///         }
+///         Ok(1)
+///     }
+///
+///     fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+///         match bytes {
+///             [] => Err(VarIntError::Truncated),
+///             [0x80, rest @ ..] => {
+///                 let payload = rest.get(..4).ok_or(VarIntError::Truncated)?;
+///                 Ok((u32::from_be_bytes(payload.try_into().unwrap()) as u64, 5))
+///             }
+///             [byte, ..] if *byte < 0x80 => Ok((*byte as u64, 1)),
+///             _ => Err(VarIntError::Malformed),
+///         }
+///     }
+/// }
+///
+/// assert_eq!(decode_all_with(&Legacy, &[0x2a, 0x80, 0, 0, 1, 0]).unwrap(), vec![42, 256]);
+/// assert_eq!(Legacy.encoded_len(256), Ok(5));
+/// ```
+pub trait VarCodec {
+    /// Maximum number of bytes of an encoded value.
+    fn max_len(&self) -> usize;
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError>;
+
+    /// Decodes a value, returning it with the number of bytes read.
+    ///
+    /// Any prefix of a valid encoding must fail with [`VarIntError::Truncated`], which is how streaming
+    /// readers know they need more bytes.
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError>;
+
+    /// Number of bytes of the encoding starting at `bytes`, when its first bytes tell without decoding it all.
+    ///
+    /// Used to report how many bytes a truncated value needs. The default implementation returns `None`.
+    fn encoded_len_at(&self, bytes: &[u8]) -> Option<usize> {
+        let _ = bytes;
+        None
+    }
+
+    /// Number of bytes of the encoding of `value`.
+    ///
+    /// The default implementation encodes the value into a scratch buffer.
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        self.encode_append(value, &mut Vec::with_capacity(self.max_len()))
+    }
+
+    /// Encodes a value into a new buffer.
+    fn encode(&self, value: u64) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::with_capacity(self.max_len());
+        self.encode_append(value, &mut result)?;
+        Ok(result)
+    }
+
+    /// Decodes a value into a narrower type such as `u8`, `u16`, `u32` or `usize`, failing with
+    /// [`VarIntError::Overflow`] when it doesn't fit rather than truncating it like an `as` cast.
+    ///
+    /// ```
+    /// use bitcoin_varint::{VarCodec, VarInt, VarIntError};
+    ///
+    /// assert_eq!(VarInt.decode_into::<u16>(&[0xfd, 0xff, 0xff]), Ok((u16::MAX, 3)));
+    /// assert_eq!(VarInt.decode_into::<u8>(&[0xfd, 0x00, 0x01]), Err(VarIntError::Overflow));
+    /// ```
+    fn decode_into<T: TryFrom<u64>>(&self, bytes: &[u8]) -> Result<(T, usize), VarIntError>
+    where
+        Self: Sized,
+    {
+        let (value, len) = self.decode(bytes)?;
+        let value = T::try_from(value).map_err(|_| VarIntError::Overflow)?;
+        Ok((value, len))
+    }
+
+    /// Decodes a value like [`decode`](VarCodec::decode), failing with a [`DecodeError`] that shows the
+    /// offending bytes and, for a truncated value, how many it needs.
+    ///
+    /// ```
+    /// use bitcoin_varint::{VarCodec, VarInt};
+    ///
+    /// let error = VarInt.decode_detailed(&[0xfe, 0x01]).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "unexpected end of input at byte 0 (value 0): needs 5 bytes, 2 available, input there: fe01"
+    /// );
+    /// ```
+    fn decode_detailed(&self, bytes: &[u8]) -> Result<(u64, usize), DecodeError> {
+        self.decode(bytes)
+            .map_err(|e| DecodeError::new(e, bytes, 0, 0).with_expected(self.encoded_len_at(bytes)))
+    }
+
+    /// Decodes a value that must take all of `bytes`, failing with [`VarIntError::Malformed`] when bytes are left
+    /// after it.
+    fn decode_exact(&self, bytes: &[u8]) -> Result<u64, VarIntError> {
+        match self.decode(bytes)? {
+            (value, len) if len == bytes.len() => Ok(value),
+            _ => Err(VarIntError::Malformed),
+        }
+    }
+
+    /// Encodes an optional value with 0 as the sentinel for `None` and `v + 1` for `Some(v)`, so that absent
+    /// fields take a single byte. `Some(u64::MAX)` fails with [`VarIntError::Overflow`].
+    ///
+    /// ```
+    /// use bitcoin_varint::{VarCodec, VarInt};
+    ///
+    /// assert_eq!(VarInt.encode_option(None), Ok(vec![0x00]));
+    /// assert_eq!(VarInt.encode_option(Some(0)), Ok(vec![0x01]));
+    /// assert_eq!(VarInt.decode_option(&[0xfd, 0xfd, 0x00]), Ok((Some(0xfc), 3)));
+    /// ```
+    fn encode_option(&self, value: Option<u64>) -> Result<Vec<u8>, VarIntError> {
+        let value = match value {
+            Some(value) => value.checked_add(1).ok_or(VarIntError::Overflow)?,
+            None => 0,
+        };
+        self.encode(value)
+    }
+
+    /// Decodes a value written by [`encode_option`](VarCodec::encode_option).
+    fn decode_option(&self, bytes: &[u8]) -> Result<(Option<u64>, usize), VarIntError> {
+        let (value, len) = self.decode(bytes)?;
+        Ok((value.checked_sub(1), len))
+    }
+
+    /// Encodes a non-zero value as `v - 1`, so that no encoding is left unused and 1 takes a single byte.
+    fn encode_nonzero(&self, value: NonZeroU64) -> Result<Vec<u8>, VarIntError> {
+        self.encode(value.get() - 1)
+    }
+
+    /// Decodes a value written by [`encode_nonzero`](VarCodec::encode_nonzero), failing with
+    /// [`VarIntError::Overflow`] on `u64::MAX` whose successor doesn't fit.
+    fn decode_nonzero(&self, bytes: &[u8]) -> Result<(NonZeroU64, usize), VarIntError> {
+        let (value, len) = self.decode(bytes)?;
+        let value = value
+            .checked_add(1)
+            .and_then(NonZeroU64::new)
+            .ok_or(VarIntError::Overflow)?;
+        Ok((value, len))
+    }
+}
+
+impl VarCodec for VarInt {
+    fn max_len(&self) -> usize {
+        9
+    }
+
+    fn encoded_len_at(&self, bytes: &[u8]) -> Option<usize> {
+        bytes.first().map(|prefix| match prefix {
+            0xfd => 3,
+            0xfe => 5,
+            0xff => 9,
+            _ => 1,
+        })
+    }
+
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        Ok(VarInt::encode_append(value, out))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        VarInt::decode_full(bytes).map(|d| (d.value, d.len))
+    }
+
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        Ok(VarInt::encoded_len(value))
+    }
+}
+
+#[cfg(feature = "bigsize")]
+impl VarCodec for BigSize {
+    fn max_len(&self) -> usize {
+        9
+    }
+
+    fn encoded_len_at(&self, bytes: &[u8]) -> Option<usize> {
+        bytes.first().map(|prefix| match prefix {
+            0xfd => 3,
+            0xfe => 5,
+            0xff => 9,
+            _ => 1,
+        })
+    }
+
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        let bytes = BigSize::encode(value);
+        out.extend_from_slice(&bytes);
+        Ok(bytes.len())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        BigSize::decode(bytes)
+    }
+
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        Ok(BigSize::get_size(value))
+    }
+}
+
+#[cfg(feature = "leb128")]
+impl VarCodec for Leb128 {
+    fn max_len(&self) -> usize {
+        Leb128::MAX_LEN
+    }
+
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        Ok(Leb128::encode_append(value, out))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        Leb128::decode(bytes)
+    }
+
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        Ok(Leb128::get_size(value))
+    }
+}
+
+#[cfg(feature = "vlq")]
+impl VarCodec for Vlq {
+    fn max_len(&self) -> usize {
+        Vlq::MAX_LEN
+    }
+
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        Ok(Vlq::encode_append(value, out))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        Vlq::decode(bytes)
+    }
+
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        Ok(Vlq::get_size(value))
+    }
+}
+
+#[cfg(feature = "groups")]
+impl VarCodec for GroupCodec {
+    fn max_len(&self) -> usize {
+        GroupCodec::max_len(self)
+    }
+
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        Ok(GroupCodec::encode_append(self, value, out))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        GroupCodec::decode(self, bytes)
+    }
+
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        Ok(self.get_size(value))
+    }
+}
+
+#[cfg(feature = "quic")]
+impl VarCodec for Quic {
+    fn max_len(&self) -> usize {
+        8
+    }
+
+    fn encoded_len_at(&self, bytes: &[u8]) -> Option<usize> {
+        bytes.first().map(|first| 1 << (first >> 6))
+    }
+
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        Quic::encode_append(value, out)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        Quic::decode(bytes)
+    }
+
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        Quic::get_size(value)
+    }
+}
+
+/// The variable-length integer encodings values can be converted between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Codec {
+    /// Bitcoin compactSize, see [`VarInt`].
+    CompactSize,
+    /// Lightning BigSize, see [`BigSize`].
+    #[cfg(feature = "bigsize")]
+    BigSize,
+    /// Unsigned LEB128, see [`Leb128`].
+    #[cfg(feature = "leb128")]
+    Leb128,
+    /// Big-endian variable-length quantity, see [`Vlq`].
+    #[cfg(feature = "vlq")]
+    Vlq,
+    /// QUIC variable-length integer, limited to 62 bits, see [`Quic`].
+    #[cfg(feature = "quic")]
+    Quic,
+}
+
+impl Codec {
+    /// Every enabled encoding, in declaration order.
+    pub const ALL: &'static [Codec] = &[
+        Codec::CompactSize,
+        #[cfg(feature = "bigsize")]
+        Codec::BigSize,
+        #[cfg(feature = "leb128")]
+        Codec::Leb128,
+        #[cfg(feature = "vlq")]
+        Codec::Vlq,
+        #[cfg(feature = "quic")]
+        Codec::Quic,
+    ];
+
+    /// Stable one byte id of the encoding, as stored in container headers.
+    pub fn id(self) -> u8 {
+        match self {
+            Codec::CompactSize => 1,
+            #[cfg(feature = "bigsize")]
+            Codec::BigSize => 2,
+            #[cfg(feature = "leb128")]
+            Codec::Leb128 => 3,
+            #[cfg(feature = "vlq")]
+            Codec::Vlq => 4,
+            #[cfg(feature = "quic")]
+            Codec::Quic => 5,
+        }
+    }
+
+    /// The encoding of an id, or `None` when it is unknown or its feature is disabled.
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|codec| codec.id() == id)
+    }
+
+    /// The encoding this variant stands for.
+    fn inner(self) -> &'static dyn VarCodec {
+        match self {
+            Codec::CompactSize => &VarInt,
+            #[cfg(feature = "bigsize")]
+            Codec::BigSize => &BigSize,
+            #[cfg(feature = "leb128")]
+            Codec::Leb128 => &Leb128,
+            #[cfg(feature = "vlq")]
+            Codec::Vlq => &Vlq,
+            #[cfg(feature = "quic")]
+            Codec::Quic => &Quic,
+        }
+    }
+
+    /// Encodes a value into a new buffer.
+    pub fn encode(self, value: u64) -> Result<Vec<u8>, VarIntError> {
+        self.inner().encode(value)
+    }
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    ///
+    /// Fails with [`VarIntError::Overflow`] when the encoding can't represent the value (QUIC above 62 bits).
+    pub fn encode_append(self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        self.inner().encode_append(value, out)
+    }
+
+    /// Number of bytes of the encoding of `value`.
+    pub fn encoded_len(self, value: u64) -> Result<usize, VarIntError> {
+        self.inner().encoded_len(value)
+    }
+
+    /// Decodes a value, returning it with the number of bytes read.
+    pub fn decode(self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        self.inner().decode(bytes)
+    }
+}
+
+impl VarCodec for Codec {
+    fn max_len(&self) -> usize {
+        self.inner().max_len()
+    }
+
+    fn encoded_len_at(&self, bytes: &[u8]) -> Option<usize> {
+        self.inner().encoded_len_at(bytes)
+    }
+
+    fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        self.inner().encode_append(value, out)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        self.inner().decode(bytes)
+    }
+
+    fn encoded_len(&self, value: u64) -> Result<usize, VarIntError> {
+        self.inner().encoded_len(value)
+    }
+}
+
+/// Largest encoding [`decode_chunks`] gathers across chunks and the stream readers read into a stack buffer,
+/// above the `max_len` of every codec of this crate.
+pub(crate) const WINDOW_LEN: usize = 16;
+
+/// Decodes the first value of an input split into chunks, such as a buffer chain or a list of [`io::IoSlice`],
+/// returning it with the number of bytes read across the chunks.
+///
+/// A value held by the first non-empty chunk is decoded in place, only one straddling chunks is copied to a
+/// small stack buffer.
+///
+/// ```
+/// use bitcoin_varint::{decode_chunks, VarInt};
+///
+/// let chunks: [&[u8]; 3] = [&[0xfe, 0x00], &[], &[0x00, 0x01, 0x00, 0x2a]];
+/// assert_eq!(decode_chunks(&VarInt, &chunks), Ok((0x10000, 5)));
+/// ```
+pub fn decode_chunks<C: VarCodec + ?Sized, B: Deref<Target = [u8]>>(
+    codec: &C,
+    chunks: &[B],
+) -> Result<(u64, usize), VarIntError> {
+    let max_len = codec.max_len().min(WINDOW_LEN);
+    let mut chunks = chunks
+        .iter()
+        .map(|chunk| &chunk[..])
+        .filter(|chunk| !chunk.is_empty());
+    let first = chunks.next().ok_or(VarIntError::Truncated)?;
+    match codec.decode(first) {
+        Err(VarIntError::Truncated) if first.len() < max_len => {}
+        result => return result,
+    }
+    let mut window = [0u8; WINDOW_LEN];
+    window[..first.len()].copy_from_slice(first);
+    let mut filled = first.len();
+    for chunk in chunks {
+        let taken = chunk.len().min(max_len - filled);
+        window[filled..filled + taken].copy_from_slice(&chunk[..taken]);
+        filled += taken;
+        if filled == max_len {
+            break;
+        }
+    }
+    codec.decode(&window[..filled])
+}
+
+/// Re-encodes a buffer of concatenated values from one encoding to another, one value at a time.
+///
+/// ```
+/// use bitcoin_varint::{transcode, Codec};
+///
+/// let leb128 = [0x83, 0x04, 0x2a];
+/// # #[cfg(feature = "leb128")]
+/// assert_eq!(transcode(&leb128, Codec::Leb128, Codec::CompactSize).unwrap(), vec![0xfd, 0x03, 0x02, 0x2a]);
+/// ```
+pub fn transcode<F: VarCodec, T: VarCodec>(
+    input: &[u8],
+    from: F,
+    to: T,
+) -> Result<Vec<u8>, VarIntError> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut pos = 0;
+    while pos < input.len() {
+        let (value, len) = from.decode(&input[pos..])?;
+        to.encode_append(value, &mut output)?;
+        pos += len;
+    }
+    Ok(output)
+}
+
+/// Streaming [`transcode`]: re-encodes the values read from `reader` into `writer` through a fixed size buffer,
+/// returning the number of values.
+///
+/// Invalid input is an [`io::ErrorKind::InvalidData`] error wrapping the [`VarIntError`], and a value cut
+/// by the end of the input an [`io::ErrorKind::UnexpectedEof`] one.
+pub fn transcode_stream<F: VarCodec, T: VarCodec, R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    from: F,
+    to: T,
+) -> io::Result<u64> {
+    let mut output = Vec::with_capacity(CHUNK_LEN);
+    let count = for_each_value(&from, reader, |value, _| {
+        to.encode_append(value, &mut output)?;
+        if output.len() >= CHUNK_LEN {
+            writer.write_all(&output)?;
+            output.clear();
+        }
+        Ok(())
+    })?;
+    writer.write_all(&output)?;
+    Ok(count)
+}
+
+/// Size of the buffers used when streaming values.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Decodes the values read from `reader` with `codec`, calling `f` with each value and its encoded length.
+///
+/// Returns the number of values, with the errors of [`transcode_stream`].
+pub(crate) fn for_each_value<C: VarCodec + ?Sized, R: Read>(
+    codec: &C,
+    reader: &mut R,
+    mut f: impl FnMut(u64, usize) -> io::Result<()>,
+) -> io::Result<u64> {
+    let mut input = vec![0u8; CHUNK_LEN];
+    let mut filled = 0;
+    let mut count = 0;
+    loop {
+        let read = match reader.read(&mut input[filled..]) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        filled += read;
+        let mut pos = 0;
+        #[cfg(feature = "metrics")]
+        let chunk_start = count;
+        while pos < filled {
+            match codec.decode(&input[pos..filled]) {
+                Ok((value, len)) => {
+                    f(value, len)?;
+                    pos += len;
+                    count += 1;
+                }
+                // The rest of the value is in the next read.
+                Err(VarIntError::Truncated) if read > 0 => break,
+                Err(e) => {
+                    #[cfg(feature = "metrics")]
+                    if e == VarIntError::NonCanonical {
+                        crate::metrics::record(|r| r.non_canonical());
+                    }
+                    return Err(e.into());
+                }
+            }
+        }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(|r| r.values_decoded(count - chunk_start, pos as u64));
+        input.copy_within(pos..filled, 0);
+        filled -= pos;
+        if read == 0 {
+            return Ok(count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "leb128")]
+    use crate::test::Trickle;
+
+    #[test]
+    fn test_decode_into_narrower_types() {
+        assert_eq!(VarInt.decode_into::<u8>(&[0xfc]), Ok((0xfc, 1)));
+        assert_eq!(VarInt.decode_into::<u8>(&[0xfd, 0xff, 0x00]), Ok((0xff, 3)));
+        assert_eq!(
+            VarInt.decode_into::<u32>(&[0xff, 0, 0, 0, 0, 1, 0, 0, 0]),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(
+            Codec::CompactSize.decode_into::<usize>(&[0xfe, 0, 0, 0, 1]),
+            Ok((1 << 24, 5))
+        );
+        assert_eq!(
+            VarInt.decode_into::<u16>(&[0xfe, 0]),
+            Err(VarIntError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_option_and_nonzero() {
+        for value in [None, Some(0), Some(0xfb), Some(0xfc), Some(u64::MAX - 1)] {
+            let bytes = VarInt.encode_option(value).unwrap();
+            assert_eq!(VarInt.decode_option(&bytes), Ok((value, bytes.len())));
+        }
+        assert_eq!(
+            VarInt.encode_option(Some(u64::MAX)),
+            Err(VarIntError::Overflow)
+        );
+
+        let one = NonZeroU64::new(1).unwrap();
+        assert_eq!(VarInt.encode_nonzero(one), Ok(vec![0x00]));
+        assert_eq!(
+            VarInt.decode_nonzero(&[0xfc]),
+            Ok((NonZeroU64::new(0xfd).unwrap(), 1))
+        );
+        let max = NonZeroU64::new(u64::MAX).unwrap();
+        let bytes = VarInt.encode_nonzero(max).unwrap();
+        assert_eq!(VarInt.decode_nonzero(&bytes), Ok((max, 9)));
+        assert_eq!(
+            VarInt.decode_nonzero(&[0xff; 9]),
+            Err(VarIntError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_decode_chunks() {
+        let bytes = [0xff, 1, 2, 3, 4, 5, 6, 7, 8, 0xaa];
+        let expected = Ok((0x0807060504030201, 9));
+        for split in 0..bytes.len() {
+            let (head, tail) = bytes.split_at(split);
+            assert_eq!(decode_chunks(&VarInt, &[head, tail]), expected);
+            let slices = [io::IoSlice::new(head), io::IoSlice::new(tail)];
+            assert_eq!(decode_chunks(&VarInt, &slices), expected);
+        }
+        let single: Vec<Vec<u8>> = bytes.iter().map(|byte| vec![*byte]).collect();
+        assert_eq!(decode_chunks(&VarInt, &single), expected);
+
+        assert_eq!(
+            decode_chunks::<_, &[u8]>(&VarInt, &[]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(
+            decode_chunks(&VarInt, &[&[0xfd][..], &[], &[0x01][..]]),
+            Err(VarIntError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_decode_detailed() {
+        assert_eq!(VarInt.decode_detailed(&[0xfd, 0x00, 0x01]), Ok((0x100, 3)));
+        let error = Codec::CompactSize
+            .decode_detailed(&[0xff, 0x01])
+            .unwrap_err();
+        assert_eq!(error.error, VarIntError::Truncated);
+        assert_eq!((error.offset, error.index), (0, 0));
+        assert_eq!((error.expected, error.available), (Some(9), 2));
+        assert_eq!(error.snippet, vec![0xff, 0x01]);
+        assert_eq!(VarInt.decode_detailed(&[]).unwrap_err().snippet, vec![]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "bigsize", feature = "leb128"))]
+    fn test_transcode() {
+        let compact = [0xfd, 0x03, 0x02, 0x2a, 0xfe, 0x00, 0x00, 0x01, 0x00];
+        let leb128 = transcode(&compact, Codec::CompactSize, Codec::Leb128).unwrap();
+        assert_eq!(leb128, vec![0x83, 0x04, 0x2a, 0x80, 0x80, 0x04]);
+        let bigsize = transcode(&leb128, Codec::Leb128, Codec::BigSize).unwrap();
+        assert_eq!(
+            bigsize,
+            vec![0xfd, 0x02, 0x03, 0x2a, 0xfe, 0x00, 0x01, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_codec_encoded_len() {
+        for codec in Codec::ALL {
+            for value in [0, 63, 200, 300, 70000, 1 << 40] {
+                let encoded = codec.encode(value).unwrap();
+                assert_eq!(codec.encoded_len(value), Ok(encoded.len()));
+                assert_eq!(codec.decode(&encoded), Ok((value, encoded.len())));
+            }
+        }
+        #[cfg(feature = "quic")]
+        {
+            assert_eq!(Codec::Quic.encode(u64::MAX), Err(VarIntError::Overflow));
+            assert_eq!(
+                transcode(&[0xff; 9], Codec::CompactSize, Codec::Quic),
+                Err(VarIntError::Overflow)
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "bigsize",
+        feature = "leb128",
+        feature = "quic",
+        feature = "vlq"
+    ))]
+    fn test_var_codec_generic() {
+        // The unit structs and the enum are interchangeable.
+        let compact = [0xfd, 0x03, 0x02, 0x2a];
+        assert_eq!(
+            transcode(&compact, VarInt, Leb128),
+            transcode(&compact, Codec::CompactSize, Codec::Leb128)
+        );
+        let codecs: [&dyn VarCodec; 3] = [&Vlq, &Quic, &Codec::BigSize];
+        for codec in codecs {
+            let encoded = codec.encode(1 << 20).unwrap();
+            assert_eq!(codec.encoded_len(1 << 20), Ok(encoded.len()));
+            assert!(encoded.len() <= codec.max_len());
+            assert_eq!(codec.decode(&encoded), Ok((1 << 20, encoded.len())));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_transcode_stream() {
+        let values: Vec<u64> = (0..1000).map(|i| i * i * i * 31).collect();
+        let leb128 = crate::bulk::encode_all_with(&Leb128, &values).unwrap();
+        let mut output = Vec::new();
+        let count = transcode_stream(
+            &mut Trickle {
+                inner: &leb128[..],
+                limit: 5,
+            },
+            &mut output,
+            Leb128,
+            VarInt,
+        )
+        .unwrap();
+        assert_eq!(count, 1000);
+        assert_eq!(output, crate::bulk::encode_all(&values));
+
+        let err = transcode_stream(
+            &mut &leb128[..leb128.len() - 1],
+            &mut Vec::new(),
+            Leb128,
+            VarInt,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_transcode_truncated() {
+        assert_eq!(
+            transcode(&[0x2a, 0xfd, 0x03], Codec::CompactSize, Codec::Leb128),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use super::BlockCodec;
+use crate::{Leb128, VarIntError};
+
+/// Fixed-width bit-packing
+///
+/// Every value of a block is stored on the same number of bits, the width of the largest one,
+/// least significant bits first. As a [`BlockCodec`], a block starts with its bit width (one byte)
+/// and its value count (LEB128).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BitPacking;
+
+impl BitPacking {
+    /// Number of bits needed by the largest value, 0 when all values are zero.
+    pub fn bit_width(values: &[u64]) -> u8 {
+        let max = values.iter().fold(0, |acc, v| acc | v);
+        (64 - max.leading_zeros()) as u8
+    }
+
+    /// Number of bytes taken by `count` values packed on `width` bits.
+    pub fn packed_len(count: usize, width: u8) -> usize {
+        (count * width as usize).div_ceil(8)
+    }
+
+    /// Appends `values` packed on `width` bits to `out`. Bits of the values above `width` are dropped.
+    pub fn pack(values: &[u64], width: u8, out: &mut Vec<u8>) {
+        let width = width.min(64) as u32;
+        let mask = if width == 64 {
+            u64::MAX
+        } else {
+            (1 << width) - 1
+        };
+        let mut acc = 0u128;
+        let mut bits = 0;
+        for value in values {
+            acc |= ((value & mask) as u128) << bits;
+            bits += width;
+            while bits >= 8 {
+                out.push(acc as u8);
+                acc >>= 8;
+                bits -= 8;
+            }
+        }
+        if bits > 0 {
+            out.push(acc as u8);
+        }
+    }
+
+    /// Unpacks `count` values of `width` bits, appending them to `out` and returning the number of bytes read.
+    pub fn unpack(
+        bytes: &[u8],
+        width: u8,
+        count: usize,
+        out: &mut Vec<u64>,
+    ) -> Result<usize, VarIntError> {
+        if width > 64 {
+            return Err(VarIntError::Malformed);
+        }
+        let len = Self::packed_len(count, width);
+        let bytes = bytes.get(..len).ok_or(VarIntError::Truncated)?;
+        let width = width as u32;
+        let mask = if width == 64 {
+            u64::MAX
+        } else {
+            (1 << width) - 1
+        };
+        let mut input = bytes.iter();
+        let mut acc = 0u128;
+        let mut bits = 0;
+        out.reserve(count);
+        for _ in 0..count {
+            while bits < width {
+                acc |= (*input.next().unwrap() as u128) << bits;
+                bits += 8;
+            }
+            out.push(acc as u64 & mask);
+            acc >>= width;
+            bits -= width;
+        }
+        Ok(len)
+    }
+
+    /// [`BitPacking::pack`] for u32 values.
+    pub fn pack_u32(values: &[u32], width: u8, out: &mut Vec<u8>) {
+        let values: Vec<u64> = values.iter().map(|v| *v as u64).collect();
+        Self::pack(&values, width.min(32), out)
+    }
+
+    /// [`BitPacking::unpack`] for u32 values.
+    pub fn unpack_u32(
+        bytes: &[u8],
+        width: u8,
+        count: usize,
+        out: &mut Vec<u32>,
+    ) -> Result<usize, VarIntError> {
+        if width > 32 {
+            return Err(VarIntError::Malformed);
+        }
+        let mut values = Vec::with_capacity(count);
+        let len = Self::unpack(bytes, width, count, &mut values)?;
+        out.extend(values.into_iter().map(|v| v as u32));
+        Ok(len)
+    }
+}
+
+impl BlockCodec for BitPacking {
+    fn encode_block(&self, values: &[u64], out: &mut Vec<u8>) -> Result<(), VarIntError> {
+        let width = Self::bit_width(values);
+        out.push(width);
+        Leb128::encode_append(values.len() as u64, out);
+        Self::pack(values, width, out);
+        Ok(())
+    }
+
+    fn decode_block(&self, bytes: &[u8], out: &mut Vec<u64>) -> Result<usize, VarIntError> {
+        let width = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let (count, len) = Leb128::decode(&bytes[1..])?;
+        let count = usize::try_from(count).map_err(|_| VarIntError::Overflow)?;
+        // Zero width blocks take no bytes, bound them anyway to keep allocations in check.
+        if count
+            .checked_mul(width.max(1) as usize)
+            .is_none_or(|bits| bits / 8 > bytes.len())
+        {
+            return Err(VarIntError::Truncated);
+        }
+        Ok(1 + len + Self::unpack(&bytes[1 + len..], width, count, out)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_packing_pack() {
+        assert_eq!(BitPacking::bit_width(&[1, 5, 3]), 3);
+        assert_eq!(BitPacking::bit_width(&[0, 0]), 0);
+        let mut out = Vec::new();
+        BitPacking::pack(&[1, 5, 3], 3, &mut out);
+        assert_eq!(out, vec![0b11_101_001, 0b0]);
+        let mut values = Vec::new();
+        assert_eq!(BitPacking::unpack(&out, 3, 3, &mut values).unwrap(), 2);
+        assert_eq!(values, vec![1, 5, 3]);
+    }
+
+    #[test]
+    fn test_bit_packing_widths() {
+        let values: Vec<u64> = (0..100).map(|i| i * 0x0123_4567_89ab_cdef).collect();
+        for width in [1, 7, 13, 32, 63, 64] {
+            let mask = if width == 64 {
+                u64::MAX
+            } else {
+                (1 << width) - 1
+            };
+            let mut out = Vec::new();
+            BitPacking::pack(&values, width, &mut out);
+            assert_eq!(out.len(), BitPacking::packed_len(values.len(), width));
+            let mut unpacked = Vec::new();
+            BitPacking::unpack(&out, width, values.len(), &mut unpacked).unwrap();
+            assert!(unpacked.iter().zip(&values).all(|(u, v)| *u == v & mask));
+        }
+    }
+
+    #[test]
+    fn test_bit_packing_u32_block() {
+        let mut out = Vec::new();
+        BitPacking::pack_u32(&[7, 0, 65535], 16, &mut out);
+        let mut values = Vec::new();
+        BitPacking::unpack_u32(&out, 16, 3, &mut values).unwrap();
+        assert_eq!(values, vec![7, 0, 65535]);
+
+        let mut block = Vec::new();
+        BitPacking.encode_block(&[7, 0, 65535], &mut block).unwrap();
+        assert_eq!(block.len(), 2 + 6);
+        let mut decoded = Vec::new();
+        assert_eq!(BitPacking.decode_block(&block, &mut decoded).unwrap(), 8);
+        assert_eq!(decoded, vec![7, 0, 65535]);
+        assert_eq!(
+            BitPacking.decode_block(&block[..7], &mut decoded),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
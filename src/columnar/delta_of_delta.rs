@@ -0,0 +1,273 @@
+use super::BlockCodec;
+use crate::{Codec, VarIntError};
+
+/// How the delta-of-delta block codec writes its residuals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Residuals {
+    /// Each residual zigzag-encoded with a varint [`Codec`], byte aligned.
+    Varint(Codec),
+    /// Gorilla's variable bit-width buckets: a single `0` bit for a zero residual, else a prefix of `10`,
+    /// `110`, `1110` or `1111` followed by the zigzag residual on 7, 9, 12 or 64 bits.
+    Bits,
+}
+
+/// Delta-of-delta block codec, for timestamps sampled at a regular interval
+///
+/// Each block stores its value count and first value, then for every following value the change of its
+/// delta to the previous one. A steady interval gives residuals of zero, which take one bit each with
+/// [`Residuals::Bits`] and one byte with [`Residuals::Varint`], where plain deltas would take the width of
+/// the interval. Arithmetic wraps, so any sequence of values round-trips, not only increasing ones.
+///
+/// The header uses the varint [`Codec`] of the residuals or LEB128 with [`Residuals::Bits`].
+///
+/// https://www.vldb.org/pvldb/vol8/p1816-teller.pdf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeltaOfDelta {
+    residuals: Residuals,
+}
+
+impl DeltaOfDelta {
+    pub fn new(residuals: Residuals) -> Self {
+        DeltaOfDelta { residuals }
+    }
+
+    fn header_codec(&self) -> Codec {
+        match self.residuals {
+            Residuals::Varint(codec) => codec,
+            Residuals::Bits => Codec::Leb128,
+        }
+    }
+}
+
+impl Default for DeltaOfDelta {
+    /// Bit-level residuals, as in Gorilla.
+    fn default() -> Self {
+        DeltaOfDelta::new(Residuals::Bits)
+    }
+}
+
+impl BlockCodec for DeltaOfDelta {
+    fn encode_block(&self, values: &[u64], out: &mut Vec<u8>) -> Result<(), VarIntError> {
+        let codec = self.header_codec();
+        codec.encode_append(values.len() as u64, out)?;
+        let Some((&first, rest)) = values.split_first() else {
+            return Ok(());
+        };
+        codec.encode_append(first, out)?;
+        let (mut previous, mut delta) = (first, 0u64);
+        let residuals = rest.iter().map(|&value| {
+            let next = value.wrapping_sub(previous);
+            let residual = zigzag(next.wrapping_sub(delta) as i64);
+            (previous, delta) = (value, next);
+            residual
+        });
+        match self.residuals {
+            Residuals::Varint(_) => {
+                for residual in residuals {
+                    codec.encode_append(residual, out)?;
+                }
+            }
+            Residuals::Bits => {
+                let mut writer = BitWriter { out, len: 0 };
+                for residual in residuals {
+                    let (prefix, prefix_len, width) = bucket(residual);
+                    writer.write(prefix, prefix_len);
+                    writer.write(residual, width);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_block(&self, bytes: &[u8], out: &mut Vec<u64>) -> Result<usize, VarIntError> {
+        let codec = self.header_codec();
+        let (count, mut pos) = codec.decode(bytes)?;
+        if count == 0 {
+            return Ok(pos);
+        }
+        let (first, len) = codec.decode(&bytes[pos..])?;
+        pos += len;
+        // Every residual takes at least one bit, or one byte when byte aligned.
+        let available = (bytes.len() - pos) as u64;
+        let min_len = match self.residuals {
+            Residuals::Varint(_) => count - 1,
+            Residuals::Bits => (count - 1).div_ceil(8),
+        };
+        if min_len > available {
+            return Err(VarIntError::Truncated);
+        }
+        out.reserve(count as usize);
+        out.push(first);
+        let (mut value, mut delta) = (first, 0u64);
+        let mut reader = BitReader {
+            bytes: &bytes[pos..],
+            pos: 0,
+        };
+        for _ in 1..count {
+            let residual = match self.residuals {
+                Residuals::Varint(_) => {
+                    let (residual, len) = codec.decode(&bytes[pos..])?;
+                    pos += len;
+                    residual
+                }
+                Residuals::Bits => {
+                    let mut prefix_len = 0;
+                    while prefix_len < 4 && reader.read(1)? == 1 {
+                        prefix_len += 1;
+                    }
+                    reader.read(BUCKET_WIDTHS[prefix_len])?
+                }
+            };
+            delta = delta.wrapping_add(unzigzag(residual) as u64);
+            value = value.wrapping_add(delta);
+            out.push(value);
+        }
+        if self.residuals == Residuals::Bits {
+            pos += reader.pos.div_ceil(8);
+        }
+        Ok(pos)
+    }
+}
+
+/// Residual widths by number of leading one bits of the prefix.
+const BUCKET_WIDTHS: [u8; 5] = [0, 7, 9, 12, 64];
+
+/// Prefix bits, prefix length and residual width of the smallest bucket holding `residual`.
+fn bucket(residual: u64) -> (u64, u8, u8) {
+    match residual {
+        0 => (0b0, 1, 0),
+        r if r < 1 << 7 => (0b10, 2, 7),
+        r if r < 1 << 9 => (0b110, 3, 9),
+        r if r < 1 << 12 => (0b1110, 4, 12),
+        _ => (0b1111, 4, 64),
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Appends bits most significant first, the last byte padded with zeros.
+struct BitWriter<'a> {
+    out: &'a mut Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter<'_> {
+    fn write(&mut self, value: u64, width: u8) {
+        for bit in (0..width).rev() {
+            if self.len.is_multiple_of(8) {
+                self.out.push(0);
+            }
+            if value >> bit & 1 == 1 {
+                *self.out.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, width: u8) -> Result<u64, VarIntError> {
+        let mut value = 0;
+        for _ in 0..width {
+            let byte = self.bytes.get(self.pos / 8).ok_or(VarIntError::Truncated)?;
+            value = value << 1 | (byte >> (7 - self.pos % 8) & 1) as u64;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::columnar::{decode_blocks, encode_blocks, FrameOfReference};
+
+    #[test]
+    fn test_delta_of_delta_block() {
+        let timestamps = [1_700_000_000, 1_700_000_060, 1_700_000_120, 1_700_000_181];
+        let codec = DeltaOfDelta::default();
+        let mut out = Vec::new();
+        codec.encode_block(&timestamps, &mut out).unwrap();
+        // 60 zigzags to 120 in the 7 bits bucket, then a zero residual and a residual of 1 -> 2.
+        assert_eq!(out[..6], [0x04, 0x80, 0xe2, 0xcf, 0xaa, 0x06]);
+        assert_eq!(out[6..], [0b1011_1100, 0b0010_0000, 0b0100_0000]);
+        let mut values = Vec::new();
+        assert_eq!(codec.decode_block(&out, &mut values), Ok(out.len()));
+        assert_eq!(values, timestamps);
+
+        let codec = DeltaOfDelta::new(Residuals::Varint(Codec::Leb128));
+        out.clear();
+        codec.encode_block(&timestamps, &mut out).unwrap();
+        assert_eq!(out[6..], [0x78, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn test_delta_of_delta_blocks() {
+        let mut state = 0x9e3779b97f4a7c15u64;
+        let regular: Vec<u64> = (0..1000u64)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                1_700_000_000 + 15 * i + state % 2
+            })
+            .collect();
+        let extremes = vec![u64::MAX, 0, 1, u64::MAX - 1, 1 << 63, 5, 5, 5];
+        for residuals in [Residuals::Bits, Residuals::Varint(Codec::CompactSize)] {
+            let codec = DeltaOfDelta::new(residuals);
+            for values in [&regular, &extremes, &vec![42]] {
+                let bytes = encode_blocks(&codec, values, 128).unwrap();
+                assert_eq!(&decode_blocks(&codec, &bytes).unwrap(), values);
+            }
+        }
+        // A second of jitter every 15 takes 1 or 9 bits per value, against 2 bytes with frame of reference.
+        let bits = encode_blocks(&DeltaOfDelta::default(), &regular, 128)
+            .unwrap()
+            .len();
+        let reference = encode_blocks(&FrameOfReference::default(), &regular, 128)
+            .unwrap()
+            .len();
+        assert!(bits * 2 < reference, "{} {}", bits, reference);
+    }
+
+    #[test]
+    fn test_delta_of_delta_errors() {
+        let codec = DeltaOfDelta::default();
+        let mut values = Vec::new();
+        assert_eq!(codec.decode_block(&[0x00], &mut values), Ok(1));
+        assert_eq!(
+            codec.decode_block(&[0x0a, 0x00, 0xff], &mut values),
+            Err(VarIntError::Truncated)
+        );
+        // The last residual announces 64 bits.
+        assert_eq!(
+            codec.decode_block(&[0x02, 0x00, 0xf0, 0x00], &mut values),
+            Err(VarIntError::Truncated)
+        );
+        let varint = DeltaOfDelta::new(Residuals::Varint(Codec::Leb128));
+        assert_eq!(
+            varint.decode_block(&[0x03, 0x00, 0x00], &mut values),
+            Err(VarIntError::Truncated)
+        );
+        #[cfg(feature = "quic")]
+        assert_eq!(
+            encode_blocks(
+                &DeltaOfDelta::new(Residuals::Varint(Codec::Quic)),
+                &[0, u64::MAX / 2],
+                8
+            ),
+            Err(VarIntError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use super::BlockCodec;
+use crate::{Codec, VarIntError};
+use std::collections::HashMap;
+
+/// Dictionary block codec, for columns with few distinct values
+///
+/// Each block stores its value count and a dictionary of its distinct values, most frequent first, followed
+/// by the index of every value in the dictionary. Enum-like columns then take a byte per value, whatever
+/// the width of the values, and the dictionary is only paid once per block.
+///
+/// The header, the dictionary and the indices use the same varint [`Codec`]: encoding fails with its error
+/// on a value it can't represent, [`VarIntError::Overflow`] for values above 62 bits with [`Codec::Quic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dictionary {
+    codec: Codec,
+}
+
+impl Dictionary {
+    pub fn new(codec: Codec) -> Self {
+        Dictionary { codec }
+    }
+}
+
+impl Default for Dictionary {
+    /// LEB128, which keeps indices of dictionaries up to 128 values on a single byte.
+    fn default() -> Self {
+        Dictionary::new(Codec::Leb128)
+    }
+}
+
+impl BlockCodec for Dictionary {
+    fn encode_block(&self, values: &[u64], out: &mut Vec<u8>) -> Result<(), VarIntError> {
+        let mut counts = HashMap::new();
+        for value in values {
+            *counts.entry(*value).or_insert(0usize) += 1;
+        }
+        let mut dictionary: Vec<(u64, usize)> = counts.into_iter().collect();
+        dictionary.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let indices: HashMap<u64, u64> = dictionary
+            .iter()
+            .enumerate()
+            .map(|(index, (value, _))| (*value, index as u64))
+            .collect();
+
+        let mut write = |value| self.codec.encode_append(value, out);
+        write(values.len() as u64)?;
+        write(dictionary.len() as u64)?;
+        for (value, _) in &dictionary {
+            write(*value)?;
+        }
+        for value in values {
+            write(indices[value])?;
+        }
+        Ok(())
+    }
+
+    fn decode_block(&self, bytes: &[u8], out: &mut Vec<u64>) -> Result<usize, VarIntError> {
+        let (count, mut pos) = self.codec.decode(bytes)?;
+        let (len, read) = self.codec.decode(&bytes[pos..])?;
+        pos += read;
+        // Every dictionary value and index takes at least one byte.
+        if count.saturating_add(len) > (bytes.len() - pos) as u64 {
+            return Err(VarIntError::Truncated);
+        }
+        let mut dictionary = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            let (value, read) = self.codec.decode(&bytes[pos..])?;
+            pos += read;
+            dictionary.push(value);
+        }
+        out.reserve(count as usize);
+        for _ in 0..count {
+            let (index, read) = self.codec.decode(&bytes[pos..])?;
+            pos += read;
+            let value = usize::try_from(index)
+                .ok()
+                .and_then(|index| dictionary.get(index))
+                .ok_or(VarIntError::Malformed)?;
+            out.push(*value);
+        }
+        Ok(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::columnar::{decode_blocks, encode_blocks};
+    use crate::Leb128;
+
+    #[test]
+    fn test_dictionary_block() {
+        let codec = Dictionary::default();
+        let values = [u64::MAX, 200, u64::MAX, u64::MAX, 200, 7];
+        let mut out = Vec::new();
+        codec.encode_block(&values, &mut out).unwrap();
+        // 6 values, then the 3 entries by decreasing frequency, then the indices.
+        assert_eq!(out[..2], [0x06, 0x03]);
+        assert_eq!(out[2..12], Leb128::encode(u64::MAX)[..]);
+        assert_eq!(
+            out[12..],
+            [0xc8, 0x01, 0x07, 0x00, 0x01, 0x00, 0x00, 0x01, 0x02]
+        );
+        let mut decoded = Vec::new();
+        assert_eq!(codec.decode_block(&out, &mut decoded), Ok(out.len()));
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn test_dictionary_blocks() {
+        let states = [0x8000_0000_0000_0001, 0x8000_0000_0000_0002, 42];
+        let values: Vec<u64> = (0..2000).map(|i| states[i * i % 7 % 3]).collect();
+        let codec = Dictionary::new(Codec::CompactSize);
+        let bytes = encode_blocks(&codec, &values, 500).unwrap();
+        assert!(bytes.len() < values.len() + 4 * 40, "{}", bytes.len());
+        assert_eq!(decode_blocks(&codec, &bytes).unwrap(), values);
+        assert_eq!(
+            decode_blocks(&codec, &encode_blocks(&codec, &[], 8).unwrap()).unwrap(),
+            []
+        );
+    }
+
+    #[test]
+    fn test_dictionary_errors() {
+        let codec = Dictionary::default();
+        let mut values = Vec::new();
+        assert_eq!(
+            codec.decode_block(&[0x02, 0x01, 0x05, 0x00, 0x01], &mut values),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            codec.decode_block(&[0x03, 0x01, 0x05, 0x00, 0x00], &mut values),
+            Err(VarIntError::Truncated)
+        );
+        #[cfg(feature = "quic")]
+        assert_eq!(
+            encode_blocks(&Dictionary::new(Codec::Quic), &[1, u64::MAX, 1], 8),
+            Err(VarIntError::Overflow)
+        );
+    }
+}
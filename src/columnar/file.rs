@@ -0,0 +1,420 @@
+use super::{BitPacking, BlockCodec, DeltaOfDelta, Dictionary, FrameOfReference, RunLength};
+use crate::{Leb128, VarIntError};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// First and last bytes of a column file.
+pub const COLUMN_MAGIC: [u8; 4] = *b"VCOL";
+
+/// Magic, codec id, block size (u32 LE) and value count (u64 LE).
+const HEADER_LEN: u64 = 4 + 1 + 4 + 8;
+
+/// Footer length (u32 LE) and magic.
+const TRAILER_LEN: u64 = 4 + 4;
+
+/// Block codec of a column file, stored as a one byte id in its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnCodec {
+    BitPacking,
+    /// Frame of reference with LEB128 residuals.
+    FrameOfReference,
+    /// Delta of delta with bit-level residuals.
+    DeltaOfDelta,
+    /// Run length with LEB128 values, runs of 3 or more.
+    RunLength,
+    /// Dictionary with LEB128 indices.
+    Dictionary,
+}
+
+impl ColumnCodec {
+    pub const ALL: &'static [ColumnCodec] = &[
+        ColumnCodec::BitPacking,
+        ColumnCodec::FrameOfReference,
+        ColumnCodec::DeltaOfDelta,
+        ColumnCodec::RunLength,
+        ColumnCodec::Dictionary,
+    ];
+
+    pub fn id(self) -> u8 {
+        match self {
+            ColumnCodec::BitPacking => 1,
+            ColumnCodec::FrameOfReference => 2,
+            ColumnCodec::DeltaOfDelta => 3,
+            ColumnCodec::RunLength => 4,
+            ColumnCodec::Dictionary => 5,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|codec| codec.id() == id)
+    }
+
+    /// Number of values announced by the header of the block at the start of `bytes`, read without
+    /// decoding the block.
+    fn block_count(self, bytes: &[u8]) -> Result<u64, VarIntError> {
+        let header = match self {
+            ColumnCodec::BitPacking => bytes.get(1..).ok_or(VarIntError::Truncated)?,
+            ColumnCodec::FrameOfReference => &bytes[Leb128::decode(bytes)?.1..],
+            _ => bytes,
+        };
+        Ok(Leb128::decode(header)?.0)
+    }
+}
+
+impl BlockCodec for ColumnCodec {
+    fn encode_block(&self, values: &[u64], out: &mut Vec<u8>) -> Result<(), VarIntError> {
+        match self {
+            ColumnCodec::BitPacking => BitPacking.encode_block(values, out),
+            ColumnCodec::FrameOfReference => FrameOfReference::default().encode_block(values, out),
+            ColumnCodec::DeltaOfDelta => DeltaOfDelta::default().encode_block(values, out),
+            ColumnCodec::RunLength => RunLength::default().encode_block(values, out),
+            ColumnCodec::Dictionary => Dictionary::default().encode_block(values, out),
+        }
+    }
+
+    fn decode_block(&self, bytes: &[u8], out: &mut Vec<u64>) -> Result<usize, VarIntError> {
+        match self {
+            ColumnCodec::BitPacking => BitPacking.decode_block(bytes, out),
+            ColumnCodec::FrameOfReference => FrameOfReference::default().decode_block(bytes, out),
+            ColumnCodec::DeltaOfDelta => DeltaOfDelta::default().decode_block(bytes, out),
+            ColumnCodec::RunLength => RunLength::default().decode_block(bytes, out),
+            ColumnCodec::Dictionary => Dictionary::default().decode_block(bytes, out),
+        }
+    }
+}
+
+/// Writer of a single column of u64 values
+///
+/// The file starts with a header: [`COLUMN_MAGIC`], the [`ColumnCodec`] id, the number of values per block and
+/// the number of values. Blocks of values encoded with the codec follow, then a footer with the offset of
+/// every block, as LEB128 deltas, its length as a u32 and [`COLUMN_MAGIC`] again. Offsets are relative to the
+/// start of the header, so a column can be embedded in a larger file.
+///
+/// The count of the header is written by [`finish`](ColumnWriter::finish), which seeks back to it: a
+/// column left unfinished has neither count nor footer, and is rejected by [`ColumnReader`].
+pub struct ColumnWriter<W> {
+    writer: W,
+    codec: ColumnCodec,
+    block_size: usize,
+    /// Position of the header in `writer`.
+    start: u64,
+    pending: Vec<u64>,
+    offsets: Vec<u64>,
+    written: u64,
+    count: u64,
+}
+
+impl<W: Write + Seek> ColumnWriter<W> {
+    /// Writes the header of a column of blocks of `block_size` values, at least 1.
+    pub fn new(mut writer: W, codec: ColumnCodec, block_size: u32) -> io::Result<Self> {
+        let block_size = block_size.max(1);
+        let start = writer.stream_position()?;
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(&COLUMN_MAGIC);
+        header.push(codec.id());
+        header.extend_from_slice(&block_size.to_le_bytes());
+        header.extend_from_slice(&0u64.to_le_bytes());
+        writer.write_all(&header)?;
+        Ok(ColumnWriter {
+            writer,
+            codec,
+            block_size: block_size as usize,
+            start,
+            pending: Vec::with_capacity(block_size.min(1 << 16) as usize),
+            offsets: Vec::new(),
+            written: HEADER_LEN,
+            count: 0,
+        })
+    }
+
+    /// Appends values, writing every block as soon as it is full.
+    ///
+    /// A block the codec can't encode fails with an [`io::ErrorKind::InvalidInput`] error wrapping the
+    /// [`VarIntError`].
+    pub fn write_values(&mut self, values: &[u64]) -> io::Result<()> {
+        for value in values {
+            self.pending.push(*value);
+            if self.pending.len() == self.block_size {
+                self.write_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the last block, the footer and the count of the header, returning the underlying writer
+    /// positioned at the end of the column.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.pending.is_empty() {
+            self.write_block()?;
+        }
+        let mut footer = Vec::new();
+        let mut last = 0;
+        for offset in &self.offsets {
+            Leb128::encode_append(offset - last, &mut footer);
+            last = *offset;
+        }
+        let footer_len =
+            u32::try_from(footer.len()).map_err(|_| io::Error::from(VarIntError::TooLarge))?;
+        footer.extend_from_slice(&footer_len.to_le_bytes());
+        footer.extend_from_slice(&COLUMN_MAGIC);
+        self.writer.write_all(&footer)?;
+        let end = self.writer.stream_position()?;
+        self.writer
+            .seek(SeekFrom::Start(self.start + HEADER_LEN - 8))?;
+        self.writer.write_all(&self.count.to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        let mut block = Vec::new();
+        self.codec
+            .encode_block(&self.pending, &mut block)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.writer.write_all(&block)?;
+        self.offsets.push(self.written);
+        self.written += block.len() as u64;
+        self.count += self.pending.len() as u64;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Random-access reader of a column written by [`ColumnWriter`], which reads only the blocks asked for.
+pub struct ColumnReader<R> {
+    reader: R,
+    codec: ColumnCodec,
+    block_size: u64,
+    count: u64,
+    start: u64,
+    /// Offsets of the blocks, then of the footer.
+    offsets: Vec<u64>,
+}
+
+impl<R: Read + Seek> ColumnReader<R> {
+    /// Reads the header at the current position of `reader` and the footer at its end.
+    ///
+    /// Files that aren't column files or whose header and footer disagree fail with an
+    /// [`io::ErrorKind::InvalidData`] error wrapping [`VarIntError::Malformed`].
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let start = reader.stream_position()?;
+        let mut header = [0u8; HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if header[..4] != COLUMN_MAGIC {
+            return Err(VarIntError::Malformed.into());
+        }
+        let codec = ColumnCodec::from_id(header[4]).ok_or(VarIntError::Malformed)?;
+        let block_size = u32::from_le_bytes(header[5..9].try_into().unwrap()) as u64;
+        let count = u64::from_le_bytes(header[9..].try_into().unwrap());
+
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end < start + HEADER_LEN + TRAILER_LEN {
+            return Err(VarIntError::Truncated.into());
+        }
+        let mut trailer = [0u8; TRAILER_LEN as usize];
+        reader.seek(SeekFrom::Start(end - TRAILER_LEN))?;
+        reader.read_exact(&mut trailer)?;
+        if trailer[4..] != COLUMN_MAGIC || block_size == 0 {
+            return Err(VarIntError::Malformed.into());
+        }
+        let footer_len = u32::from_le_bytes(trailer[..4].try_into().unwrap()) as u64;
+        let footer_start = (end - TRAILER_LEN - start)
+            .checked_sub(footer_len)
+            .filter(|footer_start| *footer_start >= HEADER_LEN)
+            .ok_or(VarIntError::Malformed)?;
+        let mut footer = vec![0u8; footer_len as usize];
+        reader.seek(SeekFrom::Start(start + footer_start))?;
+        reader.read_exact(&mut footer)?;
+
+        let mut offsets = Vec::with_capacity(count.div_ceil(block_size).min(1 << 16) as usize + 1);
+        let (mut pos, mut offset) = (0, 0u64);
+        while pos < footer.len() {
+            let (delta, len) = Leb128::decode(&footer[pos..])?;
+            pos += len;
+            offset = offset.checked_add(delta).ok_or(VarIntError::Overflow)?;
+            offsets.push(offset);
+        }
+        offsets.push(footer_start);
+        let blocks_ok = offsets.len() as u64 == count.div_ceil(block_size) + 1
+            && offsets.first().is_none_or(|first| *first >= HEADER_LEN)
+            && offsets.windows(2).all(|w| w[0] < w[1]);
+        if !blocks_ok {
+            return Err(VarIntError::Malformed.into());
+        }
+        Ok(ColumnReader {
+            reader,
+            codec,
+            block_size,
+            count,
+            start,
+            offsets,
+        })
+    }
+
+    /// Number of values of the column.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn codec(&self) -> ColumnCodec {
+        self.codec
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Reads and decodes block `index`, failing with [`VarIntError::Malformed`] when it doesn't hold the
+    /// number of values implied by the header.
+    ///
+    /// The count of the block is checked before decoding it, so that a corrupt block can't announce more values
+    /// than the block size.
+    pub fn read_block(&mut self, index: usize) -> io::Result<Vec<u64>> {
+        if index >= self.block_count() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "block index out of range",
+            ));
+        }
+        let (from, to) = (self.offsets[index], self.offsets[index + 1]);
+        let mut bytes = vec![0u8; (to - from) as usize];
+        self.reader.seek(SeekFrom::Start(self.start + from))?;
+        self.reader.read_exact(&mut bytes)?;
+        let first = index as u64 * self.block_size;
+        let expected = self.block_size.min(self.count - first);
+        if self.codec.block_count(&bytes)? != expected {
+            return Err(VarIntError::Malformed.into());
+        }
+        let mut values = Vec::new();
+        let len = self.codec.decode_block(&bytes, &mut values)?;
+        if len != bytes.len() || values.len() as u64 != expected {
+            return Err(VarIntError::Malformed.into());
+        }
+        Ok(values)
+    }
+
+    /// Value at `index`, read with the block holding it.
+    pub fn get(&mut self, index: u64) -> io::Result<Option<u64>> {
+        if index >= self.count {
+            return Ok(None);
+        }
+        let block = self.read_block((index / self.block_size) as usize)?;
+        Ok(Some(block[(index % self.block_size) as usize]))
+    }
+
+    /// Reads every value of the column.
+    pub fn read_all(&mut self) -> io::Result<Vec<u64>> {
+        let mut values = Vec::new();
+        for index in 0..self.block_count() {
+            values.extend(self.read_block(index)?);
+        }
+        Ok(values)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn write_column(codec: ColumnCodec, values: &[u64], block_size: u32) -> Vec<u8> {
+        let mut writer = ColumnWriter::new(Cursor::new(Vec::new()), codec, block_size).unwrap();
+        for chunk in values.chunks(7) {
+            writer.write_values(chunk).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_column_file_round_trip() {
+        let values: Vec<u64> = (0..1000u64)
+            .map(|i| 1_700_000_000 + 60 * i + i % 3)
+            .collect();
+        for &codec in ColumnCodec::ALL {
+            let bytes = write_column(codec, &values, 128);
+            assert_eq!(bytes[..4], COLUMN_MAGIC);
+            assert_eq!(bytes[4], codec.id());
+            assert_eq!(bytes[bytes.len() - 4..], COLUMN_MAGIC);
+            let mut reader = ColumnReader::new(Cursor::new(&bytes)).unwrap();
+            assert_eq!((reader.len(), reader.block_count()), (1000, 8));
+            assert_eq!(reader.codec(), codec);
+            assert_eq!(reader.get(999).unwrap(), Some(values[999]));
+            assert_eq!(reader.get(130).unwrap(), Some(values[130]));
+            assert_eq!(reader.get(1000).unwrap(), None);
+            assert_eq!(reader.read_all().unwrap(), values);
+        }
+
+        let empty = write_column(ColumnCodec::RunLength, &[], 16);
+        let mut reader = ColumnReader::new(Cursor::new(&empty)).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(reader.read_all().unwrap(), []);
+    }
+
+    #[test]
+    fn test_column_file_embedded() {
+        let mut file = vec![0xaa; 10];
+        let mut cursor = Cursor::new(&mut file);
+        cursor.seek(SeekFrom::End(0)).unwrap();
+        let mut writer = ColumnWriter::new(cursor, ColumnCodec::Dictionary, 4).unwrap();
+        writer.write_values(&[3, 3, 1, 3, 3, 2]).unwrap();
+        writer.finish().unwrap();
+
+        let mut cursor = Cursor::new(&file);
+        cursor.seek(SeekFrom::Start(10)).unwrap();
+        let mut reader = ColumnReader::new(cursor).unwrap();
+        assert_eq!(reader.read_block(1).unwrap(), [3, 2]);
+        assert_eq!(reader.len(), 6);
+    }
+
+    #[test]
+    fn test_column_file_errors() {
+        let bytes = write_column(ColumnCodec::FrameOfReference, &[1, 2, 3, 4, 5], 2);
+        let malformed = |bytes: &[u8]| {
+            let err = ColumnReader::new(Cursor::new(bytes)).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        };
+        malformed(&bytes[..bytes.len() - 1]);
+        let mut wrong = bytes.clone();
+        wrong[4] = 0xee;
+        malformed(&wrong);
+        let mut wrong = bytes.clone();
+        wrong[9] = 7;
+        malformed(&wrong);
+
+        // A block whose bytes no longer decode to its values.
+        let mut wrong = bytes.clone();
+        wrong[HEADER_LEN as usize + 1] = 3;
+        let mut reader = ColumnReader::new(Cursor::new(&wrong)).unwrap();
+        assert!(reader.read_block(0).is_err());
+        assert!(reader.read_block(2).is_ok());
+        assert!(reader.read_block(3).is_err());
+
+        // A run-length block of 11 bytes announcing a run of 2^33 zeros is refused before decoding it.
+        let mut bytes = write_column(ColumnCodec::RunLength, &[0, 0, 0, 1, 2, 3, 4, 5, 6, 7], 16);
+        let block = HEADER_LEN as usize..HEADER_LEN as usize + 11;
+        assert_eq!(
+            bytes[block.clone()],
+            [0x0a, 0x06, 0x00, 0x0f, 1, 2, 3, 4, 5, 6, 7]
+        );
+        bytes[block].copy_from_slice(&[
+            0x80, 0x80, 0x80, 0x80, 0x20, 0x80, 0x80, 0x80, 0x80, 0x40, 0x00,
+        ]);
+        let mut reader = ColumnReader::new(Cursor::new(&bytes)).unwrap();
+        let err = reader.read_block(0).unwrap_err();
+        assert_eq!(VarIntError::from_io(&err), Some(VarIntError::Malformed));
+
+        for &codec in ColumnCodec::ALL {
+            let bytes = write_column(codec, &[5, 6, 7], 2);
+            let (start, end) = (HEADER_LEN as usize, bytes.len() - TRAILER_LEN as usize);
+            assert_eq!(codec.block_count(&bytes[start..end]), Ok(2));
+        }
+    }
+}
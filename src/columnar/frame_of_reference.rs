@@ -0,0 +1,110 @@
+use super::BlockCodec;
+use crate::{Codec, VarIntError};
+
+/// Frame-of-reference block codec
+///
+/// Each block stores its minimum (the reference) and its value count in a header,
+/// followed by the residuals `value - reference`. Clustered values such as timestamps or block heights
+/// then take a byte or two each instead of their full width.
+///
+/// The header and the residuals use the same varint [`Codec`]: encoding fails with its error on a residual it
+/// can't represent, [`VarIntError::Overflow`] for values spanning more than 62 bits with [`Codec::Quic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameOfReference {
+    codec: Codec,
+}
+
+impl FrameOfReference {
+    pub fn new(codec: Codec) -> Self {
+        FrameOfReference { codec }
+    }
+}
+
+impl Default for FrameOfReference {
+    /// Residuals in LEB128, which keeps values up to 127 on a single byte.
+    fn default() -> Self {
+        FrameOfReference::new(Codec::Leb128)
+    }
+}
+
+impl BlockCodec for FrameOfReference {
+    fn encode_block(&self, values: &[u64], out: &mut Vec<u8>) -> Result<(), VarIntError> {
+        let reference = values.iter().copied().min().unwrap_or(0);
+        self.codec.encode_append(reference, out)?;
+        self.codec.encode_append(values.len() as u64, out)?;
+        for value in values {
+            self.codec.encode_append(value - reference, out)?;
+        }
+        Ok(())
+    }
+
+    fn decode_block(&self, bytes: &[u8], out: &mut Vec<u64>) -> Result<usize, VarIntError> {
+        let (reference, mut pos) = self.codec.decode(bytes)?;
+        let (count, len) = self.codec.decode(&bytes[pos..])?;
+        pos += len;
+        // Every residual takes at least one byte.
+        if count > (bytes.len() - pos) as u64 {
+            return Err(VarIntError::Truncated);
+        }
+        out.reserve(count as usize);
+        for _ in 0..count {
+            let (residual, len) = self.codec.decode(&bytes[pos..])?;
+            pos += len;
+            let value = reference
+                .checked_add(residual)
+                .ok_or(VarIntError::Overflow)?;
+            out.push(value);
+        }
+        Ok(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::columnar::{decode_blocks, encode_blocks};
+
+    #[test]
+    fn test_frame_of_reference_block() {
+        let codec = FrameOfReference::default();
+        let mut out = Vec::new();
+        codec
+            .encode_block(&[800_005, 800_000, 800_130], &mut out)
+            .unwrap();
+        assert_eq!(out, vec![0x80, 0xea, 0x30, 0x03, 0x05, 0x00, 0x82, 0x01]);
+        let mut values = Vec::new();
+        assert_eq!(codec.decode_block(&out, &mut values).unwrap(), out.len());
+        assert_eq!(values, vec![800_005, 800_000, 800_130]);
+    }
+
+    #[test]
+    fn test_frame_of_reference_blocks() {
+        let codec = FrameOfReference::new(Codec::CompactSize);
+        let values: Vec<u64> = (0..1000).map(|i| 800_000 + i % 200).collect();
+        let bytes = encode_blocks(&codec, &values, 128).unwrap();
+        assert!(bytes.len() < values.len() + 8 * 10);
+        assert_eq!(decode_blocks(&codec, &bytes).unwrap(), values);
+    }
+
+    #[test]
+    fn test_frame_of_reference_errors() {
+        let codec = FrameOfReference::default();
+        let mut values = Vec::new();
+        assert_eq!(
+            codec.decode_block(&[0x00, 0x02, 0x01], &mut values),
+            Err(VarIntError::Truncated)
+        );
+        let overflow = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0x01, 0x01,
+        ];
+        assert_eq!(
+            codec.decode_block(&overflow, &mut values),
+            Err(VarIntError::Overflow)
+        );
+        #[cfg(feature = "quic")]
+        assert_eq!(
+            encode_blocks(&FrameOfReference::new(Codec::Quic), &[0, u64::MAX], 8),
+            Err(VarIntError::Overflow)
+        );
+    }
+}
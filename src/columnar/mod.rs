@@ -0,0 +1,59 @@
+//! Block codecs for integer columns.
+//!
+//! Values are cut into blocks of a fixed number of values, and each block is encoded on its own
+//! with a small header, so that a block can be decoded without looking at the others.
+
+mod bit_packing;
+mod delta_of_delta;
+mod dictionary;
+mod file;
+mod frame_of_reference;
+mod rle_hybrid;
+mod run_length;
+mod simple8b;
+
+pub use bit_packing::BitPacking;
+pub use delta_of_delta::{DeltaOfDelta, Residuals};
+pub use dictionary::Dictionary;
+pub use file::{ColumnCodec, ColumnReader, ColumnWriter, COLUMN_MAGIC};
+pub use frame_of_reference::FrameOfReference;
+pub use rle_hybrid::RleHybrid;
+pub use run_length::RunLength;
+pub use simple8b::{Simple8b, Simple8bIter};
+
+use crate::VarIntError;
+
+/// A codec transforming a block of values into bytes.
+pub trait BlockCodec {
+    /// Appends the encoding of `values` to `out`.
+    ///
+    /// Fails with the error of the varint codec of the block when it can't represent a value of the block, in
+    /// which case `out` may hold part of it.
+    fn encode_block(&self, values: &[u64], out: &mut Vec<u8>) -> Result<(), VarIntError>;
+
+    /// Decodes the block at the start of `bytes`, appending its values to `out` and returning the number of bytes read.
+    fn decode_block(&self, bytes: &[u8], out: &mut Vec<u64>) -> Result<usize, VarIntError>;
+}
+
+/// Encodes `values` as a sequence of blocks of at most `block_size` values.
+pub fn encode_blocks<C: BlockCodec>(
+    codec: &C,
+    values: &[u64],
+    block_size: usize,
+) -> Result<Vec<u8>, VarIntError> {
+    let mut out = Vec::new();
+    for block in values.chunks(block_size.max(1)) {
+        codec.encode_block(block, &mut out)?;
+    }
+    Ok(out)
+}
+
+/// Decodes a sequence of blocks produced by [`encode_blocks`].
+pub fn decode_blocks<C: BlockCodec>(codec: &C, bytes: &[u8]) -> Result<Vec<u64>, VarIntError> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        pos += codec.decode_block(&bytes[pos..], &mut out)?;
+    }
+    Ok(out)
+}
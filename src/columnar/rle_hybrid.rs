@@ -0,0 +1,175 @@
+use super::BitPacking;
+use crate::{Leb128, VarIntError};
+
+/// Runs shorter than this are bit-packed rather than run-length encoded.
+const MIN_RLE_RUN: usize = 8;
+
+/// Parquet RLE/bit-packed hybrid encoding
+///
+/// Used by Parquet for repetition and definition levels and for dictionary indices. Values are
+/// stored as a sequence of runs, each starting with a ULEB128 header whose low bit tells the kind:
+/// - `count << 1`: a value repeated `count` times, stored on `bit_width.div_ceil(8)` little-endian bytes,
+/// - `groups << 1 | 1`: `groups * 8` values bit-packed on `bit_width` bits, least significant bits first.
+///
+/// The number of values is not stored, Parquet gets it from the page header. When the data is
+/// prefixed by its 4 bytes length (levels in data pages v1), that prefix is left to the caller.
+///
+/// https://parquet.apache.org/docs/file-format/data-pages/encodings/#run-length-encoding--bit-packing-hybrid-rle--3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RleHybrid {
+    pub bit_width: u8,
+}
+
+impl RleHybrid {
+    pub fn new(bit_width: u8) -> Self {
+        RleHybrid { bit_width }
+    }
+
+    /// Appends the runs encoding `values` to `out`. Bits of the values above `bit_width` are dropped.
+    ///
+    /// A run of at least 8 equal values is run-length encoded when it starts right after another run,
+    /// anything else is bit-packed, the last group being padded with zeros.
+    pub fn encode(&self, values: &[u64], out: &mut Vec<u8>) {
+        let width = self.bit_width.min(64);
+        let value_len = width.div_ceil(8) as usize;
+        let mut pos = 0;
+        while pos < values.len() {
+            let run = run_len(&values[pos..]);
+            if run >= MIN_RLE_RUN {
+                Leb128::encode_append((run as u64) << 1, out);
+                out.extend_from_slice(&(values[pos] & mask(width)).to_le_bytes()[..value_len]);
+                pos += run;
+                continue;
+            }
+            // Bit-pack groups of 8 values until a long enough run starts a group.
+            let start = pos;
+            pos += MIN_RLE_RUN;
+            while pos < values.len() && run_len(&values[pos..]) < MIN_RLE_RUN {
+                pos += MIN_RLE_RUN;
+            }
+            let groups = (pos - start) / MIN_RLE_RUN;
+            let mut packed = values[start..pos.min(values.len())].to_vec();
+            packed.resize(pos - start, 0);
+            pos = pos.min(values.len());
+            Leb128::encode_append((groups as u64) << 1 | 1, out);
+            BitPacking::pack(&packed, width, out);
+        }
+    }
+
+    /// Decodes `count` values, appending them to `out` and returning the number of bytes read.
+    ///
+    /// Padding values of the last bit-packed run are read but not returned.
+    pub fn decode(
+        &self,
+        bytes: &[u8],
+        count: usize,
+        out: &mut Vec<u64>,
+    ) -> Result<usize, VarIntError> {
+        let width = self.bit_width;
+        if width > 64 {
+            return Err(VarIntError::Malformed);
+        }
+        let mut pos = 0;
+        let mut remaining = count;
+        while remaining > 0 {
+            let (header, len) = Leb128::decode(&bytes[pos..])?;
+            pos += len;
+            let run = usize::try_from(header >> 1).map_err(|_| VarIntError::TooLarge)?;
+            if header & 1 == 1 {
+                let values = run.checked_mul(MIN_RLE_RUN).ok_or(VarIntError::TooLarge)?;
+                let len = run
+                    .checked_mul(width as usize)
+                    .ok_or(VarIntError::TooLarge)?;
+                if len > bytes.len() - pos {
+                    return Err(VarIntError::Truncated);
+                }
+                let taken = values.min(remaining);
+                BitPacking::unpack(&bytes[pos..], width, taken, out)?;
+                pos += len;
+                remaining -= taken;
+            } else {
+                let value_len = width.div_ceil(8) as usize;
+                let value_bytes = bytes
+                    .get(pos..pos + value_len)
+                    .ok_or(VarIntError::Truncated)?;
+                let mut buf = [0u8; 8];
+                buf[..value_len].copy_from_slice(value_bytes);
+                let value = u64::from_le_bytes(buf);
+                if value & !mask(width) != 0 {
+                    return Err(VarIntError::Malformed);
+                }
+                pos += value_len;
+                let taken = run.min(remaining);
+                out.extend(std::iter::repeat_n(value, taken));
+                remaining -= taken;
+            }
+        }
+        Ok(pos)
+    }
+}
+
+/// Number of values equal to the first one at the start of `values`.
+fn run_len(values: &[u64]) -> usize {
+    values.iter().take_while(|v| **v == values[0]).count()
+}
+
+fn mask(width: u8) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rle_hybrid_spec_examples() {
+        // Bit-packed example of the Parquet documentation: 0 to 7 on 3 bits.
+        let mut out = Vec::new();
+        RleHybrid::new(3).encode(&[0, 1, 2, 3, 4, 5, 6, 7], &mut out);
+        assert_eq!(out, vec![0x03, 0b10001000, 0b11000110, 0b11111010]);
+
+        let mut out = Vec::new();
+        RleHybrid::new(1).encode(&[1; 100], &mut out);
+        assert_eq!(out, vec![0xc8, 0x01, 0x01]);
+        let mut values = Vec::new();
+        assert_eq!(RleHybrid::new(1).decode(&out, 100, &mut values), Ok(3));
+        assert_eq!(values, vec![1; 100]);
+    }
+
+    #[test]
+    fn test_rle_hybrid_round_trip() {
+        let mut values: Vec<u64> = (0..45).map(|i| i % 5).collect();
+        values.extend([3; 20]);
+        values.extend((0..11).map(|i| i * 91 % 1000));
+        let codec = RleHybrid::new(10);
+        let mut out = Vec::new();
+        codec.encode(&values, &mut out);
+        let mut decoded = Vec::new();
+        assert_eq!(
+            codec.decode(&out, values.len(), &mut decoded),
+            Ok(out.len())
+        );
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn test_rle_hybrid_errors() {
+        let mut values = Vec::new();
+        assert_eq!(
+            RleHybrid::new(3).decode(&[0x03, 0x88], 8, &mut values),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(
+            RleHybrid::new(2).decode(&[0x10, 0x07], 8, &mut values),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            RleHybrid::new(2).decode(&[], 1, &mut values),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use super::BlockCodec;
+use crate::{Codec, VarIntError};
+
+/// Run-length block codec falling back to literal varints
+///
+/// Each block stores its value count in a header, followed by groups each starting with a varint whose low
+/// bit tells the kind:
+/// - `run << 1`, then a value repeated `run` times,
+/// - `len << 1 | 1`, then `len` values as they are.
+///
+/// Runs of at least `min_run` equal values are run-length encoded and anything in between is grouped into
+/// literals, so long stretches of zeros take a few bytes while varied values cost a header per stretch.
+/// Headers and values use the same varint [`Codec`]: encoding fails with its error on a value or header it
+/// can't represent, [`VarIntError::Overflow`] for values above 62 bits with [`Codec::Quic`].
+///
+/// A few bytes can announce a block of billions of values: callers decoding untrusted blocks check the
+/// count of the header, the first varint, before decoding them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunLength {
+    codec: Codec,
+    min_run: usize,
+}
+
+impl RunLength {
+    /// A codec run-length encoding runs of at least `min_run` values, and at least 2.
+    pub fn new(codec: Codec, min_run: usize) -> Self {
+        RunLength {
+            codec,
+            min_run: min_run.max(2),
+        }
+    }
+}
+
+impl Default for RunLength {
+    /// LEB128, run-length encoding runs of 3 values or more.
+    ///
+    /// Shorter runs take the same space as literals at best, and split the literal group around them.
+    fn default() -> Self {
+        RunLength::new(Codec::Leb128, 3)
+    }
+}
+
+impl BlockCodec for RunLength {
+    fn encode_block(&self, values: &[u64], out: &mut Vec<u8>) -> Result<(), VarIntError> {
+        let mut write = |value| self.codec.encode_append(value, out);
+        write(values.len() as u64)?;
+        let mut pos = 0;
+        while pos < values.len() {
+            let run = run_len(&values[pos..]);
+            if run >= self.min_run {
+                write((run as u64) << 1)?;
+                write(values[pos])?;
+                pos += run;
+                continue;
+            }
+            let start = pos;
+            pos += run;
+            while pos < values.len() {
+                let run = run_len(&values[pos..]);
+                if run >= self.min_run {
+                    break;
+                }
+                pos += run;
+            }
+            write(((pos - start) as u64) << 1 | 1)?;
+            for value in &values[start..pos] {
+                write(*value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_block(&self, bytes: &[u8], out: &mut Vec<u64>) -> Result<usize, VarIntError> {
+        let (count, mut pos) = self.codec.decode(bytes)?;
+        let mut remaining = count;
+        while remaining > 0 {
+            let (header, len) = self.codec.decode(&bytes[pos..])?;
+            pos += len;
+            let group = header >> 1;
+            if group == 0 || group > remaining {
+                return Err(VarIntError::Malformed);
+            }
+            if header & 1 == 0 {
+                let (value, len) = self.codec.decode(&bytes[pos..])?;
+                pos += len;
+                let run = usize::try_from(group).map_err(|_| VarIntError::TooLarge)?;
+                out.extend(std::iter::repeat_n(value, run));
+            } else {
+                // Every literal takes at least one byte.
+                if group > (bytes.len() - pos) as u64 {
+                    return Err(VarIntError::Truncated);
+                }
+                out.reserve(group as usize);
+                for _ in 0..group {
+                    let (value, len) = self.codec.decode(&bytes[pos..])?;
+                    pos += len;
+                    out.push(value);
+                }
+            }
+            remaining -= group;
+        }
+        Ok(pos)
+    }
+}
+
+/// Number of values equal to the first one at the start of `values`.
+fn run_len(values: &[u64]) -> usize {
+    values.iter().take_while(|v| **v == values[0]).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::columnar::{decode_blocks, encode_blocks};
+
+    #[test]
+    fn test_run_length_block() {
+        let codec = RunLength::default();
+        let mut values = vec![0; 1000];
+        values.extend_from_slice(&[5, 7, 7, 300]);
+        let mut out = Vec::new();
+        codec.encode_block(&values, &mut out).unwrap();
+        // 1004 values, a run of 1000 zeros, then 4 literals.
+        assert_eq!(
+            out,
+            vec![0xec, 0x07, 0xd0, 0x0f, 0x00, 0x09, 0x05, 0x07, 0x07, 0xac, 0x02]
+        );
+        let mut decoded = Vec::new();
+        assert_eq!(codec.decode_block(&out, &mut decoded), Ok(out.len()));
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn test_run_length_blocks() {
+        let values: Vec<u64> = (0..5000u64)
+            .map(|i| if i % 700 < 600 { 0 } else { i * 31 })
+            .collect();
+        for codec in [RunLength::default(), RunLength::new(Codec::CompactSize, 8)] {
+            let bytes = encode_blocks(&codec, &values, 1024).unwrap();
+            assert!(bytes.len() < 1500 * 3, "{}", bytes.len());
+            assert_eq!(decode_blocks(&codec, &bytes).unwrap(), values);
+        }
+        let codec = RunLength::new(Codec::Leb128, 0);
+        let values = [1, 1, 2, 2, 2, u64::MAX];
+        let bytes = encode_blocks(&codec, &values, 4).unwrap();
+        assert_eq!(decode_blocks(&codec, &bytes).unwrap(), values);
+    }
+
+    #[test]
+    fn test_run_length_errors() {
+        let codec = RunLength::default();
+        let mut values = Vec::new();
+        assert_eq!(codec.decode_block(&[0x00], &mut values), Ok(1));
+        // Groups that are empty or longer than the block.
+        assert_eq!(
+            codec.decode_block(&[0x02, 0x00, 0x00], &mut values),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            codec.decode_block(&[0x02, 0x06, 0x00], &mut values),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            codec.decode_block(&[0x02, 0x05, 0x01], &mut values),
+            Err(VarIntError::Truncated)
+        );
+        #[cfg(feature = "quic")]
+        assert_eq!(
+            encode_blocks(&RunLength::new(Codec::Quic, 2), &[1, u64::MAX, u64::MAX], 8),
+            Err(VarIntError::Overflow)
+        );
+    }
+}
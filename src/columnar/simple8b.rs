@@ -0,0 +1,144 @@
+use crate::VarIntError;
+
+/// (values per word, bits per value) for each selector.
+const SELECTORS: [(usize, u32); 16] = [
+    (240, 0),
+    (120, 0),
+    (60, 1),
+    (30, 2),
+    (20, 3),
+    (15, 4),
+    (12, 5),
+    (10, 6),
+    (8, 7),
+    (7, 8),
+    (6, 10),
+    (5, 12),
+    (4, 15),
+    (3, 20),
+    (2, 30),
+    (1, 60),
+];
+
+/// Simple8b
+///
+/// Packs small integers into 64-bit words: the 4 high bits select how the 60 low bits are split
+/// (from 60 values of 1 bit to a single value of 60 bits). Selectors 0 and 1 hold runs of 240 and 120 ones
+/// without any payload, as in InfluxDB. Values must be below 2^60.
+///
+/// Anh, V. N., & Moffat, A. (2010). Index compression using 64-bit words.
+pub struct Simple8b;
+
+impl Simple8b {
+    /// Largest value that can be packed.
+    pub const MAX_VALUE: u64 = (1 << 60) - 1;
+
+    /// Packs `values` into words, choosing for each word the selector holding the most values.
+    pub fn encode(values: &[u64]) -> Result<Vec<u64>, VarIntError> {
+        let mut words = Vec::new();
+        let mut rest = values;
+        while !rest.is_empty() {
+            let (selector, count) = SELECTORS
+                .iter()
+                .enumerate()
+                .find_map(|(selector, (count, bits))| {
+                    let fits = match bits {
+                        0 => rest.len() >= *count && rest[..*count].iter().all(|v| *v == 1),
+                        bits => {
+                            rest.len() >= *count && rest[..*count].iter().all(|v| *v >> bits == 0)
+                        }
+                    };
+                    fits.then_some((selector, *count))
+                })
+                .ok_or(VarIntError::Overflow)?;
+            let bits = SELECTORS[selector].1;
+            let mut word = (selector as u64) << 60;
+            if bits > 0 {
+                for (i, value) in rest[..count].iter().enumerate() {
+                    word |= value << (i as u32 * bits);
+                }
+            }
+            words.push(word);
+            rest = &rest[count..];
+        }
+        Ok(words)
+    }
+
+    /// Unpacks every value of `words`.
+    pub fn decode(words: &[u64]) -> Vec<u64> {
+        Self::iter(words).collect()
+    }
+
+    /// Iterates over the packed values without unpacking them all.
+    pub fn iter(words: &[u64]) -> Simple8bIter<'_> {
+        Simple8bIter { words, index: 0 }
+    }
+
+    /// Number of values held by a word.
+    pub fn count(word: u64) -> usize {
+        SELECTORS[(word >> 60) as usize].0
+    }
+}
+
+/// Iterator over values packed with [`Simple8b`].
+pub struct Simple8bIter<'a> {
+    words: &'a [u64],
+    /// Index of the next value in the current word.
+    index: usize,
+}
+
+impl Iterator for Simple8bIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let word = *self.words.first()?;
+        let (count, bits) = SELECTORS[(word >> 60) as usize];
+        let value = match bits {
+            0 => 1,
+            bits => (word >> (self.index as u32 * bits)) & ((1 << bits) - 1),
+        };
+        self.index += 1;
+        if self.index == count {
+            self.words = &self.words[1..];
+            self.index = 0;
+        }
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self
+            .words
+            .iter()
+            .map(|w| Simple8b::count(*w))
+            .sum::<usize>()
+            - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Simple8bIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple8b_selectors() {
+        assert_eq!(Simple8b::encode(&[1; 240]).unwrap(), vec![0]);
+        assert_eq!(Simple8b::encode(&[1; 120]).unwrap(), vec![1 << 60]);
+        let words = Simple8b::encode(&[3, 1, 2, 0]).unwrap();
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0] >> 60, 12);
+        assert_eq!(Simple8b::encode(&[Simple8b::MAX_VALUE]).unwrap().len(), 1);
+        assert_eq!(Simple8b::encode(&[1 << 60]), Err(VarIntError::Overflow));
+    }
+
+    #[test]
+    fn test_simple8b_round_trip() {
+        let values: Vec<u64> = (0..1000u64).map(|i| (i * 37) % (1 << (i % 40))).collect();
+        let words = Simple8b::encode(&values).unwrap();
+        assert!(words.len() < values.len());
+        assert_eq!(Simple8b::decode(&words), values);
+        assert_eq!(Simple8b::iter(&words).len(), values.len());
+    }
+}
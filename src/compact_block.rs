@@ -0,0 +1,93 @@
+//! BIP152 differentially encoded transaction indexes, as sent in `getblocktxn` messages.
+//!
+//! The list is its length as a compactSize, then each index as a compactSize holding its distance
+//! from the previous index minus one, the first one being absolute. Like Bitcoin Core, decoding
+//! rejects indexes that don't fit in 16 bits, which no block can reach.
+//!
+//! https://github.com/bitcoin/bips/blob/master/bip-0152.mediawiki
+
+use crate::{VarInt, VarIntError};
+
+/// Converts strictly increasing absolute indexes to their differential form.
+///
+/// Fails with [`VarIntError::OutOfOrder`] when an index is not greater than the previous one.
+pub fn to_differential(indexes: &[u64]) -> Result<Vec<u64>, VarIntError> {
+    let mut result = Vec::with_capacity(indexes.len());
+    let mut next = 0u64;
+    for index in indexes {
+        if *index < next {
+            return Err(VarIntError::OutOfOrder);
+        }
+        result.push(index - next);
+        next = index.checked_add(1).ok_or(VarIntError::Overflow)?;
+    }
+    Ok(result)
+}
+
+/// Converts differential indexes back to absolute ones, failing with [`VarIntError::Overflow`]
+/// when an index goes past `u16::MAX`.
+pub fn to_absolute(differential: &[u64]) -> Result<Vec<u64>, VarIntError> {
+    let mut result = Vec::with_capacity(differential.len());
+    let mut next = 0u64;
+    for diff in differential {
+        let index = next.checked_add(*diff).ok_or(VarIntError::Overflow)?;
+        if index > u16::MAX as u64 {
+            return Err(VarIntError::Overflow);
+        }
+        result.push(index);
+        next = index + 1;
+    }
+    Ok(result)
+}
+
+/// Encodes a list of absolute indexes, see [`to_differential`] for the errors.
+pub fn encode_indexes(indexes: &[u64]) -> Result<Vec<u8>, VarIntError> {
+    let differential = to_differential(indexes)?;
+    let mut result = Vec::with_capacity(1 + indexes.len());
+    VarInt::encode_append(indexes.len() as u64, &mut result);
+    for diff in differential {
+        VarInt::encode_append(diff, &mut result);
+    }
+    Ok(result)
+}
+
+/// Decodes a list of indexes into their absolute values, returning them with the number of bytes read.
+pub fn decode_indexes(bytes: &[u8]) -> Result<(Vec<u64>, usize), VarIntError> {
+    let (count, mut pos) = VarInt::decode_len(bytes)?;
+    // Every index takes at least one byte.
+    if count > bytes.len() - pos {
+        return Err(VarIntError::Truncated);
+    }
+    let mut differential = Vec::with_capacity(count);
+    for _ in 0..count {
+        let decoded = VarInt::decode_full(&bytes[pos..])?;
+        differential.push(decoded.value);
+        pos += decoded.len;
+    }
+    Ok((to_absolute(&differential)?, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_differential_indexes() {
+        let indexes = [0, 1, 5, 6, 300];
+        assert_eq!(to_differential(&indexes), Ok(vec![0, 0, 3, 0, 293]));
+        let bytes = encode_indexes(&indexes).unwrap();
+        assert_eq!(bytes, vec![0x05, 0x00, 0x00, 0x03, 0x00, 0xfd, 0x25, 0x01]);
+        assert_eq!(decode_indexes(&bytes), Ok((indexes.to_vec(), bytes.len())));
+        assert_eq!(encode_indexes(&[]), Ok(vec![0x00]));
+    }
+
+    #[test]
+    fn test_differential_errors() {
+        assert_eq!(to_differential(&[3, 3]), Err(VarIntError::OutOfOrder));
+        assert_eq!(to_differential(&[u64::MAX]), Err(VarIntError::Overflow));
+        assert_eq!(to_absolute(&[65535]), Ok(vec![65535]));
+        assert_eq!(to_absolute(&[65535, 0]), Err(VarIntError::Overflow));
+        assert_eq!(to_absolute(&[1, u64::MAX]), Err(VarIntError::Overflow));
+        assert_eq!(decode_indexes(&[0x03, 0x00]), Err(VarIntError::Truncated));
+    }
+}
@@ -0,0 +1,616 @@
+//! Consensus-style serialization: types write themselves to a [`Write`] and read themselves from a [`Read`],
+//! integers in little-endian and sequences prefixed by their compactSize length,
+//! so that whole messages can be composed from their fields.
+//!
+//! ```
+//! use bitcoin_varint::consensus::{deserialize, serialize};
+//!
+//! let outputs: Vec<(u64, Vec<u8>)> = vec![(50, vec![0x51]), (1000, vec![])];
+//! let bytes = serialize(&outputs);
+//! assert_eq!(bytes[..9], [0x02, 0x32, 0, 0, 0, 0, 0, 0, 0]);
+//! assert_eq!(deserialize::<Vec<(u64, Vec<u8>)>>(&bytes).unwrap(), outputs);
+//! ```
+
+use crate::decoder::Decoder;
+use crate::delimited::read_prefix;
+use crate::{VarCodec, VarInt, VarIntError};
+use std::io::{self, Read, Write};
+use std::num::NonZeroU64;
+
+/// Number of elements allocated up front when decoding a sequence, whatever its announced length.
+const MAX_PREALLOC: usize = 4096;
+
+pub trait Encodable {
+    /// Writes the value to `writer`, returning the number of bytes written.
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize>;
+}
+
+pub trait Decodable: Sized {
+    fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+/// Encodes a value into a new buffer.
+pub fn serialize<T: Encodable + ?Sized>(value: &T) -> Vec<u8> {
+    let mut result = Vec::new();
+    value.consensus_encode(&mut result).unwrap();
+    result
+}
+
+/// Decodes a value taking all of `bytes`, trailing bytes being an [`io::ErrorKind::InvalidData`] error.
+pub fn deserialize<T: Decodable>(mut bytes: &[u8]) -> io::Result<T> {
+    let value = T::consensus_decode(&mut bytes)?;
+    if !bytes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "trailing bytes after the value",
+        ));
+    }
+    Ok(value)
+}
+
+/// Writes a compactSize.
+pub fn write_compact_size<W: Write>(value: u64, writer: &mut W) -> io::Result<usize> {
+    let mut bytes = Vec::with_capacity(9);
+    let len = VarInt::encode_append(value, &mut bytes);
+    writer.write_all(&bytes)?;
+    Ok(len)
+}
+
+/// Reads a compactSize the way Bitcoin Core does when deserializing, see [`Decoder::bitcoin_core`].
+pub fn read_compact_size<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut window = [0u8; 9];
+    reader.read_exact(&mut window[..1])?;
+    let len = match window[0] {
+        0xfd => 3,
+        0xfe => 5,
+        0xff => 9,
+        _ => 1,
+    };
+    reader.read_exact(&mut window[1..len])?;
+    Decoder::bitcoin_core()
+        .decode(&window[..len])
+        .map(|(value, _)| value)
+        .map_err(io::Error::from)
+}
+
+/// Reads a compactSize count then that many elements with `read`, the pattern of most lists in P2P messages.
+///
+/// Counts above `max_count` fail with an [`io::ErrorKind::InvalidData`] error wrapping
+/// [`VarIntError::TooLarge`] before any element is read. Whatever `max_count`, the count is at most
+/// Bitcoin Core's limit and the allocation only grows as elements are actually read.
+///
+/// ```
+/// use bitcoin_varint::consensus::{read_vec, Decodable};
+///
+/// // An `inv` message: a count, then (type, hash) entries.
+/// let mut bytes = vec![0x01, 0x02, 0x00, 0x00, 0x00];
+/// bytes.extend_from_slice(&[0xaa; 32]);
+/// let inventory = read_vec(&mut bytes.as_slice(), 50_000, |r| {
+///     Ok((u32::consensus_decode(r)?, <[u8; 32]>::consensus_decode(r)?))
+/// })
+/// .unwrap();
+/// assert_eq!(inventory, vec![(2, [0xaa; 32])]);
+/// ```
+pub fn read_vec<R: Read, T>(
+    reader: &mut R,
+    max_count: usize,
+    read: impl FnMut(&mut R) -> io::Result<T>,
+) -> io::Result<Vec<T>> {
+    let count = read_compact_size(reader)?;
+    read_elements(count, max_count, reader, read)
+}
+
+/// Writes `value` with `codec` rather than as a fixed size integer, for fields in another format.
+pub fn write_with<C: VarCodec, W: Write>(
+    codec: &C,
+    value: impl Into<u64>,
+    writer: &mut W,
+) -> io::Result<usize> {
+    let bytes = codec.encode(value.into())?;
+    writer.write_all(&bytes)?;
+    Ok(bytes.len())
+}
+
+/// Reads a value written by [`write_with`], values that don't fit in `T` failing with
+/// [`VarIntError::Overflow`].
+pub fn read_with<C: VarCodec, T: TryFrom<u64>, R: Read>(
+    codec: &C,
+    reader: &mut R,
+) -> io::Result<T> {
+    let (value, _) = read_prefix(codec, reader)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+    T::try_from(value).map_err(|_| VarIntError::Overflow.into())
+}
+
+/// Writes an optional value with `codec`, see [`VarCodec::encode_option`].
+pub fn write_option_with<C: VarCodec, W: Write>(
+    codec: &C,
+    value: Option<u64>,
+    writer: &mut W,
+) -> io::Result<usize> {
+    let bytes = codec.encode_option(value)?;
+    writer.write_all(&bytes)?;
+    Ok(bytes.len())
+}
+
+/// Reads a value written by [`write_option_with`].
+pub fn read_option_with<C: VarCodec, R: Read>(
+    codec: &C,
+    reader: &mut R,
+) -> io::Result<Option<u64>> {
+    Ok(read_with::<C, u64, R>(codec, reader)?.checked_sub(1))
+}
+
+/// Writes a non-zero value with `codec`, see [`VarCodec::encode_nonzero`].
+pub fn write_nonzero_with<C: VarCodec, W: Write>(
+    codec: &C,
+    value: NonZeroU64,
+    writer: &mut W,
+) -> io::Result<usize> {
+    let bytes = codec.encode_nonzero(value)?;
+    writer.write_all(&bytes)?;
+    Ok(bytes.len())
+}
+
+/// Reads a value written by [`write_nonzero_with`].
+pub fn read_nonzero_with<C: VarCodec, R: Read>(
+    codec: &C,
+    reader: &mut R,
+) -> io::Result<NonZeroU64> {
+    read_with::<C, u64, R>(codec, reader)?
+        .checked_add(1)
+        .and_then(NonZeroU64::new)
+        .ok_or_else(|| VarIntError::Overflow.into())
+}
+
+/// Writes `items` prefixed by their count encoded with `codec`.
+pub fn write_vec_with<C: VarCodec, T: Encodable, W: Write>(
+    codec: &C,
+    items: &[T],
+    writer: &mut W,
+) -> io::Result<usize> {
+    let mut len = write_with(codec, items.len() as u64, writer)?;
+    for item in items {
+        len += item.consensus_encode(writer)?;
+    }
+    Ok(len)
+}
+
+/// As [`read_vec`] with the count encoded with `codec`, such as the BigSize counts of Lightning messages.
+pub fn read_vec_with<C: VarCodec, R: Read, T>(
+    codec: &C,
+    reader: &mut R,
+    max_count: usize,
+    read: impl FnMut(&mut R) -> io::Result<T>,
+) -> io::Result<Vec<T>> {
+    let count = read_with(codec, reader)?;
+    read_elements(count, max_count, reader, read)
+}
+
+fn read_elements<R: Read, T>(
+    count: u64,
+    max_count: usize,
+    reader: &mut R,
+    mut read: impl FnMut(&mut R) -> io::Result<T>,
+) -> io::Result<Vec<T>> {
+    let count = usize::try_from(count)
+        .ok()
+        .filter(|count| *count <= max_count)
+        .ok_or_else(|| io::Error::from(VarIntError::TooLarge))?;
+    let mut result = Vec::with_capacity(count.min(MAX_PREALLOC));
+    for _ in 0..count {
+        result.push(read(reader)?);
+    }
+    Ok(result)
+}
+
+macro_rules! impl_int {
+    ($($ty:ty),*) => {
+        $(
+            impl Encodable for $ty {
+                fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+                    writer.write_all(&self.to_le_bytes())?;
+                    Ok(std::mem::size_of::<$ty>())
+                }
+            }
+
+            impl Decodable for $ty {
+                fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0u8; std::mem::size_of::<$ty>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$ty>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Encodable for bool {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        (*self as u8).consensus_encode(writer)
+    }
+}
+
+impl Decodable for bool {
+    fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(u8::consensus_decode(reader)? != 0)
+    }
+}
+
+/// Optional values, as a presence byte 0x00 or 0x01 followed by the value if present.
+///
+/// Other presence bytes fail with an [`io::ErrorKind::InvalidData`] error wrapping [`VarIntError::Malformed`].
+/// For optional integers written with a varint codec, the sentinel encoding of
+/// [`VarCodec::encode_option`] saves the presence byte.
+impl<T: Encodable> Encodable for Option<T> {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        match self {
+            Some(value) => Ok(1u8.consensus_encode(writer)? + value.consensus_encode(writer)?),
+            None => 0u8.consensus_encode(writer),
+        }
+    }
+}
+
+impl<T: Decodable> Decodable for Option<T> {
+    fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        match u8::consensus_decode(reader)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::consensus_decode(reader)?)),
+            _ => Err(VarIntError::Malformed.into()),
+        }
+    }
+}
+
+/// Non-zero integers as their little-endian u64, zero failing with [`VarIntError::Malformed`].
+impl Encodable for NonZeroU64 {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        self.get().consensus_encode(writer)
+    }
+}
+
+impl Decodable for NonZeroU64 {
+    fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        NonZeroU64::new(u64::consensus_decode(reader)?).ok_or_else(|| VarIntError::Malformed.into())
+    }
+}
+
+/// Fixed size byte arrays (hashes, magic), without length prefix.
+impl<const N: usize> Encodable for [u8; N] {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        writer.write_all(self)?;
+        Ok(N)
+    }
+}
+
+impl<const N: usize> Decodable for [u8; N] {
+    fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; N];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Sequences are prefixed by their number of elements, so byte strings end up as their length followed by the bytes.
+impl<T: Encodable> Encodable for [T] {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        let prefix = VarInt::encode_len(self.len())?;
+        writer.write_all(&prefix)?;
+        let mut len = prefix.len();
+        for item in self {
+            len += item.consensus_encode(writer)?;
+        }
+        Ok(len)
+    }
+}
+
+impl<T: Encodable> Encodable for Vec<T> {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        self.as_slice().consensus_encode(writer)
+    }
+}
+
+impl<T: Decodable> Decodable for Vec<T> {
+    fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        read_vec(reader, usize::MAX, T::consensus_decode)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Encodable),*> Encodable for ($($name,)*) {
+            fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+                let ($($name,)*) = self;
+                let mut len = 0;
+                $(len += $name.consensus_encode(writer)?;)*
+                Ok(len)
+            }
+        }
+
+        impl<$($name: Decodable),*> Decodable for ($($name,)*) {
+            fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+                Ok(($($name::consensus_decode(reader)?,)*))
+            }
+        }
+    };
+}
+
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+
+/// Implements [`Encodable`] and [`Decodable`] for a struct, its fields being encoded in the listed order.
+///
+/// Fields use their own encoding unless preceded in the list by a `#[varint(...)]` attribute:
+/// - `codec = "..."` writes an integer field with [`write_with`], one of `"compact_size"`, `"bigsize"`,
+///   `"leb128"`, `"quic"` or `"vlq"`;
+/// - `len_prefix = "..."` prefixes a `Vec` field with its count in one of these encodings;
+/// - `option_codec = "..."` and `nonzero_codec = "..."` write `Option<u64>` and `NonZeroU64` fields with
+///   [`write_option_with`] and [`write_nonzero_with`], where `Option` fields otherwise take a presence byte;
+/// - `max = n` rejects `Vec` fields of more than `n` elements when decoding, alone or after `len_prefix`.
+///
+/// ```
+/// use bitcoin_varint::consensus::{deserialize, serialize};
+/// use bitcoin_varint::impl_consensus_encoding;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct GetHeaders {
+///     version: u32,
+///     locator: Vec<[u8; 32]>,
+///     stop: [u8; 32],
+/// }
+/// impl_consensus_encoding!(GetHeaders, version, #[varint(max = 101)] locator, stop);
+///
+/// let message = GetHeaders { version: 70016, locator: vec![[1; 32]], stop: [0; 32] };
+/// let bytes = serialize(&message);
+/// assert_eq!(bytes.len(), 4 + 1 + 32 + 32);
+/// assert_eq!(deserialize::<GetHeaders>(&bytes).unwrap(), message);
+///
+/// #[derive(Debug, PartialEq)]
+/// struct FeeFilter {
+///     fee_rate: u32,
+/// }
+/// impl_consensus_encoding!(FeeFilter, #[varint(codec = "compact_size")] fee_rate);
+/// assert_eq!(serialize(&FeeFilter { fee_rate: 1000 }), [0xfd, 0xe8, 0x03]);
+/// ```
+#[macro_export]
+macro_rules! impl_consensus_encoding {
+    (@encode $value:expr, $writer:ident) => {
+        $crate::consensus::Encodable::consensus_encode(&$value, $writer)
+    };
+    (@encode $value:expr, $writer:ident, codec = $codec:tt) => {
+        $crate::consensus::write_with(&$crate::impl_consensus_encoding!(@codec $codec), $value, $writer)
+    };
+    (@encode $value:expr, $writer:ident, option_codec = $codec:tt) => {
+        $crate::consensus::write_option_with(&$crate::impl_consensus_encoding!(@codec $codec), $value, $writer)
+    };
+    (@encode $value:expr, $writer:ident, nonzero_codec = $codec:tt) => {
+        $crate::consensus::write_nonzero_with(&$crate::impl_consensus_encoding!(@codec $codec), $value, $writer)
+    };
+    (@encode $value:expr, $writer:ident, len_prefix = $codec:tt $(, max = $max:expr)?) => {
+        $crate::consensus::write_vec_with(&$crate::impl_consensus_encoding!(@codec $codec), &$value, $writer)
+    };
+    (@encode $value:expr, $writer:ident, max = $max:expr) => {
+        $crate::consensus::Encodable::consensus_encode(&$value, $writer)
+    };
+    (@decode $reader:ident) => {
+        $crate::consensus::Decodable::consensus_decode($reader)
+    };
+    (@decode $reader:ident, codec = $codec:tt) => {
+        $crate::consensus::read_with(&$crate::impl_consensus_encoding!(@codec $codec), $reader)
+    };
+    (@decode $reader:ident, option_codec = $codec:tt) => {
+        $crate::consensus::read_option_with(&$crate::impl_consensus_encoding!(@codec $codec), $reader)
+    };
+    (@decode $reader:ident, nonzero_codec = $codec:tt) => {
+        $crate::consensus::read_nonzero_with(&$crate::impl_consensus_encoding!(@codec $codec), $reader)
+    };
+    (@decode $reader:ident, len_prefix = $codec:tt) => {
+        $crate::impl_consensus_encoding!(@decode $reader, len_prefix = $codec, max = usize::MAX)
+    };
+    (@decode $reader:ident, len_prefix = $codec:tt, max = $max:expr) => {
+        $crate::consensus::read_vec_with(
+            &$crate::impl_consensus_encoding!(@codec $codec),
+            $reader,
+            $max,
+            $crate::consensus::Decodable::consensus_decode,
+        )
+    };
+    (@decode $reader:ident, max = $max:expr) => {
+        $crate::consensus::read_vec($reader, $max, $crate::consensus::Decodable::consensus_decode)
+    };
+    (@codec "compact_size") => { $crate::VarInt };
+    (@codec "bigsize") => { $crate::BigSize };
+    (@codec "leb128") => { $crate::Leb128 };
+    (@codec "quic") => { $crate::quic::Quic };
+    (@codec "vlq") => { $crate::vlq::Vlq };
+    ($thing:ident, $($(#[varint($($attr:tt)*)])? $field:ident),+ $(,)?) => {
+        impl $crate::consensus::Encodable for $thing {
+            fn consensus_encode<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+                let mut len = 0;
+                $(len += $crate::impl_consensus_encoding!(@encode self.$field, writer $(, $($attr)*)?)?;)+
+                Ok(len)
+            }
+        }
+
+        impl $crate::consensus::Decodable for $thing {
+            fn consensus_decode<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+                Ok($thing {
+                    $($field: $crate::impl_consensus_encoding!(@decode reader $(, $($attr)*)?)?),+
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Inv {
+        inventory: Vec<(u32, [u8; 32])>,
+    }
+
+    impl Encodable for Inv {
+        fn consensus_encode<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+            self.inventory.consensus_encode(writer)
+        }
+    }
+
+    impl Decodable for Inv {
+        fn consensus_decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+            Ok(Inv {
+                inventory: Vec::consensus_decode(reader)?,
+            })
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Ping {
+        nonce: u64,
+        relay: bool,
+        inv: Inv,
+    }
+
+    impl_consensus_encoding!(Ping, nonce, relay, inv);
+
+    #[test]
+    fn test_consensus_struct() {
+        let inv = Inv {
+            inventory: vec![(1, [0xaa; 32]), (2, [0xbb; 32])],
+        };
+        let bytes = serialize(&inv);
+        assert_eq!(bytes.len(), 1 + 2 * 36);
+        assert_eq!(bytes[..5], [0x02, 0x01, 0x00, 0x00, 0x00]);
+        assert_eq!(deserialize::<Inv>(&bytes).unwrap(), inv);
+        assert!(deserialize::<Inv>(&bytes[..40]).is_err());
+    }
+
+    #[test]
+    fn test_consensus_encoding_macro() {
+        let ping = Ping {
+            nonce: 0x0102030405060708,
+            relay: true,
+            inv: Inv { inventory: vec![] },
+        };
+        let bytes = serialize(&ping);
+        assert_eq!(bytes, vec![8, 7, 6, 5, 4, 3, 2, 1, 1, 0]);
+        assert_eq!(deserialize::<Ping>(&bytes).unwrap(), ping);
+    }
+
+    #[test]
+    fn test_consensus_compact_size_checks() {
+        let bytes = serialize(&vec![7u8; 300]);
+        assert_eq!(bytes[..3], [0xfd, 0x2c, 0x01]);
+        assert_eq!(deserialize::<Vec<u8>>(&bytes).unwrap(), vec![7u8; 300]);
+
+        // Non-canonical, too large, and trailing data.
+        for bytes in [
+            &[0xfd, 0x01, 0x00, 0x07][..],
+            &[0xfe, 0, 0, 0, 0x10],
+            &[0x00, 0x00],
+        ] {
+            let err = deserialize::<Vec<u8>>(bytes).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[cfg(all(feature = "bigsize", feature = "leb128"))]
+    #[derive(Debug, PartialEq)]
+    struct Mixed {
+        version: u32,
+        amount: u64,
+        hashes: Vec<[u8; 32]>,
+        flags: Vec<u8>,
+    }
+
+    #[cfg(all(feature = "bigsize", feature = "leb128"))]
+    impl_consensus_encoding!(
+        Mixed,
+        version,
+        #[varint(codec = "leb128")]
+        amount,
+        #[varint(len_prefix = "bigsize", max = 2)]
+        hashes,
+        #[varint(max = 3)]
+        flags,
+    );
+
+    #[test]
+    #[cfg(all(feature = "bigsize", feature = "leb128"))]
+    fn test_consensus_field_attributes() {
+        let mixed = Mixed {
+            version: 2,
+            amount: 300,
+            hashes: vec![[0xaa; 32]],
+            flags: vec![1, 2, 3],
+        };
+        let bytes = serialize(&mixed);
+        assert_eq!(bytes[4..7], [0xac, 0x02, 0x01]);
+        assert_eq!(bytes.len(), 4 + 2 + 1 + 32 + 4);
+        assert_eq!(deserialize::<Mixed>(&bytes).unwrap(), mixed);
+
+        let long = Mixed {
+            flags: vec![0; 4],
+            ..mixed
+        };
+        let err = deserialize::<Mixed>(&serialize(&long)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Optional {
+        height: Option<u32>,
+        fee: Option<u64>,
+        count: NonZeroU64,
+    }
+
+    impl_consensus_encoding!(
+        Optional,
+        height,
+        #[varint(option_codec = "compact_size")]
+        fee,
+        #[varint(nonzero_codec = "compact_size")]
+        count,
+    );
+
+    #[test]
+    fn test_consensus_optional_fields() {
+        let count = NonZeroU64::new(1).unwrap();
+        let absent = Optional {
+            height: None,
+            fee: None,
+            count,
+        };
+        assert_eq!(serialize(&absent), [0x00, 0x00, 0x00]);
+        let present = Optional {
+            height: Some(800_000),
+            fee: Some(0),
+            count,
+        };
+        let bytes = serialize(&present);
+        assert_eq!(bytes, [0x01, 0x00, 0x35, 0x0c, 0x00, 0x01, 0x00]);
+        assert_eq!(deserialize::<Optional>(&bytes).unwrap(), present);
+
+        let err = deserialize::<Option<u8>>(&[0x02, 0x00]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(deserialize::<NonZeroU64>(&[0; 8]).is_err());
+    }
+
+    #[test]
+    fn test_read_vec() {
+        let bytes = [0x02, 0x01, 0x00, 0x02, 0x00];
+        let pairs = read_vec(&mut &bytes[..], 2, |r| {
+            Ok((u8::consensus_decode(r)?, u8::consensus_decode(r)?))
+        })
+        .unwrap();
+        assert_eq!(pairs, vec![(1, 0), (2, 0)]);
+
+        let err = read_vec(&mut &bytes[..], 1, u8::consensus_decode).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_vec(&mut &bytes[..3], 2, u16::consensus_decode).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{VarInt, VarIntError};
+
+/// Largest size Bitcoin Core accepts when deserializing a compactSize with range checking (32 MiB).
+///
+/// https://github.com/bitcoin/bitcoin/blob/master/src/serialize.h
+pub const MAX_SIZE: u64 = 0x02000000;
+
+/// A compactSize decoder with configurable strictness.
+///
+/// ```
+/// use bitcoin_varint::decoder::Decoder;
+///
+/// let decoder = Decoder::builder().canonical(true).max_value(1000).build();
+/// assert_eq!(decoder.decode(&[0xfd, 0xe8, 0x03]).unwrap(), (1000, 3));
+/// assert!(decoder.decode(&[0xfd, 0xe9, 0x03]).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoder {
+    max_value: Option<u64>,
+    canonical: bool,
+}
+
+impl Default for Decoder {
+    /// A lenient decoder, accepting any value in any form.
+    fn default() -> Self {
+        Decoder {
+            max_value: None,
+            canonical: false,
+        }
+    }
+}
+
+impl Decoder {
+    pub fn builder() -> DecoderBuilder {
+        DecoderBuilder {
+            decoder: Decoder::default(),
+        }
+    }
+
+    /// Mirrors Bitcoin Core's `ReadCompactSize(is, true)`: non-canonical encodings
+    /// are rejected with [`VarIntError::NonCanonical`] and values above [`MAX_SIZE`] with [`VarIntError::TooLarge`].
+    pub fn bitcoin_core() -> Decoder {
+        Decoder {
+            max_value: Some(MAX_SIZE),
+            canonical: true,
+        }
+    }
+
+    /// Mirrors zcashd's `ReadCompactSize` and librustzcash's `CompactSize::read`: the same limits as
+    /// [`Decoder::bitcoin_core`], but Zcash applies them to every compactSize, where Bitcoin Core skips
+    /// the range check for some fields.
+    ///
+    /// https://github.com/zcash/librustzcash/blob/main/components/zcash_encoding/src/lib.rs
+    pub fn zcash() -> Decoder {
+        Decoder {
+            max_value: Some(MAX_SIZE),
+            canonical: true,
+        }
+    }
+
+    /// Decodes a compactSize, returning the value with the number of bytes read.
+    pub fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let decoded = VarInt::decode_full(bytes)?;
+        if self.canonical && !decoded.canonical {
+            #[cfg(feature = "metrics")]
+            crate::metrics::record(|r| r.non_canonical());
+            return Err(VarIntError::NonCanonical);
+        }
+        if self.max_value.is_some_and(|max| decoded.value > max) {
+            return Err(VarIntError::TooLarge);
+        }
+        Ok((decoded.value, decoded.len))
+    }
+}
+
+/// Builder for [`Decoder`].
+#[derive(Debug, Clone, Copy)]
+pub struct DecoderBuilder {
+    decoder: Decoder,
+}
+
+impl DecoderBuilder {
+    /// Rejects values above `max`.
+    pub fn max_value(mut self, max: u64) -> Self {
+        self.decoder.max_value = Some(max);
+        self
+    }
+
+    /// Rejects values not encoded in their shortest form.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.decoder.canonical = canonical;
+        self
+    }
+
+    pub fn build(self) -> Decoder {
+        self.decoder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_default_is_lenient() {
+        let decoder = Decoder::default();
+        assert_eq!(decoder.decode(&[0xfd, 0x01, 0x00]).unwrap(), (1, 3));
+        assert_eq!(
+            decoder.decode(&[0xfe, 0, 0, 0, 0x10]).unwrap(),
+            (0x10000000, 5)
+        );
+        assert_eq!(decoder.decode(&[0xfe, 0, 0]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_decoder_bitcoin_core() {
+        let decoder = Decoder::bitcoin_core();
+        assert_eq!(
+            decoder.decode(&[0xfe, 0, 0, 0, 0x02]).unwrap(),
+            (MAX_SIZE, 5)
+        );
+        assert_eq!(
+            decoder.decode(&[0xfe, 1, 0, 0, 0x02]),
+            Err(VarIntError::TooLarge)
+        );
+        assert_eq!(
+            decoder.decode(&[0xfd, 0xfc, 0x00]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(
+            decoder.decode(&[0xfe, 0xff, 0xff, 0, 0]),
+            Err(VarIntError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn test_decoder_zcash() {
+        let decoder = Decoder::zcash();
+        assert_eq!(decoder.decode(&[0xfd, 0xfd, 0x00]).unwrap(), (0xfd, 3));
+        assert_eq!(
+            decoder.decode(&[0xff, 0, 0, 0, 0, 1, 0, 0, 0]),
+            Err(VarIntError::TooLarge)
+        );
+        assert_eq!(
+            decoder.decode(&[0xff, 0, 0, 0, 0x02, 0, 0, 0, 0]),
+            Err(VarIntError::NonCanonical)
+        );
+    }
+}
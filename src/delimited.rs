@@ -0,0 +1,826 @@
+//! Length-delimited messages, as written by protobuf's `writeDelimitedTo` and read by `parseDelimitedFrom`:
+//! each payload is preceded by its length in LEB128.
+//! The `_with` variants frame payloads with the length in any other [`VarCodec`].
+//!
+//! https://protobuf.dev/programming-guides/techniques/#streaming
+
+use crate::codec::WINDOW_LEN;
+#[cfg(feature = "leb128")]
+use crate::Leb128;
+use crate::{VarCodec, VarIntError};
+use std::io::{self, IoSlice, Read, Write};
+use std::ops::Range;
+
+/// Writes `payload` prefixed by its length.
+#[cfg(feature = "leb128")]
+pub fn write_delimited<W: Write>(payload: &[u8], writer: &mut W) -> io::Result<()> {
+    write_delimited_with(&Leb128, payload, writer)
+}
+
+/// [`write_delimited`] with the length encoded by `codec`.
+pub fn write_delimited_with<C: VarCodec + ?Sized, W: Write>(
+    codec: &C,
+    payload: &[u8],
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut prefix = Vec::with_capacity(codec.max_len());
+    u64::try_from(payload.len())
+        .map_err(|_| VarIntError::TooLarge)
+        .and_then(|len| codec.encode_append(len, &mut prefix))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    write_prefixed(writer, &prefix, payload)
+}
+
+/// Writes `prefix` then `payload` with vectored writes, so that both usually go out in a single call
+/// without copying the payload next to the prefix.
+pub(crate) fn write_prefixed<W: Write>(
+    writer: &mut W,
+    mut prefix: &[u8],
+    mut payload: &[u8],
+) -> io::Result<()> {
+    #[cfg(feature = "metrics")]
+    let frame_len = prefix.len() + payload.len();
+    while !prefix.is_empty() {
+        match writer.write_vectored(&[IoSlice::new(prefix), IoSlice::new(payload)]) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) if n < prefix.len() => prefix = &prefix[n..],
+            Ok(n) => {
+                payload = &payload[n - prefix.len()..];
+                prefix = &[];
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    writer.write_all(payload)?;
+    #[cfg(feature = "metrics")]
+    crate::metrics::record(|r| r.frame_written(frame_len as u64));
+    Ok(())
+}
+
+/// Reads the next length-prefixed payload, or `None` when the reader is at its end.
+///
+/// Payloads announced as longer than `max_len` are rejected before anything is allocated,
+/// with an [`io::ErrorKind::InvalidData`] error wrapping [`VarIntError::TooLarge`].
+/// An end of input within a message is reported as [`io::ErrorKind::UnexpectedEof`].
+#[cfg(feature = "leb128")]
+pub fn read_delimited<R: Read>(reader: &mut R, max_len: usize) -> io::Result<Option<Vec<u8>>> {
+    read_delimited_with(&Leb128, reader, max_len)
+}
+
+/// [`read_delimited`] with the length encoded by `codec`.
+///
+/// The prefix is read one byte at a time until it decodes, so nothing past it is consumed.
+pub fn read_delimited_with<C: VarCodec + ?Sized, R: Read>(
+    codec: &C,
+    reader: &mut R,
+    max_len: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut payload = Vec::new();
+    Ok(read_delimited_into_with(codec, reader, max_len, &mut payload)?.map(|_| payload))
+}
+
+/// [`read_delimited_with`] into `buf`, cleared first, returning the length of the payload, so that a buffer
+/// can be reused for every message instead of allocating one each time.
+pub fn read_delimited_into_with<C: VarCodec + ?Sized, R: Read>(
+    codec: &C,
+    reader: &mut R,
+    max_len: usize,
+    buf: &mut Vec<u8>,
+) -> io::Result<Option<usize>> {
+    buf.clear();
+    let Some((len, _prefix_len)) = read_prefix(codec, reader)? else {
+        return Ok(None);
+    };
+    if len > max_len as u64 {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(|r| r.frame_rejected(&VarIntError::TooLarge));
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            VarIntError::TooLarge,
+        ));
+    }
+    buf.resize(len as usize, 0);
+    reader.read_exact(buf)?;
+    #[cfg(feature = "metrics")]
+    crate::metrics::record(|r| r.frame_read((_prefix_len + buf.len()) as u64));
+    Ok(Some(buf.len()))
+}
+
+/// Reads the length of the next message and returns a reader over its payload, or `None` when the reader is at
+/// its end, so that large payloads can be parsed without buffering them.
+pub fn read_frame_with<C: VarCodec + ?Sized, R: Read>(
+    codec: &C,
+    mut reader: R,
+) -> io::Result<Option<FrameBody<R>>> {
+    Ok(read_prefix(codec, &mut reader)?.map(|(len, _)| FrameBody::new(reader, len)))
+}
+
+/// Reads a length prefix one byte at a time until it decodes, so nothing past it is consumed, returning it
+/// with its encoded length. The prefix is read into a stack buffer: one longer than [`WINDOW_LEN`] fails.
+pub(crate) fn read_prefix<C: VarCodec + ?Sized, R: Read>(
+    codec: &C,
+    reader: &mut R,
+) -> io::Result<Option<(u64, usize)>> {
+    let mut prefix = [0u8; WINDOW_LEN];
+    let max_len = codec.max_len().min(WINDOW_LEN);
+    loop {
+        match reader.read(&mut prefix[..1]) {
+            Ok(0) => return Ok(None),
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    let mut read = 1;
+    let len = loop {
+        match codec.decode(&prefix[..read]) {
+            Ok((len, _)) => break len,
+            Err(VarIntError::Truncated) if read < max_len => {
+                reader.read_exact(&mut prefix[read..read + 1])?;
+                read += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    Ok(Some((len, read)))
+}
+
+/// Writes length-prefixed messages, encoding every prefix in the same buffer.
+pub struct DelimitedWriter<C, W> {
+    codec: C,
+    writer: W,
+    prefix: Vec<u8>,
+}
+
+impl<C: VarCodec, W: Write> DelimitedWriter<C, W> {
+    pub fn new(codec: C, writer: W) -> Self {
+        let prefix = Vec::with_capacity(codec.max_len());
+        DelimitedWriter {
+            codec,
+            writer,
+            prefix,
+        }
+    }
+
+    /// Writes `payload` prefixed by its length, as [`write_delimited_with`] does.
+    pub fn write_message(&mut self, payload: &[u8]) -> io::Result<()> {
+        self.prefix.clear();
+        u64::try_from(payload.len())
+            .map_err(|_| VarIntError::TooLarge)
+            .and_then(|len| self.codec.encode_append(len, &mut self.prefix))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        write_prefixed(&mut self.writer, &self.prefix, payload)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads length-prefixed messages into the same buffer, which grows to the longest message and is then
+/// reused, so that a long-running reader doesn't allocate per message.
+pub struct DelimitedReader<C, R> {
+    codec: C,
+    reader: R,
+    max_len: usize,
+    buf: Vec<u8>,
+}
+
+impl<C: VarCodec, R: Read> DelimitedReader<C, R> {
+    /// Reads messages framed by `codec`, rejecting those longer than `max_len`.
+    pub fn new(codec: C, reader: R, max_len: usize) -> Self {
+        DelimitedReader {
+            codec,
+            reader,
+            max_len,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Reads the next payload, or `None` when the reader is at its end, with the errors of
+    /// [`read_delimited_with`]. The payload is borrowed until the next read.
+    pub fn read_message(&mut self) -> io::Result<Option<&[u8]>> {
+        let read =
+            read_delimited_into_with(&self.codec, &mut self.reader, self.max_len, &mut self.buf)?;
+        Ok(read.map(|_| &self.buf[..]))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// What [`LengthDelimitedCodec::decode`] does with a frame longer than the maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizedFrame {
+    /// Fails with [`VarIntError::TooLarge`].
+    Error,
+    /// Drops the frame as its bytes arrive, without buffering them, and goes on with the next one.
+    Skip,
+}
+
+/// Framing of length-prefixed messages in a byte buffer, for codecs of async runtimes, with the options of
+/// tokio's `LengthDelimitedCodec`: a maximum frame length, 8 MiB by default, what to do with longer frames,
+/// and whether yielded frames keep their prefix.
+///
+/// The methods follow the contract of `tokio_util::codec::{Encoder, Decoder}`, so wrapping them takes a few lines:
+///
+/// ```ignore
+/// impl<C: VarCodec> Decoder for Framed<C> {
+///     type Item = BytesMut;
+///     type Error = io::Error;
+///
+///     fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+///         let (consumed, frame) = self.0.decode(src)?;
+///         let mut taken = src.split_to(consumed);
+///         Ok(frame.map(|range| taken.split_off(range.start).split_to(range.len())))
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthDelimitedCodec<C> {
+    codec: C,
+    max_frame_length: usize,
+    oversized: OversizedFrame,
+    keep_prefix: bool,
+    /// Bytes of an oversized frame still to drop.
+    skipping: u64,
+}
+
+impl<C: VarCodec> LengthDelimitedCodec<C> {
+    /// Frames messages with the length in `codec`, with the default options.
+    pub fn new(codec: C) -> Self {
+        Self::builder(codec).build()
+    }
+
+    pub fn builder(codec: C) -> LengthDelimitedBuilder<C> {
+        LengthDelimitedBuilder {
+            codec: LengthDelimitedCodec {
+                codec,
+                max_frame_length: 8 * 1024 * 1024,
+                oversized: OversizedFrame::Error,
+                keep_prefix: false,
+                skipping: 0,
+            },
+        }
+    }
+
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+
+    /// Appends `payload` prefixed by its length to `dst`, failing with [`VarIntError::TooLarge`] when it is
+    /// longer than the maximum.
+    pub fn encode(&self, payload: &[u8], dst: &mut Vec<u8>) -> Result<(), VarIntError> {
+        if payload.len() > self.max_frame_length {
+            return Err(VarIntError::TooLarge);
+        }
+        let len = u64::try_from(payload.len()).map_err(|_| VarIntError::TooLarge)?;
+        let _prefix_len = self.codec.encode_append(len, dst)?;
+        dst.extend_from_slice(payload);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(|r| r.frame_written((_prefix_len + payload.len()) as u64));
+        Ok(())
+    }
+
+    /// Decodes the frame at the start of `src`, returning the number of bytes the caller must drop from the
+    /// front of `src` with the range of the frame in `src`, or `None` when `src` doesn't hold all of it yet.
+    ///
+    /// The range starts at the payload, or at the prefix when it is kept. A length above the maximum is
+    /// rejected as soon as it is read, so that a peer can't make the caller buffer the frame. With
+    /// [`OversizedFrame::Skip`], consumed bytes may belong to a dropped frame only.
+    pub fn decode(&mut self, src: &[u8]) -> Result<(usize, Option<Range<usize>>), VarIntError> {
+        let mut start = 0;
+        loop {
+            if self.skipping > 0 {
+                let dropped = self.skipping.min((src.len() - start) as u64);
+                self.skipping -= dropped;
+                start += dropped as usize;
+                if self.skipping > 0 {
+                    return Ok((start, None));
+                }
+            }
+            let (len, prefix_len) = match self.codec.decode(&src[start..]) {
+                Ok(prefix) => prefix,
+                Err(VarIntError::Truncated) => return Ok((start, None)),
+                Err(e) => return Err(e),
+            };
+            // A frame whose end doesn't fit a `usize` is oversized too, whatever the maximum.
+            let Some(end) = usize::try_from(len)
+                .ok()
+                .filter(|len| *len <= self.max_frame_length)
+                .and_then(|len| (start + prefix_len).checked_add(len))
+            else {
+                #[cfg(feature = "metrics")]
+                crate::metrics::record(|r| r.frame_rejected(&VarIntError::TooLarge));
+                match self.oversized {
+                    OversizedFrame::Error => return Err(VarIntError::TooLarge),
+                    OversizedFrame::Skip => {
+                        self.skipping = len;
+                        start += prefix_len;
+                        continue;
+                    }
+                }
+            };
+            if end > src.len() {
+                return Ok((start, None));
+            }
+            #[cfg(feature = "metrics")]
+            crate::metrics::record(|r| r.frame_read((end - start) as u64));
+            let frame = match self.keep_prefix {
+                true => start..end,
+                false => start + prefix_len..end,
+            };
+            return Ok((end, Some(frame)));
+        }
+    }
+}
+
+/// Builder for [`LengthDelimitedCodec`].
+#[derive(Debug, Clone)]
+pub struct LengthDelimitedBuilder<C> {
+    codec: LengthDelimitedCodec<C>,
+}
+
+impl<C> LengthDelimitedBuilder<C> {
+    /// Rejects frames whose payload is longer than `max` bytes.
+    pub fn max_frame_length(mut self, max: usize) -> Self {
+        self.codec.max_frame_length = max;
+        self
+    }
+
+    pub fn oversized(mut self, policy: OversizedFrame) -> Self {
+        self.codec.oversized = policy;
+        self
+    }
+
+    /// Yields frames with their length prefix.
+    pub fn keep_prefix(mut self, keep: bool) -> Self {
+        self.codec.keep_prefix = keep;
+        self
+    }
+
+    pub fn build(self) -> LengthDelimitedCodec<C> {
+        self.codec
+    }
+}
+
+/// A reader over the payload of a frame, which yields at most its declared length.
+///
+/// Parsers reading from it can't run into the next frame: past the declared length, reads return 0 as
+/// at an end of input. When the underlying reader ends before, reads fail with
+/// [`io::ErrorKind::UnexpectedEof`].
+pub struct FrameBody<R> {
+    reader: R,
+    remaining: u64,
+}
+
+impl<R: Read> FrameBody<R> {
+    /// Reads the next `len` bytes of `reader`.
+    pub fn new(reader: R, len: u64) -> Self {
+        FrameBody {
+            reader,
+            remaining: len,
+        }
+    }
+
+    /// Number of bytes of the payload not read yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Skips the rest of the payload, leaving the underlying reader at the start of the next frame.
+    pub fn finish(mut self) -> io::Result<R> {
+        let remaining = self.remaining;
+        let skipped = io::copy(&mut (&mut self.reader).take(remaining), &mut io::sink())?;
+        if skipped < remaining {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(self.reader)
+    }
+
+    /// Returns the underlying reader, wherever it is in the payload.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for FrameBody<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..len])?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("frame ended {} bytes early", self.remaining),
+            ));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// A writer checking that the bytes going through it are a sequence of frames, each a length prefix encoded
+/// by `codec` followed by that many bytes of payload, to catch corrupt output where it is produced.
+///
+/// Every write is checked before it is passed on: a write holding a malformed prefix, or one announcing a
+/// payload longer than `max_len`, fails with [`io::ErrorKind::InvalidData`] without writing anything, and so
+/// does every write after it. A write failing in the underlying writer, or writing part of the buffer, only
+/// moves past the bytes written, so it can be retried. [`ValidatingWriter::finish`] checks that the stream
+/// doesn't end within a frame.
+///
+/// ```
+/// use bitcoin_varint::delimited::{write_delimited_with, ValidatingWriter};
+/// use bitcoin_varint::VarInt;
+/// use std::io::Write;
+///
+/// let mut writer = ValidatingWriter::new(VarInt, Vec::new(), 1024);
+/// write_delimited_with(&VarInt, b"hello", &mut writer).unwrap();
+/// assert!(writer.write_all(&[0xff, 0, 0, 0, 0, 0, 0, 0, 1]).is_err());
+/// assert_eq!(writer.frames(), 1);
+/// ```
+pub struct ValidatingWriter<C, W> {
+    codec: C,
+    writer: W,
+    max_len: u64,
+    state: FrameState,
+    failed: bool,
+}
+
+/// Where a [`ValidatingWriter`] is in the stream.
+#[derive(Clone, Default)]
+struct FrameState {
+    /// The bytes of the prefix being written.
+    prefix: Vec<u8>,
+    /// Bytes of the current payload not written yet.
+    remaining: u64,
+    frames: u64,
+}
+
+impl<C: VarCodec, W: Write> ValidatingWriter<C, W> {
+    pub fn new(codec: C, writer: W, max_len: u64) -> Self {
+        let state = FrameState {
+            prefix: Vec::with_capacity(codec.max_len()),
+            ..FrameState::default()
+        };
+        ValidatingWriter {
+            codec,
+            writer,
+            max_len,
+            state,
+            failed: false,
+        }
+    }
+
+    /// Number of frames whose prefix has been written.
+    pub fn frames(&self) -> u64 {
+        self.state.frames
+    }
+
+    /// Flushes the underlying writer and returns it, failing with [`io::ErrorKind::UnexpectedEof`] when the last
+    /// frame isn't complete.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        if !self.state.prefix.is_empty() || self.state.remaining > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("stream ended within frame {}", self.state.frames),
+            ));
+        }
+        Ok(self.writer)
+    }
+
+    /// Returns the underlying writer, wherever the stream is in a frame.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl FrameState {
+    /// Moves the state past `buf`, failing on the first byte that can't be part of a valid stream.
+    fn check<C: VarCodec>(
+        &mut self,
+        codec: &C,
+        max_len: u64,
+        mut buf: &[u8],
+    ) -> Result<(), VarIntError> {
+        while let Some((byte, rest)) = buf.split_first() {
+            if self.remaining > 0 {
+                let n = buf
+                    .len()
+                    .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+                self.remaining -= n as u64;
+                buf = &buf[n..];
+                continue;
+            }
+            self.prefix.push(*byte);
+            buf = rest;
+            match codec.decode(&self.prefix) {
+                Ok((len, _)) if len > max_len => return Err(VarIntError::TooLarge),
+                Ok((len, _)) => {
+                    self.remaining = len;
+                    self.prefix.clear();
+                    self.frames += 1;
+                }
+                Err(VarIntError::Truncated) if self.prefix.len() < codec.max_len() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<C: VarCodec, W: Write> Write for ValidatingWriter<C, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.failed {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid frame written before",
+            ));
+        }
+        // The whole buffer is checked before writing, the state only moves past what was written.
+        let mut state = self.state.clone();
+        if let Err(e) = state.check(&self.codec, self.max_len, buf) {
+            #[cfg(feature = "metrics")]
+            crate::metrics::record(|r| r.frame_rejected(&e));
+            self.failed = true;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        let n = self.writer.write(buf)?;
+        if n == buf.len() {
+            self.state = state;
+        } else {
+            // A prefix of a valid write is valid.
+            let _ = self.state.check(&self.codec, self.max_len, &buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "leb128")]
+    use crate::test::Trickle;
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_delimited_round_trip() {
+        let mut stream = Vec::new();
+        write_delimited(b"hello", &mut stream).unwrap();
+        write_delimited(&[0x2a; 300], &mut stream).unwrap();
+        assert_eq!(&stream[..6], b"\x05hello");
+        assert_eq!(&stream[6..8], &[0xac, 0x02]);
+
+        let mut reader = stream.as_slice();
+        assert_eq!(
+            read_delimited(&mut reader, 1024).unwrap().unwrap(),
+            b"hello"
+        );
+        assert_eq!(
+            read_delimited(&mut reader, 1024).unwrap().unwrap(),
+            vec![0x2a; 300]
+        );
+        assert!(read_delimited(&mut reader, 1024).unwrap().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_delimited_partial_writes() {
+        let payload = [0x55; 200];
+        let mut writer = Trickle {
+            inner: Vec::new(),
+            limit: 3,
+        };
+        write_delimited(&payload, &mut writer).unwrap();
+        let mut expected = vec![0xc8, 0x01];
+        expected.extend_from_slice(&payload);
+        assert_eq!(writer.inner, expected);
+    }
+
+    #[test]
+    fn test_delimited_with_codec() {
+        use crate::VarInt;
+
+        let mut stream = Vec::new();
+        write_delimited_with(&VarInt, &[0x11; 300], &mut stream).unwrap();
+        write_delimited_with(&VarInt, b"ok", &mut stream).unwrap();
+        assert_eq!(&stream[..3], &[0xfd, 0x2c, 0x01]);
+
+        let mut reader = stream.as_slice();
+        let payload = read_delimited_with(&VarInt, &mut reader, 1024).unwrap();
+        assert_eq!(payload.unwrap(), vec![0x11; 300]);
+        let payload = read_delimited_with(&VarInt, &mut reader, 1024).unwrap();
+        assert_eq!(payload.unwrap(), b"ok");
+        assert!(read_delimited_with(&VarInt, &mut reader, 1024)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_delimited_buffer_reuse() {
+        use crate::VarInt;
+
+        let mut writer = DelimitedWriter::new(VarInt, Vec::new());
+        for payload in [&b"hello"[..], b"", b"hi"] {
+            writer.write_message(payload).unwrap();
+        }
+        let stream = writer.into_inner();
+        assert_eq!(stream, b"\x05hello\x00\x02hi");
+
+        let mut buf = Vec::new();
+        let mut reader = &stream[..];
+        assert_eq!(
+            read_delimited_into_with(&VarInt, &mut reader, 16, &mut buf).unwrap(),
+            Some(5)
+        );
+        let ptr = buf.as_ptr();
+        assert_eq!(
+            read_delimited_into_with(&VarInt, &mut reader, 16, &mut buf).unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            read_delimited_into_with(&VarInt, &mut reader, 16, &mut buf).unwrap(),
+            Some(2)
+        );
+        assert_eq!((buf.as_ptr(), &buf[..]), (ptr, &b"hi"[..]));
+        assert_eq!(
+            read_delimited_into_with(&VarInt, &mut reader, 16, &mut buf).unwrap(),
+            None
+        );
+
+        let mut reader = DelimitedReader::new(VarInt, &stream[..], 16);
+        assert_eq!(reader.read_message().unwrap(), Some(&b"hello"[..]));
+        assert_eq!(reader.read_message().unwrap(), Some(&b""[..]));
+        assert_eq!(reader.read_message().unwrap(), Some(&b"hi"[..]));
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+
+    #[test]
+    fn test_validating_writer() {
+        use crate::VarInt;
+
+        let mut stream = Vec::new();
+        write_delimited_with(&VarInt, &[0x11; 300], &mut stream).unwrap();
+        write_delimited_with(&VarInt, b"", &mut stream).unwrap();
+        write_delimited_with(&VarInt, b"ok", &mut stream).unwrap();
+        // Written in pieces cutting prefixes and payloads.
+        let mut writer = ValidatingWriter::new(VarInt, Vec::new(), 300);
+        for piece in stream.chunks(2) {
+            writer.write_all(piece).unwrap();
+        }
+        assert_eq!(writer.frames(), 3);
+        assert_eq!(writer.finish().unwrap(), stream);
+
+        let mut writer = ValidatingWriter::new(VarInt, Vec::new(), 299);
+        let err = writer.write(&stream).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(writer.write(b"").is_err());
+        assert!(writer.into_inner().is_empty());
+
+        let mut writer = ValidatingWriter::new(VarInt, Vec::new(), 300);
+        writer.write_all(&stream[..10]).unwrap();
+        let err = writer.finish().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_validating_writer_retry() {
+        use crate::VarInt;
+
+        /// Fails every other write, and writes at most 3 bytes of the others.
+        struct Flaky {
+            out: Vec<u8>,
+            fail: bool,
+        }
+
+        impl Write for Flaky {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.fail = !self.fail;
+                if self.fail {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                let n = buf.len().min(3);
+                self.out.extend_from_slice(&buf[..n]);
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut stream = Vec::new();
+        write_delimited_with(&VarInt, &[0x11; 300], &mut stream).unwrap();
+        write_delimited_with(&VarInt, b"ok", &mut stream).unwrap();
+        let flaky = Flaky {
+            out: Vec::new(),
+            fail: false,
+        };
+        let mut writer = ValidatingWriter::new(VarInt, flaky, 300);
+        let err = writer.write(&stream).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        assert_eq!(writer.frames(), 0);
+        assert_eq!(writer.write(&stream).unwrap(), 3);
+        assert_eq!(writer.frames(), 1);
+        // write_all retries interrupted writes.
+        writer.write_all(&stream[3..]).unwrap();
+        assert_eq!(writer.frames(), 2);
+        assert_eq!(writer.finish().unwrap().out, stream);
+    }
+
+    #[test]
+    fn test_frame_body() {
+        use crate::VarInt;
+
+        let mut stream = Vec::new();
+        write_delimited_with(&VarInt, b"first frame", &mut stream).unwrap();
+        write_delimited_with(&VarInt, b"second", &mut stream).unwrap();
+        let mut reader = stream.as_slice();
+
+        // A parser reading to the end only gets its own frame.
+        let mut body = read_frame_with(&VarInt, &mut reader).unwrap().unwrap();
+        let mut first = [0u8; 5];
+        body.read_exact(&mut first).unwrap();
+        assert_eq!(&first, b"first");
+        assert_eq!(body.remaining(), 6);
+        body.finish().unwrap();
+        let mut body = read_frame_with(&VarInt, &mut reader).unwrap().unwrap();
+        let mut second = Vec::new();
+        body.read_to_end(&mut second).unwrap();
+        assert_eq!(second, b"second");
+        assert!(read_frame_with(&VarInt, &mut reader).unwrap().is_none());
+
+        // An underrun is an error, not a short frame.
+        let mut body = FrameBody::new(&b"abc"[..], 5);
+        let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = FrameBody::new(&b"abc"[..], 5).finish().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_delimited_errors() {
+        let mut reader: &[u8] = &[0xac, 0x02, 0x00];
+        let err = read_delimited(&mut reader, 299).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut reader: &[u8] = &[0x05, b'h'];
+        let err = read_delimited(&mut reader, 1024).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_length_delimited_codec() {
+        use crate::VarInt;
+
+        let mut codec = LengthDelimitedCodec::builder(VarInt)
+            .max_frame_length(4)
+            .build();
+        let mut stream = Vec::new();
+        codec.encode(b"abc", &mut stream).unwrap();
+        codec.encode(b"", &mut stream).unwrap();
+        assert_eq!(
+            codec.encode(b"abcde", &mut stream),
+            Err(VarIntError::TooLarge)
+        );
+        assert_eq!(stream, b"\x03abc\x00");
+        assert_eq!(codec.decode(&stream[..3]), Ok((0, None)));
+        assert_eq!(codec.decode(&stream), Ok((4, Some(1..4))));
+        assert_eq!(codec.decode(&stream[4..]), Ok((1, Some(1..1))));
+        assert_eq!(codec.decode(&[0x05, 0x01]), Err(VarIntError::TooLarge));
+        assert_eq!(codec.decode(&[]), Ok((0, None)));
+
+        let mut codec = LengthDelimitedCodec::builder(VarInt)
+            .max_frame_length(4)
+            .oversized(OversizedFrame::Skip)
+            .keep_prefix(true)
+            .build();
+        // An oversized frame arriving in two pieces, then a frame kept with its prefix.
+        assert_eq!(codec.decode(&[0x06, 1, 2, 3]), Ok((4, None)));
+        assert_eq!(codec.decode(&[4, 5, 6, 0x02, b'o']), Ok((3, None)));
+        assert_eq!(codec.decode(&[0x02, b'o', b'k']), Ok((3, Some(0..3))));
+
+        // Lengths whose frame would end past `usize::MAX` are oversized even without a maximum.
+        let mut codec = LengthDelimitedCodec::builder(VarInt)
+            .max_frame_length(usize::MAX)
+            .build();
+        let huge = [0xff; 9];
+        assert_eq!(codec.decode(&huge), Err(VarIntError::TooLarge));
+        assert_eq!(codec.decode(&[0xfd, 0x00, 0x01, 0xaa]), Ok((0, None)));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::VarIntError;
+
+/// The size of an EBML element, as announced by its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementSize {
+    Known(u64),
+    /// All data bits set: the element extends until an element that cannot be its child is found (live streams).
+    Unknown,
+}
+
+/// EBML Variable-Size Integers (VINT)
+///
+/// The number of leading zero bits of the first byte, plus one, gives the total length (1 to 8 bytes).
+/// The first set bit is the VINT_MARKER, the remaining bits are the value in big-endian order.
+///
+/// Element IDs are kept as the raw bytes including the marker (e.g. `0x1A45DFA3` for the EBML header),
+/// while element sizes strip the marker and reserve the all-ones value for "unknown size".
+///
+/// https://www.rfc-editor.org/rfc/rfc8794.html#name-variable-size-integer
+pub struct Ebml;
+
+impl Ebml {
+    /// Largest encodable known size (2^56 - 2), 2^56 - 1 being the unknown size sentinel on 8 bytes.
+    pub const MAX_SIZE: u64 = (1 << 56) - 2;
+
+    /// Encodes an element size in the shortest form.
+    pub fn encode_size(size: u64) -> Result<Vec<u8>, VarIntError> {
+        let len = (1..=8)
+            .find(|len| size < Self::sentinel(*len))
+            .ok_or(VarIntError::Overflow)?;
+        Self::encode_size_with_len(size, len)
+    }
+
+    /// Encodes an element size on exactly `len` bytes, as muxers do to reserve room for a size patched in later.
+    pub fn encode_size_with_len(size: u64, len: usize) -> Result<Vec<u8>, VarIntError> {
+        if !(1..=8).contains(&len) || size >= Self::sentinel(len) {
+            return Err(VarIntError::Overflow);
+        }
+        Ok(Self::write(size | (1 << (7 * len)), len))
+    }
+
+    /// Encodes the one byte "unknown size" marker.
+    pub fn encode_unknown_size() -> Vec<u8> {
+        vec![0xff]
+    }
+
+    /// Decodes an element size, returning it with the number of bytes read.
+    pub fn decode_size(bytes: &[u8]) -> Result<(ElementSize, usize), VarIntError> {
+        let (raw, len) = Self::read(bytes, 8)?;
+        let value = raw & !(1 << (7 * len));
+        if value == Self::sentinel(len) {
+            Ok((ElementSize::Unknown, len))
+        } else {
+            Ok((ElementSize::Known(value), len))
+        }
+    }
+
+    /// Encodes an element ID given with its marker bit, checking that it is a valid ID of at most 4 bytes.
+    pub fn encode_id(id: u32) -> Result<Vec<u8>, VarIntError> {
+        let len = 4 - (id.leading_zeros() as usize / 8);
+        if len == 0 {
+            return Err(VarIntError::Malformed);
+        }
+        let bytes = Self::write(id as u64, len);
+        match Self::decode_id(&bytes)? {
+            (_, read) if read == len => Ok(bytes),
+            _ => Err(VarIntError::Malformed),
+        }
+    }
+
+    /// Decodes an element ID, returning it with its marker bit and the number of bytes read.
+    ///
+    /// IDs whose data bits are all zeros or all ones, or that are not in their shortest form, are rejected.
+    pub fn decode_id(bytes: &[u8]) -> Result<(u32, usize), VarIntError> {
+        let (raw, len) = Self::read(bytes, 4)?;
+        let value = raw & !(1 << (7 * len));
+        if value == 0 || value == Self::sentinel(len) {
+            return Err(VarIntError::Malformed);
+        }
+        // The shorter form is only available if it does not end up all ones.
+        if len > 1 && value < Self::sentinel(len - 1) {
+            return Err(VarIntError::NonCanonical);
+        }
+        Ok((raw as u32, len))
+    }
+
+    /// The all-ones value on `len` bytes.
+    fn sentinel(len: usize) -> u64 {
+        (1 << (7 * len)) - 1
+    }
+
+    fn write(raw: u64, len: usize) -> Vec<u8> {
+        raw.to_be_bytes()[8 - len..].to_vec()
+    }
+
+    fn read(bytes: &[u8], max_len: usize) -> Result<(u64, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let len = first.leading_zeros() as usize + 1;
+        if len > max_len {
+            return Err(VarIntError::Malformed);
+        }
+        let payload = bytes.get(..len).ok_or(VarIntError::Truncated)?;
+        let mut buf = [0u8; 8];
+        buf[8 - len..].copy_from_slice(payload);
+        Ok((u64::from_be_bytes(buf), len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ebml_encode_size() {
+        assert_eq!(Ebml::encode_size(0).unwrap(), vec![0x80]);
+        assert_eq!(Ebml::encode_size(126).unwrap(), vec![0xfe]);
+        assert_eq!(Ebml::encode_size(127).unwrap(), vec![0x40, 0x7f]);
+        assert_eq!(
+            Ebml::encode_size_with_len(2, 8).unwrap(),
+            vec![1, 0, 0, 0, 0, 0, 0, 2]
+        );
+        assert_eq!(
+            Ebml::encode_size(Ebml::MAX_SIZE + 1),
+            Err(VarIntError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_ebml_decode_size() {
+        assert_eq!(
+            Ebml::decode_size(&[0x40, 0x7f]).unwrap(),
+            (ElementSize::Known(127), 2)
+        );
+        assert_eq!(
+            Ebml::decode_size(&[0xff]).unwrap(),
+            (ElementSize::Unknown, 1)
+        );
+        assert_eq!(
+            Ebml::decode_size(&[0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap(),
+            (ElementSize::Unknown, 8)
+        );
+        assert_eq!(Ebml::decode_size(&[0x00]), Err(VarIntError::Malformed));
+        assert_eq!(
+            Ebml::decode_size(&[0x20, 0x00]),
+            Err(VarIntError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_ebml_id() {
+        assert_eq!(
+            Ebml::encode_id(0x1A45DFA3).unwrap(),
+            vec![0x1a, 0x45, 0xdf, 0xa3]
+        );
+        assert_eq!(
+            Ebml::decode_id(&[0x18, 0x53, 0x80, 0x67]).unwrap(),
+            (0x18538067, 4)
+        );
+        assert_eq!(Ebml::decode_id(&[0xff]), Err(VarIntError::Malformed));
+        assert_eq!(Ebml::encode_id(0x8101), Err(VarIntError::Malformed));
+        assert_eq!(
+            Ebml::decode_id(&[0x40, 0x01]),
+            Err(VarIntError::NonCanonical)
+        );
+        // 0x407f cannot be shortened to 0xff, which is reserved.
+        assert_eq!(Ebml::decode_id(&[0x40, 0x7f]).unwrap(), (0x407f, 2));
+    }
+}
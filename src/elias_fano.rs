@@ -0,0 +1,345 @@
+use crate::{Leb128, VarIntError};
+
+/// Ones and zeros of the high bits between two samples of the select index.
+const SAMPLE: usize = 256;
+
+/// Elias-Fano representation of a non-decreasing sequence
+///
+/// Each value is split into `low_bits` low bits, stored packed, and a high part stored in unary
+/// in a bitvector: the i-th value sets bit `high + i`. This takes less than `2 + log2(max / len)`
+/// bits per value while giving access to any value in near constant time.
+///
+/// https://www.antoniomallia.it/sorted-integers-compression-with-elias-fano-encoding.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EliasFano {
+    len: usize,
+    low_bits: u8,
+    low: Vec<u64>,
+    high: Vec<u64>,
+    /// Number of bits used in `high`.
+    high_len: usize,
+    /// Positions in `high` of every `SAMPLE`-th one.
+    ones: Vec<usize>,
+    /// Positions in `high` of every `SAMPLE`-th zero.
+    zeros: Vec<usize>,
+}
+
+impl EliasFano {
+    /// Builds the representation of `values`, which must be sorted.
+    pub fn new(values: &[u64]) -> Result<Self, VarIntError> {
+        if values.windows(2).any(|w| w[0] > w[1]) {
+            return Err(VarIntError::OutOfOrder);
+        }
+        let len = values.len();
+        let max = values.last().copied().unwrap_or(0);
+        let low_bits = match max.checked_div(len as u64) {
+            Some(ratio) if ratio > 0 => 63 - ratio.leading_zeros() as u8,
+            _ => 0,
+        };
+        let high_len = len + (max >> low_bits) as usize + 1;
+        let mut low = vec![0; (len * low_bits as usize).div_ceil(64)];
+        let mut high = vec![0; high_len.div_ceil(64)];
+        for (i, value) in values.iter().enumerate() {
+            write_bits(&mut low, i * low_bits as usize, low_bits, *value);
+            let pos = (value >> low_bits) as usize + i;
+            high[pos / 64] |= 1 << (pos % 64);
+        }
+        Ok(Self::with_parts(len, low_bits, low, high, high_len))
+    }
+
+    fn with_parts(
+        len: usize,
+        low_bits: u8,
+        low: Vec<u64>,
+        high: Vec<u64>,
+        high_len: usize,
+    ) -> Self {
+        let mut sequence = EliasFano {
+            len,
+            low_bits,
+            low,
+            high,
+            high_len,
+            ones: Vec::new(),
+            zeros: Vec::new(),
+        };
+        let (mut ones, mut zeros) = (0, 0);
+        for pos in 0..high_len {
+            if sequence.high_bit(pos) {
+                if ones % SAMPLE == 0 {
+                    sequence.ones.push(pos);
+                }
+                ones += 1;
+            } else {
+                if zeros % SAMPLE == 0 {
+                    sequence.zeros.push(pos);
+                }
+                zeros += 1;
+            }
+        }
+        sequence
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the `i`-th value.
+    pub fn select(&self, i: usize) -> Option<u64> {
+        if i >= self.len {
+            return None;
+        }
+        let high = (self.select_bit(&self.ones, i, true) - i) as u64;
+        Some(high << self.low_bits | self.low(i))
+    }
+
+    /// Returns the first value greater than or equal to `x`, with its index.
+    pub fn next_geq(&self, x: u64) -> Option<(usize, u64)> {
+        if self.len == 0 || x > self.select(self.len - 1)? {
+            return None;
+        }
+        // Values with a high part of at least `x`'s start right after the `bucket`-th zero.
+        let bucket = (x >> self.low_bits) as usize;
+        let mut pos = match bucket {
+            0 => 0,
+            _ => self.select_bit(&self.zeros, bucket - 1, false) + 1,
+        };
+        let mut index = pos - bucket;
+        loop {
+            if self.high_bit(pos) {
+                let value = ((pos - index) as u64) << self.low_bits | self.low(index);
+                if value >= x {
+                    return Some((index, value));
+                }
+                index += 1;
+            }
+            pos += 1;
+        }
+    }
+
+    pub fn iter(&self) -> EliasFanoIter<'_> {
+        EliasFanoIter {
+            sequence: self,
+            index: 0,
+            pos: 0,
+        }
+    }
+
+    /// Serializes the sequence: its length (LEB128), the number of low bits (one byte),
+    /// the number of high bits (LEB128), then the low and high bits, least significant first.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        Leb128::encode_append(self.len as u64, &mut out);
+        out.push(self.low_bits);
+        Leb128::encode_append(self.high_len as u64, &mut out);
+        write_words(&self.low, self.len * self.low_bits as usize, &mut out);
+        write_words(&self.high, self.high_len, &mut out);
+        out
+    }
+
+    /// Deserializes a sequence written by [`EliasFano::to_bytes`], returning it with the number of bytes read.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), VarIntError> {
+        let (len, mut pos) = Leb128::decode(bytes)?;
+        let low_bits = *bytes.get(pos).ok_or(VarIntError::Truncated)?;
+        pos += 1;
+        let (high_len, size) = Leb128::decode(&bytes[pos..])?;
+        pos += size;
+        if low_bits > 63 || high_len <= len {
+            return Err(VarIntError::Malformed);
+        }
+        let len = usize::try_from(len).map_err(|_| VarIntError::TooLarge)?;
+        let high_len = usize::try_from(high_len).map_err(|_| VarIntError::TooLarge)?;
+        let low_len = len
+            .checked_mul(low_bits as usize)
+            .ok_or(VarIntError::TooLarge)?;
+        if bytes.len() - pos < low_len.div_ceil(8) + high_len.div_ceil(8) {
+            return Err(VarIntError::Truncated);
+        }
+        let low = read_words(&bytes[pos..], low_len);
+        pos += low_len.div_ceil(8);
+        let high = read_words(&bytes[pos..], high_len);
+        pos += high_len.div_ceil(8);
+
+        let ones: usize = high.iter().map(|w| w.count_ones() as usize).sum();
+        // The last high bit is always the zero terminating the last bucket.
+        if ones != len || high[(high_len - 1) / 64] >> ((high_len - 1) % 64) & 1 != 0 {
+            return Err(VarIntError::Malformed);
+        }
+        let sequence = Self::with_parts(len, low_bits, low, high, high_len);
+        if sequence
+            .iter()
+            .zip(sequence.iter().skip(1))
+            .any(|(a, b)| a > b)
+        {
+            return Err(VarIntError::OutOfOrder);
+        }
+        Ok((sequence, pos))
+    }
+
+    fn low(&self, i: usize) -> u64 {
+        read_bits(&self.low, i * self.low_bits as usize, self.low_bits)
+    }
+
+    fn high_bit(&self, pos: usize) -> bool {
+        self.high[pos / 64] >> (pos % 64) & 1 == 1
+    }
+
+    /// Position of the `rank`-th one (or zero) of the high bits, starting from the closest sample.
+    fn select_bit(&self, samples: &[usize], rank: usize, one: bool) -> usize {
+        let word = |i: usize| if one { self.high[i] } else { !self.high[i] };
+        let start = samples[rank / SAMPLE];
+        let mut remaining = rank % SAMPLE;
+        let mut index = start / 64;
+        let mut bits = word(index) & (u64::MAX << (start % 64));
+        loop {
+            let count = bits.count_ones() as usize;
+            if remaining < count {
+                for _ in 0..remaining {
+                    bits &= bits - 1;
+                }
+                return index * 64 + bits.trailing_zeros() as usize;
+            }
+            remaining -= count;
+            index += 1;
+            bits = word(index);
+        }
+    }
+}
+
+/// Iterator over the values of an [`EliasFano`] sequence, in order.
+pub struct EliasFanoIter<'a> {
+    sequence: &'a EliasFano,
+    index: usize,
+    /// Position in the high bits.
+    pos: usize,
+}
+
+impl Iterator for EliasFanoIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index >= self.sequence.len {
+            return None;
+        }
+        while !self.sequence.high_bit(self.pos) {
+            self.pos += 1;
+        }
+        let high = (self.pos - self.index) as u64;
+        let value = high << self.sequence.low_bits | self.sequence.low(self.index);
+        self.index += 1;
+        self.pos += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.sequence.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for EliasFanoIter<'_> {}
+
+fn mask(width: u8) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+fn write_bits(words: &mut [u64], pos: usize, width: u8, value: u64) {
+    if width == 0 {
+        return;
+    }
+    let value = value & mask(width);
+    let (index, shift) = (pos / 64, pos % 64);
+    words[index] |= value << shift;
+    if shift + width as usize > 64 {
+        words[index + 1] |= value >> (64 - shift);
+    }
+}
+
+fn read_bits(words: &[u64], pos: usize, width: u8) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+    let (index, shift) = (pos / 64, pos % 64);
+    let mut value = words[index] >> shift;
+    if shift + width as usize > 64 {
+        value |= words[index + 1] << (64 - shift);
+    }
+    value & mask(width)
+}
+
+/// Appends the first `bits` bits of `words` to `out`, as little-endian bytes.
+fn write_words(words: &[u64], bits: usize, out: &mut Vec<u8>) {
+    let bytes = words.iter().flat_map(|w| w.to_le_bytes());
+    out.extend(bytes.take(bits.div_ceil(8)));
+}
+
+/// Reads `bits` bits written by [`write_words`], clearing any bit after them.
+fn read_words(bytes: &[u8], bits: usize) -> Vec<u64> {
+    let mut words: Vec<u64> = bytes[..bits.div_ceil(8)]
+        .chunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect();
+    if let Some(last) = words.last_mut() {
+        *last &= mask(((bits - 1) % 64 + 1) as u8);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elias_fano_select() {
+        let values: Vec<u64> = (0..2000u64).map(|i| i * i / 3 + 5).collect();
+        let sequence = EliasFano::new(&values).unwrap();
+        assert_eq!(sequence.len(), values.len());
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(sequence.select(i), Some(*value));
+        }
+        assert_eq!(sequence.select(values.len()), None);
+        assert!(sequence.iter().eq(values.iter().copied()));
+    }
+
+    #[test]
+    fn test_elias_fano_next_geq() {
+        let values = [3, 3, 4, 7, 18, 18, 100, 1 << 40];
+        let sequence = EliasFano::new(&values).unwrap();
+        assert_eq!(sequence.next_geq(0), Some((0, 3)));
+        assert_eq!(sequence.next_geq(5), Some((3, 7)));
+        assert_eq!(sequence.next_geq(18), Some((4, 18)));
+        assert_eq!(sequence.next_geq(101), Some((7, 1 << 40)));
+        assert_eq!(sequence.next_geq((1 << 40) + 1), None);
+        assert_eq!(EliasFano::new(&[5, 4]), Err(VarIntError::OutOfOrder));
+        assert_eq!(EliasFano::new(&[]).unwrap().next_geq(0), None);
+    }
+
+    #[test]
+    fn test_elias_fano_bytes() {
+        let values: Vec<u64> = (0..1000u64).map(|i| i * 1000 + i % 7).collect();
+        let sequence = EliasFano::new(&values).unwrap();
+        let bytes = sequence.to_bytes();
+        // About 2 + log2(1000) bits per value.
+        assert!(bytes.len() < values.len() * 12 / 8 + 16);
+        assert_eq!(
+            EliasFano::from_bytes(&bytes).unwrap(),
+            (sequence, bytes.len())
+        );
+        assert_eq!(
+            EliasFano::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
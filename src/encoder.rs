@@ -0,0 +1,484 @@
+//! A reusable encoder with configurable codec and limits, the counterpart of [`Decoder`](crate::decoder::Decoder).
+//!
+//! Every codec of this crate writes values in their shortest form, so encoded values are canonical whatever
+//! the configuration.
+//!
+//! Writes to async writers go through a buffer, which the encoder fills without needing the writer:
+//!
+//! ```ignore
+//! async fn write_counts<W: AsyncWrite + Unpin>(writer: &mut W, encoder: &Encoder, counts: &[u64]) -> io::Result<()> {
+//!     writer.write_all(&encoder.encode_all(counts)?).await
+//! }
+//! ```
+
+use crate::{Codec, VarCodec, VarIntError};
+use std::io::{self, Write};
+use std::mem::ManuallyDrop;
+use std::{error, fmt, mem, ptr};
+
+/// An encoder for the values of one format, refusing those it doesn't allow.
+///
+/// ```
+/// use bitcoin_varint::encoder::Encoder;
+/// use bitcoin_varint::VarIntError;
+///
+/// let encoder = Encoder::builder().max_value(1000).build();
+/// assert_eq!(encoder.encode(1000), Ok(vec![0xfd, 0xe8, 0x03]));
+/// assert_eq!(encoder.encode(1001), Err(VarIntError::TooLarge));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoder {
+    codec: Codec,
+    max_value: Option<u64>,
+}
+
+impl Default for Encoder {
+    /// compactSize, accepting any value.
+    fn default() -> Self {
+        Encoder {
+            codec: Codec::CompactSize,
+            max_value: None,
+        }
+    }
+}
+
+impl Encoder {
+    pub fn builder() -> EncoderBuilder {
+        EncoderBuilder {
+            encoder: Encoder::default(),
+        }
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    ///
+    /// Values above the maximum fail with [`VarIntError::TooLarge`], and values the codec can't represent
+    /// with its error, [`VarIntError::Overflow`] for QUIC.
+    pub fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        if self.max_value.is_some_and(|max| value > max) {
+            return Err(VarIntError::TooLarge);
+        }
+        self.codec.encode_append(value, out)
+    }
+
+    /// Encodes a value into a new buffer.
+    pub fn encode(&self, value: u64) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::with_capacity(self.codec.max_len());
+        self.encode_append(value, &mut result)?;
+        Ok(result)
+    }
+
+    /// Encodes a length or count, failing with [`VarIntError::TooLarge`] rather than truncating it where a
+    /// `usize` is wider than 64 bits.
+    pub fn encode_len(&self, len: usize) -> Result<Vec<u8>, VarIntError> {
+        self.encode(u64::try_from(len).map_err(|_| VarIntError::TooLarge)?)
+    }
+
+    /// Encodes every value of `values`, concatenated, failing on the first value not allowed.
+    pub fn encode_all(&self, values: &[u64]) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::with_capacity(values.len());
+        for value in values {
+            self.encode_append(*value, &mut result)?;
+        }
+        Ok(result)
+    }
+
+    /// Writes the encoding of `value` to `writer`, returning the number of bytes written.
+    ///
+    /// Values not allowed fail with [`io::ErrorKind::InvalidInput`], before anything is written.
+    pub fn write<W: Write>(&self, value: u64, writer: &mut W) -> io::Result<usize> {
+        let bytes = self
+            .encode(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        writer.write_all(&bytes)?;
+        Ok(bytes.len())
+    }
+}
+
+/// Builder for [`Encoder`].
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderBuilder {
+    encoder: Encoder,
+}
+
+impl EncoderBuilder {
+    /// Encodes values with `codec` instead of compactSize.
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.encoder.codec = codec;
+        self
+    }
+
+    /// Rejects values above `max`.
+    pub fn max_value(mut self, max: u64) -> Self {
+        self.encoder.max_value = Some(max);
+        self
+    }
+
+    pub fn build(self) -> Encoder {
+        self.encoder
+    }
+}
+
+/// A writer of values that batches their encodings in a buffer, so that writing many small values takes
+/// one write of the underlying writer per buffer rather than one per value.
+///
+/// The buffer is written once it holds `capacity` bytes, on [`flush`](VarIntWriter::flush), and when the
+/// writer is dropped, where errors are ignored as [`io::BufWriter`] does. A write failing partway only drops
+/// from the buffer the bytes the underlying writer took, so retrying doesn't write them twice.
+///
+/// ```
+/// use bitcoin_varint::encoder::VarIntWriter;
+///
+/// let mut writer = VarIntWriter::new(Vec::new());
+/// for value in [1, 515, 70_000] {
+///     writer.write_value(value).unwrap();
+/// }
+/// assert_eq!(writer.written_values(), 3);
+/// assert_eq!(writer.into_inner().unwrap(), [0x01, 0xfd, 0x03, 0x02, 0xfe, 0x70, 0x11, 0x01, 0x00]);
+/// ```
+#[derive(Debug)]
+pub struct VarIntWriter<W: Write> {
+    encoder: Encoder,
+    writer: W,
+    buf: Vec<u8>,
+    capacity: usize,
+    values: u64,
+    /// Set while the underlying writer is being written, so that dropping the writer after it panicked
+    /// doesn't write the buffer again.
+    panicked: bool,
+}
+
+impl<W: Write> VarIntWriter<W> {
+    /// Writes compactSizes through a buffer of 8 KiB.
+    pub fn new(writer: W) -> Self {
+        Self::with_capacity(8 * 1024, writer)
+    }
+
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
+        Self::with_encoder(Encoder::default(), capacity, writer)
+    }
+
+    /// Writes values encoded by `encoder`, which refuses those it doesn't allow.
+    pub fn with_encoder(encoder: Encoder, capacity: usize, writer: W) -> Self {
+        VarIntWriter {
+            encoder,
+            writer,
+            buf: Vec::with_capacity(capacity + encoder.codec().max_len()),
+            capacity,
+            values: 0,
+            panicked: false,
+        }
+    }
+
+    /// Buffers the encoding of `value`, returning its length, and writes the buffer once full.
+    ///
+    /// Values the encoder doesn't allow fail with [`io::ErrorKind::InvalidInput`], and are not counted.
+    pub fn write_value(&mut self, value: u64) -> io::Result<usize> {
+        let len = self
+            .encoder
+            .encode_append(value, &mut self.buf)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.values += 1;
+        if self.buf.len() >= self.capacity {
+            self.write_buf()?;
+        }
+        Ok(len)
+    }
+
+    /// Buffers every value of `values`, stopping at the first failure.
+    pub fn write_values(&mut self, values: &[u64]) -> io::Result<()> {
+        for value in values {
+            self.write_value(*value)?;
+        }
+        Ok(())
+    }
+
+    /// Number of values accepted so far, whether still buffered or written.
+    pub fn written_values(&self) -> u64 {
+        self.values
+    }
+
+    /// Bytes waiting in the buffer.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Writes the buffer and flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_buf()?;
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flushes the buffer and returns the underlying writer.
+    ///
+    /// On failure, the error gives back the writer with the bytes it didn't take.
+    pub fn into_inner(mut self) -> Result<W, IntoInnerError<W>> {
+        let result = self.flush();
+        let (writer, buffered) = self.into_parts();
+        match result {
+            Ok(()) => Ok(writer),
+            Err(error) => Err(IntoInnerError {
+                writer,
+                buffered,
+                error,
+            }),
+        }
+    }
+
+    /// Returns the underlying writer and the buffered bytes, without writing them.
+    pub fn into_parts(self) -> (W, Vec<u8>) {
+        let mut this = ManuallyDrop::new(self);
+        let buf = mem::take(&mut this.buf);
+        // SAFETY: `this` is never dropped nor used again, so the writer is moved out once.
+        let writer = unsafe { ptr::read(&this.writer) };
+        (writer, buf)
+    }
+
+    /// Writes the buffer, dropping from it the bytes the underlying writer took even when it fails.
+    fn write_buf(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        let result = loop {
+            if written == self.buf.len() {
+                break Ok(());
+            }
+            self.panicked = true;
+            let result = self.writer.write(&self.buf[written..]);
+            self.panicked = false;
+            match result {
+                Ok(0) => {
+                    break Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write the buffered values",
+                    ))
+                }
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.buf.drain(..written);
+        result
+    }
+}
+
+impl<W: Write> Drop for VarIntWriter<W> {
+    fn drop(&mut self) {
+        if !self.panicked {
+            let _ = self.write_buf();
+        }
+    }
+}
+
+/// The error of [`VarIntWriter::into_inner`], holding the underlying writer and the bytes it didn't take.
+#[derive(Debug)]
+pub struct IntoInnerError<W> {
+    writer: W,
+    buffered: Vec<u8>,
+    error: io::Error,
+}
+
+impl<W> IntoInnerError<W> {
+    /// The error writing the buffer or flushing the writer.
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Bytes of the buffer the writer didn't take.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffered
+    }
+
+    pub fn into_error(self) -> io::Error {
+        self.error
+    }
+
+    /// The error, the writer and the bytes of the buffer it didn't take.
+    pub fn into_parts(self) -> (io::Error, W, Vec<u8>) {
+        (self.error, self.writer, self.buffered)
+    }
+}
+
+impl<W> fmt::Display for IntoInnerError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<W: fmt::Debug> error::Error for IntoInnerError<W> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<W> From<IntoInnerError<W>> for io::Error {
+    fn from(error: IntoInnerError<W>) -> Self {
+        error.error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::MAX_SIZE;
+
+    #[test]
+    fn test_encoder_default() {
+        let encoder = Encoder::default();
+        assert_eq!(encoder.encode(u64::MAX).unwrap().len(), 9);
+        assert_eq!(encoder.encode_len(300), Ok(vec![0xfd, 0x2c, 0x01]));
+        assert_eq!(
+            encoder.encode_all(&[1, 0xfd]),
+            Ok(vec![0x01, 0xfd, 0xfd, 0x00])
+        );
+        let mut out = Vec::new();
+        assert_eq!(encoder.write(0x10000, &mut out).unwrap(), 5);
+        assert_eq!(out, [0xfe, 0x00, 0x00, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_encoder_policy() {
+        let encoder = Encoder::builder().max_value(MAX_SIZE).build();
+        assert!(encoder.encode(MAX_SIZE).is_ok());
+        assert_eq!(
+            encoder.encode_len(MAX_SIZE as usize + 1),
+            Err(VarIntError::TooLarge)
+        );
+        assert_eq!(
+            encoder.encode_all(&[1, MAX_SIZE + 1]),
+            Err(VarIntError::TooLarge)
+        );
+        let mut out = Vec::new();
+        let err = encoder.write(u64::MAX, &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "quic")]
+    fn test_encoder_codec() {
+        let encoder = Encoder::builder().codec(Codec::Quic).build();
+        assert_eq!(encoder.codec(), Codec::Quic);
+        assert_eq!(encoder.encode(300), Ok(vec![0x41, 0x2c]));
+        assert_eq!(encoder.encode(u64::MAX), Err(VarIntError::Overflow));
+    }
+
+    #[test]
+    fn test_varint_writer() {
+        /// Counts the writes reaching it.
+        #[derive(Debug)]
+        struct Writes(Vec<u8>, usize);
+
+        impl Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.extend_from_slice(buf);
+                self.1 += 1;
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let values: Vec<u64> = (0..1000).map(|i| i * 7).collect();
+        let mut writer = VarIntWriter::with_capacity(256, Writes(Vec::new(), 0));
+        writer.write_values(&values).unwrap();
+        assert_eq!(writer.written_values(), 1000);
+        assert!(writer.buffered().len() < 256);
+        let inner = writer.into_inner().unwrap();
+        assert_eq!(inner.0, crate::bulk::encode_all(&values));
+        assert!(inner.1 <= inner.0.len() / 256 + 1);
+
+        let encoder = Encoder::builder().max_value(MAX_SIZE).build();
+        let mut out = Vec::new();
+        {
+            let mut writer = VarIntWriter::with_encoder(encoder, 64, &mut out);
+            writer.write_value(5).unwrap();
+            let err = writer.write_value(u64::MAX).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert_eq!((writer.written_values(), writer.buffered()), (1, &[5][..]));
+            assert!(writer.get_ref().is_empty());
+        }
+        // Dropping the writer wrote the buffer.
+        assert_eq!(out, [5]);
+    }
+
+    #[test]
+    fn test_varint_writer_partial_writes() {
+        /// Takes at most 3 bytes per write, failing every other write and every flush.
+        #[derive(Debug)]
+        struct Flaky {
+            out: Vec<u8>,
+            fail: bool,
+        }
+
+        impl Write for Flaky {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.fail = !self.fail;
+                if self.fail {
+                    return Err(io::ErrorKind::BrokenPipe.into());
+                }
+                let n = buf.len().min(3);
+                self.out.extend_from_slice(&buf[..n]);
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::ErrorKind::Other.into())
+            }
+        }
+
+        let flaky = Flaky {
+            out: Vec::new(),
+            fail: true,
+        };
+        let mut writer = VarIntWriter::with_capacity(64, flaky);
+        writer.write_values(&[1, 515, 70_000]).unwrap();
+        let err = writer.flush().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(writer.get_ref().out, [0x01, 0xfd, 0x03]);
+        assert_eq!(writer.buffered(), [0x02, 0xfe, 0x70, 0x11, 0x01, 0x00]);
+
+        let err = writer.into_inner().unwrap_err();
+        assert_eq!(err.error().kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(err.buffered(), [0x11, 0x01, 0x00]);
+        let (_, mut flaky, buffered) = err.into_parts();
+        assert_eq!(flaky.out, [0x01, 0xfd, 0x03, 0x02, 0xfe, 0x70]);
+        assert_eq!(buffered, [0x11, 0x01, 0x00]);
+
+        // Once the buffer is written, the failing flush still gives back the writer.
+        flaky.out.clear();
+        let mut writer = VarIntWriter::with_capacity(64, flaky);
+        writer.write_value(7).unwrap();
+        let err = writer.into_inner().unwrap_err();
+        assert_eq!(err.error().kind(), io::ErrorKind::Other);
+        assert!(err.buffered().is_empty());
+        let (_, mut flaky, _) = err.into_parts();
+        assert_eq!(flaky.out, [7]);
+
+        // Dropping the writer writes what the flush left, once.
+        flaky.out.clear();
+        flaky.fail = true;
+        {
+            let mut writer = VarIntWriter::with_capacity(64, &mut flaky);
+            writer.write_values(&[1, 515, 70_000]).unwrap();
+            assert!(writer.flush().is_err());
+        }
+        assert_eq!(flaky.out, [0x01, 0xfd, 0x03, 0x02, 0xfe, 0x70]);
+
+        let mut writer = VarIntWriter::with_capacity(64, Vec::new());
+        writer.write_value(3).unwrap();
+        assert_eq!(writer.into_parts(), (vec![], vec![3]));
+    }
+}
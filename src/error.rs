@@ -0,0 +1,247 @@
+use std::{fmt, io};
+
+/// Errors returned by the decoders of this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VarIntError {
+    /// The input ended before the full encoding could be read.
+    Truncated,
+    /// The value was not encoded in its shortest form.
+    NonCanonical,
+    /// The bytes do not form a valid encoding.
+    Malformed,
+    /// The value is out of the range the encoding can represent.
+    Overflow,
+    /// The value exceeds the maximum allowed by the decoder configuration.
+    TooLarge,
+    /// Record types are not in strictly increasing order.
+    OutOfOrder,
+    /// A record of unknown even type was found, which the reader is required to understand.
+    UnknownEvenType(u64),
+}
+
+impl fmt::Display for VarIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarIntError::Truncated => f.write_str("unexpected end of input"),
+            VarIntError::NonCanonical => f.write_str("non-canonical encoding"),
+            VarIntError::Malformed => f.write_str("malformed encoding"),
+            VarIntError::Overflow => f.write_str("value out of range"),
+            VarIntError::TooLarge => f.write_str("value too large"),
+            VarIntError::OutOfOrder => f.write_str("record types out of order"),
+            VarIntError::UnknownEvenType(t) => write!(f, "unknown even type {}", t),
+        }
+    }
+}
+
+impl VarIntError {
+    /// The [`io::ErrorKind`] this error maps to when converted into an [`io::Error`]:
+    /// [`io::ErrorKind::UnexpectedEof`] for [`VarIntError::Truncated`], [`io::ErrorKind::InvalidData`] otherwise.
+    pub fn io_kind(&self) -> io::ErrorKind {
+        match self {
+            VarIntError::Truncated => io::ErrorKind::UnexpectedEof,
+            _ => io::ErrorKind::InvalidData,
+        }
+    }
+
+    /// A stable number for the variant, for callers that can't match on it such as those of the
+    /// [C API](crate::ffi): 1 for [`VarIntError::Truncated`] to 7 for [`VarIntError::UnknownEvenType`], in
+    /// declaration order. Codes are never reused, a new variant takes the next one.
+    pub fn code(&self) -> i32 {
+        match self {
+            VarIntError::Truncated => 1,
+            VarIntError::NonCanonical => 2,
+            VarIntError::Malformed => 3,
+            VarIntError::Overflow => 4,
+            VarIntError::TooLarge => 5,
+            VarIntError::OutOfOrder => 6,
+            VarIntError::UnknownEvenType(_) => 7,
+        }
+    }
+
+    /// Recovers the error an [`io::Error`] was converted from, if any.
+    pub fn from_io(error: &io::Error) -> Option<VarIntError> {
+        let inner = error.get_ref()?;
+        if let Some(e) = inner.downcast_ref::<VarIntError>() {
+            return Some(*e);
+        }
+        inner.downcast_ref::<DecodeError>().map(|e| e.error)
+    }
+}
+
+/// A leaf error: it has no [`source`](std::error::Error::source), and is itself the source of the
+/// [`DecodeError`] and [`io::Error`] wrapping it.
+impl std::error::Error for VarIntError {}
+
+/// Wraps the error with the kind given by [`VarIntError::io_kind`], so that it can be recovered
+/// with [`VarIntError::from_io`] or by downcasting [`io::Error::get_ref`].
+impl From<VarIntError> for io::Error {
+    fn from(error: VarIntError) -> Self {
+        io::Error::new(error.io_kind(), error)
+    }
+}
+
+/// A decoding error within a buffer of concatenated values, with where it happened.
+///
+/// Returned by the bulk decoders and [`VarCodec::decode_detailed`](crate::VarCodec::decode_detailed).
+/// The other single-value decoders such as [`VarInt::decode_full`](crate::VarInt::decode_full), the
+/// [`Codec`](crate::Codec) methods or [`IncrementalDecoder::feed`](crate::incremental::IncrementalDecoder::feed)
+/// return the bare [`VarIntError`], which stays `Copy` and allocation free: the input isn't at hand for
+/// the incremental decoder, and the other callers hold it to report it themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub error: VarIntError,
+    /// Byte offset of the failing value in the input.
+    pub offset: usize,
+    /// Index of the failing value, that is the number of values decoded before it.
+    pub index: usize,
+    /// The input from `offset`, up to [`DecodeError::SNIPPET_LEN`] bytes.
+    pub snippet: Vec<u8>,
+    /// Number of input bytes from `offset`.
+    pub available: usize,
+    /// For a truncated value, how many bytes its encoding takes, when its first bytes tell.
+    pub expected: Option<usize>,
+}
+
+impl DecodeError {
+    pub const SNIPPET_LEN: usize = 16;
+
+    pub(crate) fn new(error: VarIntError, input: &[u8], offset: usize, index: usize) -> Self {
+        let end = input.len().min(offset + Self::SNIPPET_LEN);
+        DecodeError {
+            error,
+            offset,
+            index,
+            snippet: input[offset..end].to_vec(),
+            available: input.len() - offset,
+            expected: None,
+        }
+    }
+
+    pub(crate) fn with_expected(mut self, expected: Option<usize>) -> Self {
+        if self.error == VarIntError::Truncated {
+            self.expected = expected;
+        }
+        self
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at byte {} (value {}): ",
+            self.error, self.offset, self.index
+        )?;
+        if let Some(expected) = self.expected {
+            write!(f, "needs {} bytes, ", expected)?;
+        }
+        write!(f, "{} available", self.available)?;
+        if !self.snippet.is_empty() {
+            f.write_str(", input there: ")?;
+            for byte in &self.snippet {
+                write!(f, "{:02x}", byte)?;
+            }
+            if self.available > self.snippet.len() {
+                f.write_str("...")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Wraps the error with the kind of the underlying [`VarIntError`], keeping the offset in the message.
+impl From<DecodeError> for io::Error {
+    fn from(error: DecodeError) -> Self {
+        io::Error::new(error.error.io_kind(), error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_io_error_round_trip() {
+        let error = io::Error::from(VarIntError::Truncated);
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(VarIntError::from_io(&error), Some(VarIntError::Truncated));
+        let error = io::Error::from(VarIntError::UnknownEvenType(4));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "unknown even type 4");
+        assert_eq!(
+            VarIntError::from_io(&error),
+            Some(VarIntError::UnknownEvenType(4))
+        );
+        assert_eq!(
+            VarIntError::from_io(&io::ErrorKind::InvalidData.into()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        // Part of the public API: these values must never change.
+        let codes = [
+            (VarIntError::Truncated, 1),
+            (VarIntError::NonCanonical, 2),
+            (VarIntError::Malformed, 3),
+            (VarIntError::Overflow, 4),
+            (VarIntError::TooLarge, 5),
+            (VarIntError::OutOfOrder, 6),
+            (VarIntError::UnknownEvenType(2), 7),
+        ];
+        for (error, code) in codes {
+            assert_eq!(error.code(), code, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_decode_error_display() {
+        let input = [0xff; 40];
+        let error = DecodeError::new(VarIntError::Truncated, &input, 36, 4).with_expected(Some(9));
+        assert_eq!(
+            error.to_string(),
+            "unexpected end of input at byte 36 (value 4): needs 9 bytes, 4 available, input there: ffffffff"
+        );
+        let error = DecodeError::new(VarIntError::Overflow, &input, 0, 0).with_expected(Some(9));
+        assert_eq!(error.expected, None);
+        assert!(error.to_string().ends_with(&format!(
+            "40 available, input there: {}...",
+            "ff".repeat(16)
+        )));
+        let error = DecodeError::new(VarIntError::Truncated, &input, 40, 5);
+        assert_eq!(
+            error.to_string(),
+            "unexpected end of input at byte 40 (value 5): 0 available"
+        );
+    }
+
+    #[test]
+    fn test_decode_error_source() {
+        let error = DecodeError::new(VarIntError::NonCanonical, &[0x00, 0xfd, 0x01, 0x00], 1, 1);
+        assert_eq!(error.snippet, vec![0xfd, 0x01, 0x00]);
+        assert_eq!(
+            error.to_string(),
+            "non-canonical encoding at byte 1 (value 1): 3 available, input there: fd0100"
+        );
+        let source = error.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<VarIntError>(),
+            Some(&VarIntError::NonCanonical)
+        );
+        let error = io::Error::from(error);
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            VarIntError::from_io(&error),
+            Some(VarIntError::NonCanonical)
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{Codec, VarIntError};
+
+/// What the first byte of an encoded value says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefix {
+    /// The value is stored in the prefix byte itself.
+    Direct,
+    /// The prefix is a marker announcing a payload of `payload_len` bytes.
+    Marker { payload_len: usize },
+    /// The encoding has no prefix (LEB128, VLQ), every byte carries part of the value.
+    Unprefixed,
+    /// The high bits of the first byte give the length, its other bits being part of the value (QUIC).
+    LengthBits { len: usize },
+}
+
+/// Breakdown of how a value is encoded, as returned by [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation<'a> {
+    pub codec: Codec,
+    pub prefix: Prefix,
+    /// Bytes holding the value: the payload after a marker, or all the bytes read otherwise.
+    pub payload: &'a [u8],
+    pub value: u64,
+    /// Number of bytes read.
+    pub len: usize,
+    /// Whether the value was encoded in its shortest form.
+    pub canonical: bool,
+    /// The shortest encoding of the value.
+    pub shortest: Vec<u8>,
+}
+
+/// Explains the compactSize at the start of `bytes`.
+pub fn explain(bytes: &[u8]) -> Result<Explanation<'_>, VarIntError> {
+    explain_with(Codec::CompactSize, bytes)
+}
+
+/// Explains the first value of `bytes` in the given encoding.
+pub fn explain_with(codec: Codec, bytes: &[u8]) -> Result<Explanation<'_>, VarIntError> {
+    let (value, len) = codec.decode(bytes)?;
+    let shortest = codec.encode(value)?;
+    let (prefix, payload) = match codec {
+        #[cfg(feature = "leb128")]
+        Codec::Leb128 => (Prefix::Unprefixed, &bytes[..len]),
+        #[cfg(feature = "vlq")]
+        Codec::Vlq => (Prefix::Unprefixed, &bytes[..len]),
+        #[cfg(feature = "quic")]
+        Codec::Quic => (Prefix::LengthBits { len }, &bytes[..len]),
+        _ if len == 1 => (Prefix::Direct, &bytes[..1]),
+        _ => (
+            Prefix::Marker {
+                payload_len: len - 1,
+            },
+            &bytes[1..len],
+        ),
+    };
+    Ok(Explanation {
+        codec,
+        prefix,
+        payload,
+        value,
+        len,
+        canonical: shortest.len() == len,
+        shortest,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_compact_size() {
+        let explanation = explain(&[0xfe, 0x03, 0x02, 0x00, 0x00, 0x2a]).unwrap();
+        assert_eq!(explanation.prefix, Prefix::Marker { payload_len: 4 });
+        assert_eq!(explanation.payload, &[0x03, 0x02, 0x00, 0x00]);
+        assert_eq!(explanation.value, 515);
+        assert_eq!(explanation.len, 5);
+        assert!(!explanation.canonical);
+        assert_eq!(explanation.shortest, vec![0xfd, 0x03, 0x02]);
+
+        let explanation = explain(&[0x2a]).unwrap();
+        assert_eq!(explanation.prefix, Prefix::Direct);
+        assert!(explanation.canonical);
+        assert_eq!(explain(&[0xfd, 0x03]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_explain_leb128() {
+        let explanation = explain_with(Codec::Leb128, &[0x83, 0x84, 0x00]).unwrap();
+        assert_eq!(explanation.prefix, Prefix::Unprefixed);
+        assert_eq!(explanation.payload, &[0x83, 0x84, 0x00]);
+        assert_eq!(explanation.value, 515);
+        assert_eq!(explanation.shortest, vec![0x83, 0x04]);
+        assert!(!explanation.canonical);
+    }
+}
@@ -0,0 +1,123 @@
+//! A C API over compactSize, for callers in other languages.
+//!
+//! Functions return [`VARINT_OK`] on success, else the [`VarIntError::code`] of the failure or
+//! [`VARINT_BUFFER_TOO_SMALL`]. The declarations are in `include/bitcoin_varint.h`, and a library to link
+//! against is built with:
+//!
+//! ```text
+//! cargo rustc --release --lib --features ffi --crate-type staticlib
+//! ```
+//!
+//! `include/BitcoinVarint.cs` binds the same functions for C# and Unity, loading the `cdylib` crate type.
+
+use crate::bulk::EncodedVarInt;
+use crate::{VarInt, VarIntError};
+
+pub const VARINT_OK: i32 = 0;
+/// The output buffer can't hold the encoding, which takes at most 9 bytes. Above every error code.
+pub const VARINT_BUFFER_TOO_SMALL: i32 = 64;
+
+/// Number of bytes of the encoding of `value`.
+#[no_mangle]
+pub extern "C" fn varint_encoded_len(value: u64) -> usize {
+    VarInt::encoded_len(value)
+}
+
+/// Writes the encoding of `value` to `out`, which holds `capacity` bytes, and its length to `written`.
+///
+/// # Safety
+///
+/// `out` must be valid for writes of `capacity` bytes and `written` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn varint_encode(
+    value: u64,
+    out: *mut u8,
+    capacity: usize,
+    written: *mut usize,
+) -> i32 {
+    let encoded = EncodedVarInt::new(value);
+    if encoded.len() > capacity {
+        return VARINT_BUFFER_TOO_SMALL;
+    }
+    // SAFETY: the caller guarantees `out` holds `capacity` bytes, at least the length of the encoding.
+    unsafe {
+        std::ptr::copy_nonoverlapping(encoded.as_ptr(), out, encoded.len());
+        *written = encoded.len();
+    }
+    VARINT_OK
+}
+
+/// Decodes the compactSize at the start of the `len` bytes of `bytes`, writing it to `value` and the number of
+/// bytes it takes to `read`.
+///
+/// Non-canonical encodings are rejected, as consensus does. Nothing is written on failure.
+///
+/// # Safety
+///
+/// `bytes` must be valid for reads of `len` bytes, or may be null when `len` is 0, and `value` and `read` must
+/// be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn varint_decode(
+    bytes: *const u8,
+    len: usize,
+    value: *mut u64,
+    read: *mut usize,
+) -> i32 {
+    let input = if len == 0 {
+        &[][..]
+    } else {
+        // SAFETY: the caller guarantees `bytes` is valid for reads of `len` bytes.
+        unsafe { std::slice::from_raw_parts(bytes, len) }
+    };
+    match VarInt::decode_full(input) {
+        Ok(decoded) if !decoded.canonical => VarIntError::NonCanonical.code(),
+        Ok(decoded) => {
+            // SAFETY: the caller guarantees `value` and `read` are valid for a write.
+            unsafe {
+                *value = decoded.value;
+                *read = decoded.len;
+            }
+            VARINT_OK
+        }
+        Err(e) => e.code(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_round_trip() {
+        let mut out = [0u8; 9];
+        let mut written = 0;
+        let code = unsafe { varint_encode(300, out.as_mut_ptr(), out.len(), &mut written) };
+        assert_eq!(
+            (code, &out[..written]),
+            (VARINT_OK, &[0xfd, 0x2c, 0x01][..])
+        );
+        assert_eq!(varint_encoded_len(300), 3);
+
+        let (mut value, mut read) = (0, 0);
+        let code = unsafe { varint_decode(out.as_ptr(), written, &mut value, &mut read) };
+        assert_eq!((code, value, read), (VARINT_OK, 300, 3));
+    }
+
+    #[test]
+    fn test_ffi_errors() {
+        let mut out = [0u8; 4];
+        let mut written = 0;
+        let code = unsafe { varint_encode(1 << 32, out.as_mut_ptr(), out.len(), &mut written) };
+        assert_eq!(code, VARINT_BUFFER_TOO_SMALL);
+
+        let (mut value, mut read) = (0, 0);
+        let code = unsafe { varint_decode(std::ptr::null(), 0, &mut value, &mut read) };
+        assert_eq!(code, VarIntError::Truncated.code());
+        let bytes = [0xfd, 0x01, 0x00];
+        let code = unsafe { varint_decode(bytes.as_ptr(), bytes.len(), &mut value, &mut read) };
+        assert_eq!(
+            (code, value, read),
+            (VarIntError::NonCanonical.code(), 0, 0)
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::VarIntError;
+
+/// The UTF-8-like coded numbers of FLAC frame headers
+///
+/// The frame or sample number is coded as a UTF-8 character would be, but extended to 7 bytes: a first byte
+/// `11111110` followed by 6 continuation bytes holds up to 36 bits. The number of leading ones of the first byte
+/// gives the length, bytes below `0x80` standing alone, and every continuation byte is `10xxxxxx`, so that a
+/// reader can resynchronize on the next first byte. Surrogates and values above `U+10FFFF` are valid here.
+///
+/// Decoding rejects overlong encodings, which no encoder produces.
+///
+/// https://www.rfc-editor.org/rfc/rfc9639.html#name-coded-number
+pub struct Flac;
+
+impl Flac {
+    /// Maximum number of bytes of an encoded number.
+    pub const MAX_LEN: usize = 7;
+    /// Largest encodable number, a sample number of 36 bits.
+    pub const MAX_VALUE: u64 = (1 << 36) - 1;
+
+    pub fn encode(value: u64) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::with_capacity(Self::MAX_LEN);
+        Self::encode_append(value, &mut result)?;
+        Ok(result)
+    }
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    pub fn encode_append(value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        let len = Self::get_size(value).ok_or(VarIntError::Overflow)?;
+        if len == 1 {
+            out.push(value as u8);
+            return Ok(1);
+        }
+        // `len` ones then a zero, followed by the high bits of the value.
+        let lead = !(0xffu8 >> len);
+        out.push(lead | (value >> (6 * (len - 1))) as u8);
+        for i in (0..len - 1).rev() {
+            out.push(0x80 | ((value >> (6 * i)) as u8 & 0x3f));
+        }
+        Ok(len)
+    }
+
+    /// Decodes a number, returning it with the number of bytes read.
+    ///
+    /// A continuation byte or `0xff` in first position, or a first byte followed by too few continuation bytes,
+    /// is [`VarIntError::Malformed`].
+    pub fn decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let len = match first.leading_ones() as usize {
+            0 => return Ok((first as u64, 1)),
+            1 | 8 => return Err(VarIntError::Malformed),
+            len => len,
+        };
+        let mut value = (first & (0x7f >> len)) as u64;
+        for i in 1..len {
+            let byte = *bytes.get(i).ok_or(VarIntError::Truncated)?;
+            if byte & 0xc0 != 0x80 {
+                return Err(VarIntError::Malformed);
+            }
+            value = value << 6 | (byte & 0x3f) as u64;
+        }
+        if Self::get_size(value) != Some(len) {
+            return Err(VarIntError::NonCanonical);
+        }
+        Ok((value, len))
+    }
+
+    /// Returns the bytes needed to encode this value, `None` above [`Flac::MAX_VALUE`].
+    pub fn get_size(value: u64) -> Option<usize> {
+        // 7 bits on one byte, then 5 more bits per byte: 11 on 2 bytes and so on up to 36 on 7.
+        let bits = 64 - value.leading_zeros() as usize;
+        match bits {
+            0..=7 => Some(1),
+            8..=36 => Some((bits - 1).div_ceil(5)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flac_encode() {
+        assert_eq!(Flac::encode(0x7f).unwrap(), vec![0x7f]);
+        // The same bytes as UTF-8 up to 31 bits.
+        for c in ['\u{80}', '\u{7ff}', '\u{800}', '\u{10ffff}'] {
+            let mut utf8 = [0u8; 4];
+            assert_eq!(
+                Flac::encode(c as u64).unwrap(),
+                c.encode_utf8(&mut utf8).as_bytes()
+            );
+        }
+        assert_eq!(
+            Flac::encode(Flac::MAX_VALUE).unwrap(),
+            vec![0xfe, 0xbf, 0xbf, 0xbf, 0xbf, 0xbf, 0xbf]
+        );
+        assert_eq!(
+            Flac::encode(1 << 31).unwrap(),
+            vec![0xfe, 0x82, 0x80, 0x80, 0x80, 0x80, 0x80]
+        );
+        assert_eq!(
+            Flac::encode(Flac::MAX_VALUE + 1),
+            Err(VarIntError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_flac_decode() {
+        for value in [0, 0x80, 0xffff, 0x7fff_ffff, 1 << 31, Flac::MAX_VALUE] {
+            let bytes = Flac::encode(value).unwrap();
+            assert_eq!(Flac::decode(&bytes), Ok((value, bytes.len())));
+        }
+        assert_eq!(Flac::decode(&[0x80]), Err(VarIntError::Malformed));
+        assert_eq!(Flac::decode(&[0xff]), Err(VarIntError::Malformed));
+        assert_eq!(Flac::decode(&[0xc2, 0x00]), Err(VarIntError::Malformed));
+        assert_eq!(Flac::decode(&[0xe0, 0x80]), Err(VarIntError::Truncated));
+        // 0x7f on two bytes.
+        assert_eq!(Flac::decode(&[0xc1, 0xbf]), Err(VarIntError::NonCanonical));
+    }
+}
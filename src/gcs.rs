@@ -0,0 +1,181 @@
+//! Golomb-coded sets as built by BIP158 compact block filters.
+//!
+//! Items are hashed with SipHash-2-4, keyed by the first 16 bytes of the block hash, and the hashes are
+//! mapped to `[0, n * m)` by a multiply-shift. The sorted values are then coded as gaps with
+//! [`rice_encode`](crate::golomb::rice_encode), after their count as a compactSize.
+//!
+//! https://github.com/bitcoin/bips/blob/master/bip-0158.mediawiki#construction
+
+use crate::golomb::{rice_decode, rice_encode};
+use crate::{VarInt, VarIntError};
+
+/// Parameters of the basic filter type of BIP158.
+pub const BASIC_P: u8 = 19;
+pub const BASIC_M: u64 = 784931;
+
+/// SipHash-2-4 of `data` keyed by `k0` and `k1`.
+///
+/// https://www.aumasson.jp/siphash/siphash.pdf
+pub fn siphash24(k0: u64, k1: u64, data: &[u8]) -> u64 {
+    let mut v = [
+        k0 ^ 0x736f6d6570736575,
+        k1 ^ 0x646f72616e646f6d,
+        k0 ^ 0x6c7967656e657261,
+        k1 ^ 0x7465646279746573,
+    ];
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let m = u64::from_le_bytes(chunk.try_into().unwrap());
+        v[3] ^= m;
+        sip_round(&mut v);
+        sip_round(&mut v);
+        v[0] ^= m;
+    }
+    let mut last = [0u8; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = data.len() as u8;
+    let m = u64::from_le_bytes(last);
+    v[3] ^= m;
+    sip_round(&mut v);
+    sip_round(&mut v);
+    v[0] ^= m;
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// Maps a 64 bits hash to `[0, f)` as `(hash * f) >> 64`, which avoids the bias and the cost of a modulo.
+pub fn hash_to_range(hash: u64, f: u64) -> u64 {
+    ((hash as u128 * f as u128) >> 64) as u64
+}
+
+/// SipHash key of a filter: the first 16 bytes of the block hash, in its internal byte order.
+pub fn block_key(block_hash: &[u8; 32]) -> (u64, u64) {
+    (
+        u64::from_le_bytes(block_hash[..8].try_into().unwrap()),
+        u64::from_le_bytes(block_hash[8..16].try_into().unwrap()),
+    )
+}
+
+/// Sorted hashes of `items` in `[0, items.len() * m)`.
+pub fn hashed_set<T: AsRef<[u8]>>(key: (u64, u64), items: &[T], m: u64) -> Vec<u64> {
+    let f = (items.len() as u64).saturating_mul(m);
+    let mut values: Vec<u64> = items
+        .iter()
+        .map(|item| hash_to_range(siphash24(key.0, key.1, item.as_ref()), f))
+        .collect();
+    values.sort_unstable();
+    values
+}
+
+/// Builds a serialized filter on the distinct items of `items`.
+pub fn build_filter<T: AsRef<[u8]>>(key: (u64, u64), items: &[T], p: u8, m: u64) -> Vec<u8> {
+    let mut items: Vec<&[u8]> = items.iter().map(AsRef::as_ref).collect();
+    items.sort_unstable();
+    items.dedup();
+    let values = hashed_set(key, &items, m);
+    let gaps: Vec<u64> = values
+        .iter()
+        .scan(0, |last, value| {
+            Some(value - std::mem::replace(last, *value))
+        })
+        .collect();
+    let mut filter = Vec::new();
+    VarInt::encode_append(items.len() as u64, &mut filter);
+    filter.extend_from_slice(&rice_encode(&gaps, p));
+    filter
+}
+
+/// Whether any of `items` may be in a serialized filter; false positives happen at a rate of `1 / m`.
+pub fn match_any<T: AsRef<[u8]>>(
+    filter: &[u8],
+    key: (u64, u64),
+    items: &[T],
+    p: u8,
+    m: u64,
+) -> Result<bool, VarIntError> {
+    let (n, len) = VarInt::decode_len(filter)?;
+    let gaps = rice_decode(&filter[len..], p, n)?;
+    let f = (n as u64).saturating_mul(m);
+    let mut queries: Vec<u64> = items
+        .iter()
+        .map(|item| hash_to_range(siphash24(key.0, key.1, item.as_ref()), f))
+        .collect();
+    queries.sort_unstable();
+    let mut value = 0u64;
+    let mut queries = queries.into_iter().peekable();
+    for gap in gaps {
+        value = value.checked_add(gap).ok_or(VarIntError::Overflow)?;
+        while queries.next_if(|query| *query < value).is_some() {}
+        if queries.peek() == Some(&value) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::hex;
+
+    #[test]
+    fn test_siphash24() {
+        // Reference vectors of the SipHash paper: key 00..0f, messages 00..(len - 1).
+        let (k0, k1) = (0x0706050403020100, 0x0f0e0d0c0b0a0908);
+        let message: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash24(k0, k1, &[]), 0x726fdb47dd0e0e31);
+        assert_eq!(siphash24(k0, k1, &message), 0xa129ca6149be45e5);
+        assert_eq!(siphash24(k0, k1, &message[..8]), 0x93f5f5799a932462);
+
+        assert_eq!(hash_to_range(u64::MAX, 10), 9);
+        assert_eq!(hash_to_range(1 << 63, 10), 5);
+        assert_eq!(hash_to_range(0, 10), 0);
+    }
+
+    #[test]
+    fn test_basic_filter() {
+        // BIP158 test vector for the testnet genesis block, whose only output script is its coinbase's.
+        let mut block_hash: [u8; 32] =
+            hex("000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943")
+                .try_into()
+                .unwrap();
+        block_hash.reverse();
+        let script = hex("4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac");
+        let key = block_key(&block_hash);
+        let filter = build_filter(key, &[&script], BASIC_P, BASIC_M);
+        assert_eq!(filter, hex("019dfca8"));
+        assert_eq!(
+            build_filter(key, &[&script, &script], BASIC_P, BASIC_M),
+            filter
+        );
+
+        assert_eq!(
+            match_any(&filter, key, &[&script[..]], BASIC_P, BASIC_M),
+            Ok(true)
+        );
+        assert_eq!(
+            match_any(&filter, key, &[b"other"], BASIC_P, BASIC_M),
+            Ok(false)
+        );
+        assert_eq!(
+            match_any(&[0x02, 0x9d], key, &[b"other"], BASIC_P, BASIC_M),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
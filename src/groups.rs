@@ -0,0 +1,220 @@
+use crate::VarIntError;
+
+/// Which group of a value comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupOrder {
+    /// Least significant group first, as in [`Leb128`](crate::Leb128), each group in little-endian order.
+    LittleEndian,
+    /// Most significant group first, as in [`Vlq`](crate::vlq::Vlq), each group in big-endian order.
+    BigEndian,
+}
+
+/// Continuation-bit encoding with groups of any width
+///
+/// LEB128 and VLQ generalized: values are cut into groups of `group_bits` bits, each stored in a unit of
+/// `group_bits + 1` bits whose top bit tells whether another unit follows. Units are whole bytes, so the
+/// width is one of 7, 15, 23, 31, 39, 47, 55 and 63 bits.
+///
+/// Padded encodings are accepted as [`Leb128`](crate::Leb128) and [`Vlq`](crate::vlq::Vlq) accept them,
+/// values that don't fit 64 bits are not.
+///
+/// ```
+/// use bitcoin_varint::groups::{GroupCodec, GroupOrder};
+///
+/// let codec = GroupCodec::new(15, GroupOrder::LittleEndian);
+/// assert_eq!(codec.encode(0x8000), vec![0x00, 0x80, 0x01, 0x00]);
+/// assert_eq!(codec.decode(&[0x00, 0x80, 0x01, 0x00]), Ok((0x8000, 4)));
+/// ```
+///
+/// https://en.wikipedia.org/wiki/Variable-length_quantity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupCodec {
+    group_bits: u32,
+    order: GroupOrder,
+}
+
+impl GroupCodec {
+    /// LEB128, whose encodings are the same as [`Leb128`](crate::Leb128).
+    pub const LEB128: GroupCodec = GroupCodec::new(7, GroupOrder::LittleEndian);
+    /// VLQ, whose encodings are the same as [`Vlq`](crate::vlq::Vlq).
+    pub const VLQ: GroupCodec = GroupCodec::new(7, GroupOrder::BigEndian);
+
+    /// # Panics
+    ///
+    /// When `group_bits + 1` isn't a multiple of 8 between 8 and 64.
+    pub const fn new(group_bits: u32, order: GroupOrder) -> Self {
+        assert!(
+            group_bits < 64 && (group_bits + 1).is_multiple_of(8),
+            "groups must have 7, 15, 23, 31, 39, 47, 55 or 63 bits"
+        );
+        GroupCodec { group_bits, order }
+    }
+
+    pub fn group_bits(&self) -> u32 {
+        self.group_bits
+    }
+
+    pub fn order(&self) -> GroupOrder {
+        self.order
+    }
+
+    /// Maximum number of bytes of an encoded u64.
+    pub fn max_len(&self) -> usize {
+        self.max_groups() * self.unit_len()
+    }
+
+    pub fn encode(&self, value: u64) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.max_len());
+        self.encode_append(value, &mut result);
+        result
+    }
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    pub fn encode_append(&self, value: u64, out: &mut Vec<u8>) -> usize {
+        let groups = self.groups(value);
+        let mask = (1u64 << self.group_bits) - 1;
+        let unit_len = self.unit_len();
+        for i in 0..groups {
+            let (group, more) = match self.order {
+                GroupOrder::LittleEndian => (i, i + 1 < groups),
+                GroupOrder::BigEndian => (groups - 1 - i, i + 1 < groups),
+            };
+            let mut unit = (value >> (self.group_bits as usize * group)) & mask;
+            if more {
+                unit |= 1 << self.group_bits;
+            }
+            match self.order {
+                GroupOrder::LittleEndian => out.extend_from_slice(&unit.to_le_bytes()[..unit_len]),
+                GroupOrder::BigEndian => out.extend_from_slice(&unit.to_be_bytes()[8 - unit_len..]),
+            }
+        }
+        groups * unit_len
+    }
+
+    /// Decodes a value, returning it with the number of bytes read.
+    pub fn decode(&self, bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let unit_len = self.unit_len();
+        let mask = (1u64 << self.group_bits) - 1;
+        let mut value = 0u64;
+        for (i, chunk) in bytes.chunks(unit_len).enumerate() {
+            if chunk.len() < unit_len {
+                break;
+            }
+            let mut unit = [0u8; 8];
+            let unit = match self.order {
+                GroupOrder::LittleEndian => {
+                    unit[..unit_len].copy_from_slice(chunk);
+                    u64::from_le_bytes(unit)
+                }
+                GroupOrder::BigEndian => {
+                    unit[8 - unit_len..].copy_from_slice(chunk);
+                    u64::from_be_bytes(unit)
+                }
+            };
+            let group = unit & mask;
+            match self.order {
+                GroupOrder::LittleEndian => {
+                    let shift = self.group_bits as usize * i;
+                    if shift >= 64 || (group << shift) >> shift != group {
+                        return Err(VarIntError::Overflow);
+                    }
+                    value |= group << shift;
+                }
+                GroupOrder::BigEndian => {
+                    if value >> (64 - self.group_bits) != 0 {
+                        return Err(VarIntError::Overflow);
+                    }
+                    value = value << self.group_bits | group;
+                }
+            }
+            if unit >> self.group_bits == 0 {
+                return Ok((value, (i + 1) * unit_len));
+            }
+        }
+        Err(VarIntError::Truncated)
+    }
+
+    /// Returns the bytes needed to encode this value
+    pub fn get_size(&self, value: u64) -> usize {
+        self.groups(value) * self.unit_len()
+    }
+
+    fn groups(&self, value: u64) -> usize {
+        (64 - (value | 1).leading_zeros() as usize).div_ceil(self.group_bits as usize)
+    }
+
+    fn max_groups(&self) -> usize {
+        64usize.div_ceil(self.group_bits as usize)
+    }
+
+    fn unit_len(&self) -> usize {
+        (self.group_bits as usize + 1) / 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(feature = "leb128", feature = "vlq"))]
+    fn test_group_codec_7_bits() {
+        use crate::vlq::Vlq;
+        use crate::Leb128;
+
+        for value in [0, 0x7f, 0x80, 0x3fff, 0x4000, 1 << 63, u64::MAX] {
+            let bytes = Leb128::encode(value);
+            assert_eq!(GroupCodec::LEB128.encode(value), bytes);
+            assert_eq!(GroupCodec::LEB128.decode(&bytes), Ok((value, bytes.len())));
+            let bytes = Vlq::encode(value);
+            assert_eq!(GroupCodec::VLQ.encode(value), bytes);
+            assert_eq!(GroupCodec::VLQ.decode(&bytes), Ok((value, bytes.len())));
+        }
+        assert_eq!(GroupCodec::LEB128.max_len(), 10);
+    }
+
+    #[test]
+    fn test_group_codec_wide() {
+        let le = GroupCodec::new(15, GroupOrder::LittleEndian);
+        let be = GroupCodec::new(15, GroupOrder::BigEndian);
+        assert_eq!(le.encode(0x7fff), [0xff, 0x7f]);
+        assert_eq!(be.encode(0x8000), [0x80, 0x01, 0x00, 0x00]);
+        assert_eq!(be.decode(&[0x80, 0x01, 0x00, 0x00]), Ok((0x8000, 4)));
+        assert_eq!(le.max_len(), 10);
+        assert_eq!(le.get_size(u64::MAX), 10);
+
+        for bits in [7, 15, 23, 31, 39, 47, 55, 63] {
+            for order in [GroupOrder::LittleEndian, GroupOrder::BigEndian] {
+                let codec = GroupCodec::new(bits, order);
+                for value in [0, 1, (1 << bits) - 1, 1 << bits, u64::MAX] {
+                    let bytes = codec.encode(value);
+                    assert_eq!(bytes.len(), codec.get_size(value));
+                    assert_eq!(codec.decode(&bytes), Ok((value, bytes.len())), "{}", bits);
+                    assert_eq!(
+                        codec.decode(&bytes[..bytes.len() - 1]),
+                        Err(VarIntError::Truncated)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_group_codec_overflow() {
+        let le = GroupCodec::new(31, GroupOrder::LittleEndian);
+        // A third group holds the top 2 bits only.
+        let bytes = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x04, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(le.decode(&bytes), Err(VarIntError::Overflow));
+        let be = GroupCodec::new(63, GroupOrder::BigEndian);
+        let mut bytes = vec![0x80, 0, 0, 0, 0, 0, 0, 0x02];
+        bytes.extend_from_slice(&[0; 8]);
+        assert_eq!(be.decode(&bytes), Err(VarIntError::Overflow));
+        // Padding is accepted.
+        assert_eq!(
+            be.decode(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5]),
+            Ok((5, 16))
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! HTTP/3 frame headers: the frame type then the payload length, both QUIC varints.
+//!
+//! ```
+//! use bitcoin_varint::h3::{FrameHeader, FrameReader, HEADERS};
+//!
+//! let mut stream = Vec::new();
+//! FrameHeader::new(HEADERS, 3).encode_append(&mut stream).unwrap();
+//! stream.extend_from_slice(&[0x00, 0x00, 0xd1]);
+//! let frames: Vec<_> = FrameReader::new(stream.as_slice()).collect::<Result<_, _>>().unwrap();
+//! assert_eq!((frames[0].offset, frames[0].header.frame_type), (0, HEADERS));
+//! ```
+//!
+//! https://www.rfc-editor.org/rfc/rfc9114.html#name-frame-layout
+
+use crate::quic::Quic;
+use crate::VarIntError;
+use std::io::{self, Read};
+
+pub const DATA: u64 = 0x00;
+pub const HEADERS: u64 = 0x01;
+pub const CANCEL_PUSH: u64 = 0x03;
+pub const SETTINGS: u64 = 0x04;
+pub const PUSH_PROMISE: u64 = 0x05;
+pub const GOAWAY: u64 = 0x07;
+pub const MAX_PUSH_ID: u64 = 0x0d;
+
+/// Type and payload length of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub frame_type: u64,
+    pub length: u64,
+}
+
+impl FrameHeader {
+    pub fn new(frame_type: u64, length: u64) -> Self {
+        Self { frame_type, length }
+    }
+
+    /// Encodes the header, failing with [`VarIntError::Overflow`] when a field is above [`Quic::MAX_VALUE`].
+    pub fn encode(&self) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::with_capacity(16);
+        self.encode_append(&mut result)?;
+        Ok(result)
+    }
+
+    /// Appends the header to `out`, returning the number of bytes written.
+    ///
+    /// Nothing is written when a field can't be encoded.
+    pub fn encode_append(&self, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        Quic::get_size(self.length)?;
+        let type_len = Quic::encode_append(self.frame_type, out)?;
+        Ok(type_len + Quic::encode_append(self.length, out)?)
+    }
+
+    /// Decodes a header, returning it with the number of bytes read.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, usize), VarIntError> {
+        let (frame_type, type_len) = Quic::decode(bytes)?;
+        let (length, length_len) = Quic::decode(&bytes[type_len..])?;
+        Ok((Self::new(frame_type, length), type_len + length_len))
+    }
+
+    /// Whether the type is reserved for greasing, `0x1f * N + 0x21`, which receivers must ignore.
+    pub fn is_reserved(&self) -> bool {
+        self.frame_type >= 0x21 && (self.frame_type - 0x21).is_multiple_of(0x1f)
+    }
+}
+
+/// Where a frame sits in a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameBoundary {
+    /// Offset of the start of the header from where the reader started.
+    pub offset: u64,
+    pub header: FrameHeader,
+    /// Length of the header, the payload follows it.
+    pub header_len: usize,
+}
+
+/// Iterator over the frames of a stream, skipping their payloads.
+///
+/// An end of input within a frame is an [`io::ErrorKind::UnexpectedEof`] error, after which the iteration stops.
+pub struct FrameReader<R> {
+    reader: R,
+    offset: u64,
+    done: bool,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Offset of the end of the last frame returned.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next header and skips its payload, or returns `None` at the end of the stream.
+    pub fn next_frame(&mut self) -> io::Result<Option<FrameBoundary>> {
+        let mut bytes = [0u8; 16];
+        loop {
+            match self.reader.read(&mut bytes[..1]) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        let type_len = 1 << (bytes[0] >> 6);
+        self.reader.read_exact(&mut bytes[1..type_len + 1])?;
+        let length_len = 1 << (bytes[type_len] >> 6);
+        let header_len = type_len + length_len;
+        self.reader
+            .read_exact(&mut bytes[type_len + 1..header_len])?;
+        let (header, _) = FrameHeader::decode(&bytes[..header_len])?;
+
+        let skipped = io::copy(&mut (&mut self.reader).take(header.length), &mut io::sink())?;
+        if skipped < header.length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let boundary = FrameBoundary {
+            offset: self.offset,
+            header,
+            header_len,
+        };
+        self.offset += header_len as u64 + header.length;
+        Ok(Some(boundary))
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = io::Result<FrameBoundary>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_frame().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_header() {
+        let header = FrameHeader::new(SETTINGS, 300);
+        let bytes = header.encode().unwrap();
+        assert_eq!(bytes, vec![0x04, 0x41, 0x2c]);
+        assert_eq!(FrameHeader::decode(&bytes), Ok((header, 3)));
+        assert_eq!(
+            FrameHeader::decode(&bytes[..2]),
+            Err(VarIntError::Truncated)
+        );
+
+        let mut out = Vec::new();
+        let too_long = FrameHeader::new(DATA, u64::MAX);
+        assert_eq!(too_long.encode_append(&mut out), Err(VarIntError::Overflow));
+        assert!(out.is_empty());
+        assert!(FrameHeader::new(0x21 + 0x1f * 3, 0).is_reserved());
+        assert!(!FrameHeader::new(GOAWAY, 0).is_reserved());
+    }
+
+    #[test]
+    fn test_frame_reader() {
+        let mut stream = Vec::new();
+        FrameHeader::new(SETTINGS, 2)
+            .encode_append(&mut stream)
+            .unwrap();
+        stream.extend_from_slice(&[0x06, 0x00]);
+        FrameHeader::new(0x21, 0)
+            .encode_append(&mut stream)
+            .unwrap();
+        FrameHeader::new(DATA, 100)
+            .encode_append(&mut stream)
+            .unwrap();
+        stream.extend_from_slice(&[0xaa; 100]);
+
+        let frames: Vec<_> = FrameReader::new(stream.as_slice())
+            .map(|frame| frame.map(|f| (f.offset, f.header.frame_type, f.header_len)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(frames, vec![(0, SETTINGS, 2), (4, 0x21, 2), (6, DATA, 3)]);
+
+        // A payload cut short fails once, then the iteration stops.
+        let mut reader = FrameReader::new(&stream[..stream.len() - 1]);
+        assert_eq!(reader.by_ref().filter(Result::is_ok).count(), 2);
+        let mut reader = FrameReader::new(&stream[..stream.len() - 1]);
+        let last = reader.by_ref().last().unwrap().unwrap_err();
+        assert_eq!(last.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+        // As does a header cut short.
+        let err = FrameReader::new(&[0x40][..]).next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::VarIntError;
+
+/// Hadoop `WritableUtils` VInt and VLong
+///
+/// Values from -112 to 127 take a single byte. Other values start with a byte telling both the sign
+/// and the number of bytes that follow (-113 to -120 for 1 to 8 bytes of a non-negative value,
+/// -121 to -128 for a negative one), followed by the big-endian value, complemented when negative.
+///
+/// https://hadoop.apache.org/docs/stable/api/org/apache/hadoop/io/WritableUtils.html#writeVLong-java.io.DataOutput-long-
+pub struct Hadoop;
+
+impl Hadoop {
+    pub const MAX_LEN: usize = 9;
+
+    pub fn encode_vlong(value: i64) -> Vec<u8> {
+        if (-112..=127).contains(&value) {
+            return vec![value as u8];
+        }
+        let (magnitude, base) = if value < 0 {
+            (!value as u64, -120)
+        } else {
+            (value as u64, -112)
+        };
+        let len = 8 - magnitude.leading_zeros() as usize / 8;
+        let mut result = Vec::with_capacity(1 + len);
+        result.push((base - len as i8) as u8);
+        result.extend_from_slice(&magnitude.to_be_bytes()[8 - len..]);
+        result
+    }
+
+    /// Decodes a VLong, returning it with the number of bytes read.
+    ///
+    /// Like Hadoop, values are not required to use their shortest form.
+    pub fn decode_vlong(bytes: &[u8]) -> Result<(i64, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)? as i8;
+        if first >= -112 {
+            return Ok((first as i64, 1));
+        }
+        let (negative, len) = if first < -120 {
+            (true, (-120 - first) as usize)
+        } else {
+            (false, (-112 - first) as usize)
+        };
+        let payload = bytes.get(1..1 + len).ok_or(VarIntError::Truncated)?;
+        let magnitude = payload.iter().fold(0u64, |acc, b| acc << 8 | *b as u64);
+        let value = if negative { !magnitude } else { magnitude };
+        Ok((value as i64, 1 + len))
+    }
+
+    pub fn encode_vint(value: i32) -> Vec<u8> {
+        Self::encode_vlong(value as i64)
+    }
+
+    /// Decodes a VInt, failing with [`VarIntError::Overflow`] when the value does not fit an `i32`.
+    pub fn decode_vint(bytes: &[u8]) -> Result<(i32, usize), VarIntError> {
+        let (value, len) = Self::decode_vlong(bytes)?;
+        let value = i32::try_from(value).map_err(|_| VarIntError::Overflow)?;
+        Ok((value, len))
+    }
+
+    /// Returns the bytes needed to encode this value
+    pub fn get_size(value: i64) -> usize {
+        if (-112..=127).contains(&value) {
+            return 1;
+        }
+        let magnitude = if value < 0 { !value } else { value } as u64;
+        9 - magnitude.leading_zeros() as usize / 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hadoop_vlong() {
+        let vectors: [(i64, &[u8]); 9] = [
+            (0, &[0x00]),
+            (127, &[0x7f]),
+            (-112, &[0x90]),
+            (128, &[0x8f, 0x80]),
+            (-113, &[0x87, 0x70]),
+            (256, &[0x8e, 0x01, 0x00]),
+            (-1000, &[0x86, 0x03, 0xe7]),
+            (
+                i64::MAX,
+                &[0x88, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+            (
+                i64::MIN,
+                &[0x80, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+        ];
+        for (value, bytes) in vectors {
+            assert_eq!(Hadoop::encode_vlong(value), bytes);
+            assert_eq!(Hadoop::get_size(value), bytes.len());
+            assert_eq!(Hadoop::decode_vlong(bytes), Ok((value, bytes.len())));
+        }
+        assert_eq!(
+            Hadoop::decode_vlong(&[0x8e, 0x01]),
+            Err(VarIntError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_hadoop_vint() {
+        assert_eq!(
+            Hadoop::encode_vint(i32::MIN),
+            vec![0x84, 0x7f, 0xff, 0xff, 0xff]
+        );
+        assert_eq!(
+            Hadoop::decode_vint(&[0x84, 0x7f, 0xff, 0xff, 0xff]),
+            Ok((i32::MIN, 5))
+        );
+        assert_eq!(
+            Hadoop::decode_vint(&[0x8b, 0x01, 0x00, 0x00, 0x00, 0x00]),
+            Err(VarIntError::Overflow)
+        );
+    }
+}
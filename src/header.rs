@@ -0,0 +1,243 @@
+//! A small header at the start of files of encoded values, telling which encoding they hold and with which
+//! parameters.
+//!
+//! The header is [`HEADER_MAGIC`], the version of the header format, then as a compactSize the length of the
+//! rest: the id of the encoding, the number of parameters and every parameter as a key and a value, all
+//! compactSizes. Readers skip what follows the parameters up to that length, so headers of later versions,
+//! with more fields at the end, stay readable.
+//!
+//! ```
+//! use bitcoin_varint::header::{read_header, write_header, Header, PARAM_BLOCK_SIZE};
+//! use bitcoin_varint::Codec;
+//!
+//! let header = Header::for_codec(Codec::CompactSize).with_param(PARAM_BLOCK_SIZE, 4096);
+//! let mut file = Vec::new();
+//! write_header(&header, &mut file).unwrap();
+//!
+//! let read = read_header(&mut &file[..]).unwrap();
+//! assert_eq!(read.codec(), Some(Codec::CompactSize));
+//! assert_eq!(read.param(PARAM_BLOCK_SIZE), Some(4096));
+//! ```
+
+use crate::delimited::read_prefix;
+use crate::{Codec, VarInt, VarIntError};
+use std::io::{self, Read, Write};
+
+/// First bytes of a header.
+pub const HEADER_MAGIC: [u8; 4] = *b"VINT";
+
+/// Version of the header format written by [`write_header`].
+pub const HEADER_VERSION: u8 = 1;
+
+/// Longest header [`read_header`] accepts after the magic and version.
+pub const MAX_HEADER_LEN: usize = 4096;
+
+/// Id of Golomb-Rice coded values, see [`golomb`](crate::golomb), with their parameter in [`PARAM_RICE_P`].
+pub const ID_GOLOMB_RICE: u8 = 0x10;
+/// Id of the chunked compactSize container of [`chunked`](crate::chunked).
+pub const ID_CHUNKED: u8 = 0x11;
+
+/// The Rice parameter P of Golomb-Rice coded values.
+pub const PARAM_RICE_P: u64 = 1;
+/// The number of values per block or chunk.
+pub const PARAM_BLOCK_SIZE: u64 = 2;
+
+/// The encoding of a file and its parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// Version of the header format, [`HEADER_VERSION`] for the headers of this crate.
+    pub version: u8,
+    /// [`Codec::id`] for plain values, or one of the `ID_` constants.
+    pub id: u8,
+    /// Parameters by key, in the order they are written.
+    pub params: Vec<(u64, u64)>,
+}
+
+impl Header {
+    /// A header for values encoded as `id`, without parameters.
+    pub fn new(id: u8) -> Self {
+        Header {
+            version: HEADER_VERSION,
+            id,
+            params: Vec::new(),
+        }
+    }
+
+    pub fn for_codec(codec: Codec) -> Self {
+        Self::new(codec.id())
+    }
+
+    /// Adds a parameter, replacing the value of `key` when it is already set.
+    pub fn with_param(mut self, key: u64, value: u64) -> Self {
+        match self.params.iter_mut().find(|(k, _)| *k == key) {
+            Some(param) => param.1 = value,
+            None => self.params.push((key, value)),
+        }
+        self
+    }
+
+    pub fn param(&self, key: u64) -> Option<u64> {
+        self.params.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    }
+
+    /// The codec of the values, when [`Header::id`] is one of an enabled [`Codec`].
+    pub fn codec(&self) -> Option<Codec> {
+        Codec::from_id(self.id)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = vec![self.id];
+        VarInt::encode_append(self.params.len() as u64, &mut body);
+        for (key, value) in &self.params {
+            VarInt::encode_append(*key, &mut body);
+            VarInt::encode_append(*value, &mut body);
+        }
+        let mut result = HEADER_MAGIC.to_vec();
+        result.push(self.version);
+        VarInt::encode_append(body.len() as u64, &mut result);
+        result.extend_from_slice(&body);
+        result
+    }
+
+    /// Decodes the header at the start of `bytes`, returning it with its length.
+    ///
+    /// Bytes that don't start with [`HEADER_MAGIC`], a version 0, or parameters overrunning the announced
+    /// length fail with [`VarIntError::Malformed`].
+    pub fn decode(bytes: &[u8]) -> Result<(Header, usize), VarIntError> {
+        let start = bytes.get(..5).ok_or(VarIntError::Truncated)?;
+        if start[..4] != HEADER_MAGIC || start[4] == 0 {
+            return Err(VarIntError::Malformed);
+        }
+        let (len, len_len) = read_varint(&bytes[5..])?;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| (5 + len_len).checked_add(len))
+            .filter(|end| *end <= bytes.len())
+            .ok_or(VarIntError::Truncated)?;
+        let mut header = Self::decode_body(&bytes[5 + len_len..end])?;
+        header.version = start[4];
+        Ok((header, end))
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Header, VarIntError> {
+        let malformed = |e: VarIntError| match e {
+            VarIntError::Truncated => VarIntError::Malformed,
+            e => e,
+        };
+        let (&id, mut rest) = body.split_first().ok_or(VarIntError::Malformed)?;
+        let (count, len) = read_varint(rest).map_err(malformed)?;
+        rest = &rest[len..];
+        let mut params = Vec::new();
+        for _ in 0..count {
+            let (key, len) = read_varint(rest).map_err(malformed)?;
+            rest = &rest[len..];
+            let (value, len) = read_varint(rest).map_err(malformed)?;
+            rest = &rest[len..];
+            params.push((key, value));
+        }
+        Ok(Header {
+            version: HEADER_VERSION,
+            id,
+            params,
+        })
+    }
+}
+
+fn read_varint(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+    VarInt::decode_full(bytes).map(|decoded| (decoded.value, decoded.len))
+}
+
+/// Writes `header` to `writer`.
+pub fn write_header<W: Write>(header: &Header, writer: &mut W) -> io::Result<()> {
+    writer.write_all(&header.encode())
+}
+
+/// Reads a header from `reader`, consuming nothing past it.
+///
+/// Headers longer than [`MAX_HEADER_LEN`] fail with an [`io::ErrorKind::InvalidData`] error wrapping
+/// [`VarIntError::TooLarge`], before anything is allocated, and the other errors of [`Header::decode`] are
+/// [`io::ErrorKind::InvalidData`] errors as well.
+pub fn read_header<R: Read>(reader: &mut R) -> io::Result<Header> {
+    let mut start = [0u8; 5];
+    reader.read_exact(&mut start)?;
+    if start[..4] != HEADER_MAGIC || start[4] == 0 {
+        return Err(VarIntError::Malformed.into());
+    }
+    let (len, _) = read_prefix(&VarInt, reader)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+    if len > MAX_HEADER_LEN as u64 {
+        return Err(VarIntError::TooLarge.into());
+    }
+    let mut body = vec![0u8; len as usize];
+    reader.read_exact(&mut body)?;
+    let mut header = Header::decode_body(&body)?;
+    header.version = start[4];
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = Header::new(ID_GOLOMB_RICE)
+            .with_param(PARAM_RICE_P, 19)
+            .with_param(PARAM_RICE_P, 20)
+            .with_param(1000, 1 << 40);
+        let bytes = header.encode();
+        assert_eq!(
+            bytes[..9],
+            [b'V', b'I', b'N', b'T', 1, 0x10, 0x10, 0x02, 0x01]
+        );
+        assert_eq!(Header::decode(&bytes), Ok((header.clone(), bytes.len())));
+        let read = read_header(&mut &bytes[..]).unwrap();
+        assert_eq!(read, header);
+        assert_eq!(read.param(PARAM_RICE_P), Some(20));
+        assert_eq!(read.param(PARAM_BLOCK_SIZE), None);
+        assert_eq!(read.codec(), None);
+
+        for codec in Codec::ALL {
+            assert_eq!(Header::for_codec(*codec).codec(), Some(*codec));
+        }
+    }
+
+    #[test]
+    fn test_header_later_version() {
+        // A version 2 header with a field after the parameters, and the data after the header.
+        let bytes = [
+            b'V', b'I', b'N', b'T', 2, 0x05, 0x03, 0x01, 0x02, 0x80, 0xaa, 0x2a,
+        ];
+        let (header, len) = Header::decode(&bytes).unwrap();
+        assert_eq!((header.version, header.id, len), (2, 3, 11));
+        assert_eq!(header.params, [(PARAM_BLOCK_SIZE, 0x80)]);
+        let mut reader = &bytes[..];
+        assert_eq!(read_header(&mut reader).unwrap(), header);
+        assert_eq!(reader, [0x2a]);
+    }
+
+    #[test]
+    fn test_header_errors() {
+        assert_eq!(Header::decode(b"VINT"), Err(VarIntError::Truncated));
+        assert_eq!(
+            Header::decode(b"VCOL\x01\x01\x01"),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            Header::decode(b"VINT\x00\x01\x01"),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            Header::decode(b"VINT\x01\x03\x01\x05"),
+            Err(VarIntError::Truncated)
+        );
+        // Two parameters announced in a body holding one.
+        assert_eq!(
+            Header::decode(b"VINT\x01\x04\x01\x02\x01\x02"),
+            Err(VarIntError::Malformed)
+        );
+        let err = read_header(&mut &b"VINT\x01\xfd\x01\x10"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_header(&mut &b"VINT\x01\x03\x01"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
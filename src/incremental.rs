@@ -0,0 +1,231 @@
+//! Incremental compactSize decoding, for readers that hand out bytes as they arrive.
+//!
+//! [`IncrementalDecoder`] keeps the bytes of a partially received value itself, rather than in the
+//! stack of a future, which is what makes an async read built on it cancel-safe: with an
+//! `AsyncBufRead`, each poll peeks at the buffer with `poll_fill_buf`, feeds it to the decoder and
+//! `consume`s exactly what the decoder took, all without an await in between. Dropping the read
+//! future at any await point therefore loses nothing: the consumed bytes are in the decoder, which
+//! outlives the future, and the next read resumes where the previous one stopped.
+//!
+//! ```ignore
+//! async fn read_varint<R: AsyncBufRead + Unpin>(
+//!     reader: &mut R,
+//!     decoder: &mut IncrementalDecoder,
+//! ) -> io::Result<u64> {
+//!     loop {
+//!         let available = reader.fill_buf().await?;
+//!         if available.is_empty() {
+//!             return Err(io::ErrorKind::UnexpectedEof.into());
+//!         }
+//!         let (consumed, value) = decoder.feed(available)?;
+//!         reader.consume(consumed);
+//!         if let Some(value) = value {
+//!             return Ok(value);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Completion-based runtimes such as tokio-uring and glommio take the buffer of a read and hand it back with
+//! the result, which suits the decoder as well: it only borrows the buffer between two reads, and what a read
+//! brought past the value stays in the buffer for the caller.
+//!
+//! ```ignore
+//! async fn read_varint(file: &File, pos: &mut u64, mut buf: Vec<u8>) -> (io::Result<u64>, Vec<u8>) {
+//!     let mut decoder = IncrementalDecoder::new();
+//!     loop {
+//!         buf.clear();
+//!         let (read, returned) = file.read_at(buf, *pos).await;
+//!         buf = returned;
+//!         match read {
+//!             Ok(0) => return (Err(io::ErrorKind::UnexpectedEof.into()), buf),
+//!             Ok(_) => {}
+//!             Err(e) => return (Err(e), buf),
+//!         }
+//!         match decoder.feed(&buf) {
+//!             Ok((consumed, value)) => {
+//!                 *pos += consumed as u64;
+//!                 if let Some(value) = value {
+//!                     return (Ok(value), buf);
+//!                 }
+//!             }
+//!             Err(e) => return (Err(e.into()), buf),
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Encoding needs no reader at all: [`VarInt::encode`] and [`crate::encoder::Encoder::encode_all`] return owned
+//! buffers, ready to be passed to the runtime's write.
+
+use crate::{VarInt, VarIntError};
+
+/// A compactSize decoder fed with the bytes of a stream in pieces of any size.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncrementalDecoder {
+    /// Bytes of the value received so far.
+    bytes: [u8; 9],
+    filled: u8,
+}
+
+impl IncrementalDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes bytes from the front of `input`, returning how many were taken along with the value once complete.
+    ///
+    /// Never takes bytes past the end of the value, so the rest of `input` is left to the caller.
+    /// After a value or an error, the decoder is ready for the next value.
+    pub fn feed(&mut self, input: &[u8]) -> Result<(usize, Option<u64>), VarIntError> {
+        let filled = self.filled as usize;
+        let Some(&first) = self.bytes[..filled.min(1)].first().or(input.first()) else {
+            return Ok((0, None));
+        };
+        let len = match first {
+            0xfd => 3,
+            0xfe => 5,
+            0xff => 9,
+            _ => 1,
+        };
+        let taken = input.len().min(len - filled.min(len));
+        // Written with checked accesses so that no panic path remains, see tests/no_panic.rs.
+        if let (Some(pending), Some(new)) = (
+            self.bytes.get_mut(filled..filled + taken),
+            input.get(..taken),
+        ) {
+            pending.copy_from_slice(new);
+        }
+        self.filled += taken as u8;
+        if (self.filled as usize) < len {
+            return Ok((taken, None));
+        }
+        self.filled = 0;
+        VarInt::decode_full(&self.bytes[..len]).map(|decoded| (taken, Some(decoded.value)))
+    }
+
+    /// Whether no byte of a value is pending.
+    pub fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+
+    /// Bytes of the value received so far.
+    pub fn pending(&self) -> &[u8] {
+        &self.bytes[..self.filled as usize]
+    }
+
+    /// Serializes the state of the decoder, to checkpoint a stream along with the offset of the input fed so
+    /// far: the number of pending bytes (one byte), then the pending bytes.
+    ///
+    /// ```
+    /// use bitcoin_varint::incremental::IncrementalDecoder;
+    ///
+    /// let mut decoder = IncrementalDecoder::new();
+    /// decoder.feed(&[0xfd, 0x03]).unwrap();
+    /// let checkpoint = decoder.to_bytes();
+    ///
+    /// // After a restart, feeding resumes from the byte after the checkpoint.
+    /// let (mut decoder, _) = IncrementalDecoder::from_bytes(&checkpoint).unwrap();
+    /// assert_eq!(decoder.feed(&[0x02]), Ok((1, Some(515))));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.filled];
+        out.extend_from_slice(self.pending());
+        out
+    }
+
+    /// Deserializes a state written by [`IncrementalDecoder::to_bytes`], returning it with the number of bytes
+    /// read.
+    ///
+    /// Pending bytes holding a complete value fail with [`VarIntError::Malformed`], as a decoder never keeps
+    /// one.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), VarIntError> {
+        let (&filled, rest) = bytes.split_first().ok_or(VarIntError::Truncated)?;
+        let pending = rest.get(..filled as usize).ok_or(VarIntError::Truncated)?;
+        // Also rejects more than 8 pending bytes, which always hold a complete value.
+        if VarInt::decode_full(pending) != Err(VarIntError::Truncated) {
+            return Err(VarIntError::Malformed);
+        }
+        let mut decoder = Self::new();
+        decoder.bytes[..pending.len()].copy_from_slice(pending);
+        decoder.filled = filled;
+        Ok((decoder, 1 + pending.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bulk::encode_all;
+
+    #[test]
+    fn test_incremental_any_split() {
+        let values = [7, 0xfd, 0x1_0000, u64::MAX, 0];
+        let bytes = encode_all(&values);
+        for chunk in 1..=bytes.len() {
+            let mut decoder = IncrementalDecoder::new();
+            let mut decoded = Vec::new();
+            for mut piece in bytes.chunks(chunk) {
+                while !piece.is_empty() {
+                    let (consumed, value) = decoder.feed(piece).unwrap();
+                    decoded.extend(value);
+                    piece = &piece[consumed..];
+                }
+            }
+            assert_eq!(decoded, values, "chunks of {}", chunk);
+            assert!(decoder.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_incremental_leaves_the_rest() {
+        let mut decoder = IncrementalDecoder::new();
+        assert_eq!(decoder.feed(&[]), Ok((0, None)));
+        assert_eq!(decoder.feed(&[0xfd, 0x03]), Ok((2, None)));
+        assert_eq!(decoder.pending(), &[0xfd, 0x03]);
+        // The byte after the value is not taken.
+        assert_eq!(decoder.feed(&[0x02, 0xaa]), Ok((1, Some(515))));
+        assert_eq!(decoder.feed(&[0xfd, 0x01]), Ok((2, None)));
+        assert_eq!(decoder.feed(&[0x00]), Ok((1, Some(1))));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_incremental_checkpoint() {
+        let bytes = encode_all(&[u64::MAX, 300]);
+        for split in 0..bytes.len() {
+            let mut decoder = IncrementalDecoder::new();
+            let (taken, _) = decoder.feed(&bytes[..split]).unwrap();
+            let checkpoint = decoder.to_bytes();
+            let (mut resumed, len) = IncrementalDecoder::from_bytes(&checkpoint).unwrap();
+            assert_eq!(
+                (resumed.pending(), len),
+                (decoder.pending(), checkpoint.len())
+            );
+            let mut values = Vec::new();
+            let mut rest = &bytes[taken..];
+            while !rest.is_empty() {
+                let (consumed, value) = resumed.feed(rest).unwrap();
+                values.extend(value);
+                rest = &rest[consumed..];
+            }
+            assert_eq!(values.last(), Some(&300), "split at {}", split);
+        }
+        assert_eq!(
+            IncrementalDecoder::from_bytes(&[]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(
+            IncrementalDecoder::from_bytes(&[2, 0xfe]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(
+            IncrementalDecoder::from_bytes(&[1, 0x05]),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            IncrementalDecoder::from_bytes(&[9, 0xff, 0, 0, 0, 0, 0, 0, 0, 1]),
+            Err(VarIntError::Malformed)
+        );
+    }
+}
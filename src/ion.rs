@@ -0,0 +1,165 @@
+use crate::VarIntError;
+
+/// Amazon Ion VarUInt and VarInt, the variable-length integers of Ion's binary encoding
+///
+/// Groups of 7 bits, most significant first, with the high bit set on the last byte rather than on the ones
+/// followed by another, unlike [`Vlq`](crate::vlq::Vlq). VarInt spends the bit below the end flag of its
+/// first byte on the sign, keeping 6 bits of magnitude there: values are sign and magnitude, not two's
+/// complement, so that `0xc0` is a negative zero, decoded as 0.
+///
+/// Leading zero groups (padding) are accepted, values that don't fit 64 bits are not.
+///
+/// https://amazon-ion.github.io/ion-docs/docs/binary.html#varuint-and-varint-fields
+pub struct Ion;
+
+impl Ion {
+    /// Maximum number of bytes of an encoded `u64` or `i64`.
+    pub const MAX_LEN: usize = 10;
+
+    pub fn encode_var_uint(value: u64) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::MAX_LEN);
+        Self::encode_var_uint_append(value, &mut result);
+        result
+    }
+
+    /// Appends the VarUInt encoding of `value` to `out`, returning the number of bytes written.
+    pub fn encode_var_uint_append(value: u64, out: &mut Vec<u8>) -> usize {
+        let len = Self::var_uint_size(value);
+        for i in (1..len).rev() {
+            out.push((value >> (7 * i)) as u8 & 0x7f);
+        }
+        out.push(value as u8 | 0x80);
+        len
+    }
+
+    /// Decodes a VarUInt, returning it with the number of bytes read.
+    pub fn decode_var_uint(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        Self::read_groups(0, bytes, 0)
+    }
+
+    pub fn encode_var_int(value: i64) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::MAX_LEN);
+        Self::encode_var_int_append(value, &mut result);
+        result
+    }
+
+    /// Appends the VarInt encoding of `value` to `out`, returning the number of bytes written.
+    pub fn encode_var_int_append(value: i64, out: &mut Vec<u8>) -> usize {
+        let magnitude = value.unsigned_abs();
+        let len = Self::var_int_size(value);
+        let sign = if value < 0 { 0x40 } else { 0 };
+        let end = if len == 1 { 0x80 } else { 0 };
+        out.push(end | sign | ((magnitude >> (7 * (len - 1))) as u8 & 0x3f));
+        for i in (0..len - 1).rev() {
+            let end = if i == 0 { 0x80 } else { 0 };
+            out.push(end | ((magnitude >> (7 * i)) as u8 & 0x7f));
+        }
+        len
+    }
+
+    /// Decodes a VarInt, returning it with the number of bytes read.
+    pub fn decode_var_int(bytes: &[u8]) -> Result<(i64, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let (magnitude, len) = if first & 0x80 != 0 {
+            ((first & 0x3f) as u64, 1)
+        } else {
+            Self::read_groups((first & 0x3f) as u64, &bytes[1..], 1)?
+        };
+        let value = if first & 0x40 == 0 {
+            i64::try_from(magnitude).map_err(|_| VarIntError::Overflow)?
+        } else if magnitude <= 1 << 63 {
+            (magnitude as i64).wrapping_neg()
+        } else {
+            return Err(VarIntError::Overflow);
+        };
+        Ok((value, len))
+    }
+
+    /// Returns the bytes needed to encode this value as a VarUInt
+    pub fn var_uint_size(value: u64) -> usize {
+        (64 - (value | 1).leading_zeros() as usize).div_ceil(7)
+    }
+
+    /// Returns the bytes needed to encode this value as a VarInt
+    pub fn var_int_size(value: i64) -> usize {
+        let bits = 64 - value.unsigned_abs().leading_zeros() as usize;
+        1 + bits.saturating_sub(6).div_ceil(7)
+    }
+
+    /// Appends the 7-bit groups of `bytes` to `value` up to the one with the end flag, returning the result with
+    /// `read` plus the number of bytes read.
+    fn read_groups(mut value: u64, bytes: &[u8], read: usize) -> Result<(u64, usize), VarIntError> {
+        for (i, byte) in bytes.iter().enumerate() {
+            if value >> 57 != 0 {
+                return Err(VarIntError::Overflow);
+            }
+            value = value << 7 | (byte & 0x7f) as u64;
+            if byte & 0x80 != 0 {
+                return Ok((value, read + i + 1));
+            }
+        }
+        Err(VarIntError::Truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ion_var_uint() {
+        let vectors: [(u64, &[u8]); 5] = [
+            (0, &[0x80]),
+            (0x7f, &[0xff]),
+            (0x80, &[0x01, 0x80]),
+            (0x3fff, &[0x7f, 0xff]),
+            (
+                u64::MAX,
+                &[0x01, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0xff],
+            ),
+        ];
+        for (value, bytes) in vectors {
+            assert_eq!(Ion::encode_var_uint(value), bytes);
+            assert_eq!(Ion::decode_var_uint(bytes), Ok((value, bytes.len())));
+        }
+        assert_eq!(Ion::decode_var_uint(&[0x00, 0x85]), Ok((5, 2)));
+        assert_eq!(
+            Ion::decode_var_uint(&[0x01, 0x00]),
+            Err(VarIntError::Truncated)
+        );
+        let mut bytes = vec![0x02];
+        bytes.extend_from_slice(&[0x7f; 8]);
+        bytes.push(0xff);
+        assert_eq!(Ion::decode_var_uint(&bytes), Err(VarIntError::Overflow));
+    }
+
+    #[test]
+    fn test_ion_var_int() {
+        let vectors: [(i64, &[u8]); 7] = [
+            (0, &[0x80]),
+            (63, &[0xbf]),
+            (-63, &[0xff]),
+            (64, &[0x00, 0xc0]),
+            (-8191, &[0x7f, 0xff]),
+            (
+                i64::MIN,
+                &[0x41, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80],
+            ),
+            (
+                i64::MAX,
+                &[0x00, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0xff],
+            ),
+        ];
+        for (value, bytes) in vectors {
+            assert_eq!(Ion::encode_var_int(value), bytes, "{}", value);
+            assert_eq!(Ion::decode_var_int(bytes), Ok((value, bytes.len())));
+        }
+        assert_eq!(Ion::decode_var_int(&[0xc0]), Ok((0, 1)));
+        assert_eq!(Ion::decode_var_int(&[0x40, 0x81]), Ok((-1, 2)));
+        // 2^63 fits as a magnitude only when negative.
+        let mut bytes = vec![0x01];
+        bytes.extend_from_slice(&[0x00; 8]);
+        bytes.push(0x80);
+        assert_eq!(Ion::decode_var_int(&bytes), Err(VarIntError::Overflow));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::VarIntError;
+
+/// Unsigned LEB128
+///
+/// Little-endian groups of 7 bits, the high bit of each byte telling whether another byte follows.
+/// Used by protobuf, WebAssembly and DWARF. A u64 takes at most 10 bytes.
+///
+/// https://en.wikipedia.org/wiki/LEB128
+pub struct Leb128;
+
+impl Leb128 {
+    /// Maximum number of bytes of an encoded u64.
+    pub const MAX_LEN: usize = 10;
+
+    pub fn encode(value: u64) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::MAX_LEN);
+        Self::encode_append(value, &mut result);
+        result
+    }
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    pub fn encode_append(mut value: u64, out: &mut Vec<u8>) -> usize {
+        let start = out.len();
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+        out.len() - start
+    }
+
+    /// Decodes a value, returning it with the number of bytes read.
+    ///
+    /// Padded encodings (e.g. `[0x80, 0x00]` for 0) are accepted, values over `u64::MAX` are not.
+    pub fn decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let mut value = 0u64;
+        for (i, byte) in bytes.iter().take(Self::MAX_LEN).enumerate() {
+            let group = (byte & 0x7f) as u64;
+            if i == Self::MAX_LEN - 1 && group > 1 {
+                return Err(VarIntError::Overflow);
+            }
+            value |= group << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok((value, i + 1));
+            }
+        }
+        if bytes.len() >= Self::MAX_LEN {
+            Err(VarIntError::Overflow)
+        } else {
+            Err(VarIntError::Truncated)
+        }
+    }
+
+    /// Maximum number of bytes of an encoded u32.
+    pub const MAX_LEN_U32: usize = 5;
+
+    /// [`Leb128::encode_append`] for a u32, with 32-bit arithmetic only, for targets where `u64` operations
+    /// are software routines such as Cortex-M0 and AVR.
+    pub fn encode_u32_append(mut value: u32, out: &mut Vec<u8>) -> usize {
+        let start = out.len();
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+        out.len() - start
+    }
+
+    /// Decodes a u32 the way LevelDB and RocksDB read their `varint32`: at most 5 bytes, the last one
+    /// carrying only the 4 remaining bits.
+    ///
+    /// Running out of input is reported as [`VarIntError::Truncated`], while a fifth byte with a
+    /// continuation bit or bits above the 32nd, which LevelDB treats as corruption, is [`VarIntError::Overflow`].
+    ///
+    /// https://github.com/google/leveldb/blob/main/util/coding.h
+    pub fn decode_u32(bytes: &[u8]) -> Result<(u32, usize), VarIntError> {
+        let mut value = 0u32;
+        for (i, byte) in bytes.iter().take(Self::MAX_LEN_U32).enumerate() {
+            if i == Self::MAX_LEN_U32 - 1 && *byte > 0x0f {
+                return Err(VarIntError::Overflow);
+            }
+            value |= ((byte & 0x7f) as u32) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok((value, i + 1));
+            }
+        }
+        Err(VarIntError::Truncated)
+    }
+
+    /// Returns the bytes needed to encode this value
+    pub fn get_size(value: u64) -> usize {
+        (64 - (value | 1).leading_zeros() as usize).div_ceil(7)
+    }
+
+    pub fn encode_reversed(value: u64) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::MAX_LEN);
+        Self::encode_reversed_append(value, &mut result);
+        result
+    }
+
+    /// Appends the encoding of `value` with its bytes in reverse order, so that it is read backwards from its
+    /// last byte with [`Leb128::decode_from_end`], as the length of a footer at the end of a file.
+    ///
+    /// ```
+    /// use bitcoin_varint::Leb128;
+    ///
+    /// let mut file = b"data".to_vec();
+    /// let footer = b"footer";
+    /// file.extend_from_slice(footer);
+    /// Leb128::encode_reversed_append(footer.len() as u64, &mut file);
+    ///
+    /// let (footer_len, len) = Leb128::decode_from_end(&file).unwrap();
+    /// let end = file.len() - len;
+    /// assert_eq!(&file[end - footer_len as usize..end], footer);
+    /// ```
+    pub fn encode_reversed_append(value: u64, out: &mut Vec<u8>) -> usize {
+        let start = out.len();
+        Self::encode_append(value, out);
+        out[start..].reverse();
+        out.len() - start
+    }
+
+    /// Decodes the value written by [`Leb128::encode_reversed_append`] at the end of `bytes`, returning it with
+    /// the number of bytes it takes, counted from the end.
+    pub fn decode_from_end(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let mut tail = [0u8; Self::MAX_LEN];
+        for (slot, byte) in tail.iter_mut().zip(bytes.iter().rev()) {
+            *slot = *byte;
+        }
+        Self::decode(&tail[..bytes.len().min(Self::MAX_LEN)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leb128_encode() {
+        assert_eq!(Leb128::encode(0), vec![0x00]);
+        assert_eq!(Leb128::encode(127), vec![0x7f]);
+        assert_eq!(Leb128::encode(624485), vec![0xe5, 0x8e, 0x26]);
+        assert_eq!(Leb128::encode(u64::MAX).len(), 10);
+        assert_eq!(Leb128::get_size(u64::MAX), 10);
+        assert_eq!(Leb128::get_size(128), 2);
+    }
+
+    #[test]
+    fn test_leb128_decode() {
+        assert_eq!(
+            Leb128::decode(&[0xe5, 0x8e, 0x26, 0xff]).unwrap(),
+            (624485, 3)
+        );
+        assert_eq!(Leb128::decode(&[0x80, 0x00]).unwrap(), (0, 2));
+        assert_eq!(
+            Leb128::decode(&Leb128::encode(u64::MAX)).unwrap().0,
+            u64::MAX
+        );
+        assert_eq!(Leb128::decode(&[0xe5, 0x8e]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_leb128_decode_overflow() {
+        let mut bytes = vec![0xff; 9];
+        bytes.push(0x02);
+        assert_eq!(Leb128::decode(&bytes), Err(VarIntError::Overflow));
+        assert_eq!(Leb128::decode(&[0x80; 11]), Err(VarIntError::Overflow));
+    }
+
+    #[test]
+    fn test_leb128_reversed() {
+        let mut out = vec![0xaa];
+        assert_eq!(Leb128::encode_reversed_append(624485, &mut out), 3);
+        assert_eq!(out, [0xaa, 0x26, 0x8e, 0xe5]);
+        assert_eq!(Leb128::decode_from_end(&out), Ok((624485, 3)));
+        assert_eq!(Leb128::encode_reversed(128), [0x01, 0x80]);
+        assert_eq!(Leb128::decode_from_end(&[0x7f]), Ok((127, 1)));
+        assert_eq!(
+            Leb128::decode_from_end(&[0x8e, 0xe5]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(Leb128::decode_from_end(&[]), Err(VarIntError::Truncated));
+        assert_eq!(
+            Leb128::decode_from_end(&[0x80; 11]),
+            Err(VarIntError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_leb128_decode_u32() {
+        assert_eq!(Leb128::decode_u32(&[0xe5, 0x8e, 0x26]), Ok((624485, 3)));
+        let max = [0xff, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(Leb128::decode_u32(&max), Ok((u32::MAX, 5)));
+        let mut out = Vec::new();
+        assert_eq!(Leb128::encode_u32_append(u32::MAX, &mut out), 5);
+        assert_eq!(out, max);
+        assert_eq!(
+            Leb128::decode_u32(&[0xff, 0xff, 0xff, 0xff, 0x10]),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(
+            Leb128::decode_u32(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00]),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(
+            Leb128::decode_u32(&[0xff, 0xff]),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
@@ -1,6 +1,146 @@
+#[cfg(feature = "bigint")]
+pub mod bigint;
+#[cfg(feature = "bigsize")]
+pub mod bigsize;
+pub mod block;
+pub mod bulk;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod chunked;
+mod codec;
+#[cfg(feature = "columnar")]
+pub mod columnar;
+pub mod compact_block;
+pub mod consensus;
+pub mod decoder;
+pub mod delimited;
+#[cfg(feature = "ebml")]
+pub mod ebml;
+#[cfg(feature = "leb128")]
+pub mod elias_fano;
+pub mod encoder;
+mod error;
+pub mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "flac")]
+pub mod flac;
+#[cfg(feature = "golomb")]
+pub mod gcs;
+#[cfg(feature = "golomb")]
+pub mod golomb;
+#[cfg(feature = "groups")]
+pub mod groups;
+#[cfg(feature = "h3")]
+pub mod h3;
+#[cfg(feature = "hadoop")]
+pub mod hadoop;
+pub mod header;
+pub mod incremental;
+#[cfg(feature = "ion")]
+pub mod ion;
+#[cfg(feature = "leb128")]
+pub mod leb128;
+#[cfg(feature = "lucene")]
+pub mod lucene;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod morton;
+pub mod mux;
+#[cfg(feature = "oid")]
+pub mod oid;
+#[cfg(feature = "leb128")]
+pub mod packed;
+#[cfg(feature = "leb128")]
+pub mod postings;
+pub mod psbt;
+#[cfg(feature = "quic")]
+pub mod quic;
+#[cfg(feature = "rdb")]
+pub mod rdb;
+pub mod record_log;
+#[cfg(feature = "rlp")]
+pub mod rlp;
+#[cfg(feature = "scale")]
+pub mod scale;
+#[cfg(feature = "shortvec")]
+pub mod shortvec;
+pub mod sorted_set;
+pub mod sparse_index;
+pub mod stats;
 pub mod test;
+pub mod tlv;
+pub mod tx;
+#[cfg(feature = "leb128")]
+pub mod varint_vec;
+#[cfg(feature = "test-vectors")]
+pub mod vectors;
+#[cfg(kani)]
+mod verification;
+#[cfg(feature = "vlq")]
+pub mod vlq;
+pub mod witness;
+#[cfg(feature = "xrpl")]
+pub mod xrpl;
 
-use std::io::Error;
+#[cfg(feature = "bigsize")]
+pub use bigsize::BigSize;
+pub use codec::{decode_chunks, transcode, transcode_stream, Codec, VarCodec};
+pub use error::{DecodeError, VarIntError};
+#[cfg(feature = "leb128")]
+pub use leb128::Leb128;
+
+use std::cmp::Ordering;
+use std::io::{BufRead, Error, ErrorKind, Read};
+
+/// A decoded compactSize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    pub value: u64,
+    /// Number of bytes read.
+    pub len: usize,
+    /// Whether the value was encoded in its shortest form.
+    pub canonical: bool,
+}
+
+/// How to extract a compactSize from the 9 bytes window starting at its prefix.
+#[derive(Clone, Copy)]
+struct DecodeEntry {
+    /// Where the little-endian payload starts: on the prefix itself for single byte values.
+    offset: u8,
+    mask: u64,
+    len: u8,
+    /// Smallest value for which this prefix is the shortest form.
+    min: u64,
+}
+
+const DECODE_TABLE: [DecodeEntry; 256] = {
+    let mut table = [DecodeEntry {
+        offset: 0,
+        mask: 0xff,
+        len: 1,
+        min: 0,
+    }; 256];
+    table[0xfd] = DecodeEntry {
+        offset: 1,
+        mask: 0xffff,
+        len: 3,
+        min: 0xfd,
+    };
+    table[0xfe] = DecodeEntry {
+        offset: 1,
+        mask: 0xffffffff,
+        len: 5,
+        min: 0x10000,
+    };
+    table[0xff] = DecodeEntry {
+        offset: 1,
+        mask: u64::MAX,
+        len: 9,
+        min: 0x100000000,
+    };
+    table
+};
 
 /// CompactSize Unsigned Integers  
 ///
@@ -18,57 +158,379 @@ pub struct VarInt;
 impl VarInt {
     /// For numbers from 0 to 252, compactSize unsigned integers look like regular unsigned integers.
     /// For other numbers up to 0xffffffffffffffff, a byte is prefixed to the number to indicate its length—but otherwise the numbers look like regular unsigned integers in little-endian order.
+    ///
+    /// The encoding is always the shortest form, the only one consensus accepts, as is that of
+    /// [`VarInt::encode_append`].
     pub fn encode(size: u64) -> Result<Vec<u8>, Error> {
-        let size_bytes = size.to_le_bytes();
-        let result = match size {
-            x if x <= 252 => vec![size_bytes[0]],
-            x if (253..0xffff).contains(&x) => {
-                vec![0xfd, size_bytes[0], size_bytes[1]]
+        let mut result = Vec::with_capacity(9);
+        Self::encode_append(size, &mut result);
+        Ok(result)
+    }
+
+    /// Appends the encoding of `size` to `out`, returning the number of bytes written.
+    ///
+    /// Lets a message be built field after field into one buffer, without a temporary `Vec` per field.
+    /// Single byte values, the bulk of lengths and counts in practice, take an early return.
+    #[inline]
+    pub fn encode_append(size: u64, out: &mut Vec<u8>) -> usize {
+        if size < 0xfd {
+            out.push(size as u8);
+            return 1;
+        }
+        let len = Self::encoded_len(size);
+        match len {
+            1 => out.push(size as u8),
+            3 => {
+                out.push(0xfd);
+                out.extend_from_slice(&(size as u16).to_le_bytes());
             }
-            x if (0x10000..0xffffffff).contains(&x) => vec![
-                0xfe,
-                size_bytes[0],
-                size_bytes[1],
-                size_bytes[2],
-                size_bytes[3],
-            ],
-            x if (0x100000000..u64::MAX).contains(&x) => {
-                let mut x = size_bytes.to_vec();
-                x.insert(0, 0xff);
-                x
+            5 => {
+                out.push(0xfe);
+                out.extend_from_slice(&(size as u32).to_le_bytes());
             }
-            _ => panic!("VarInt: unexpected input"),
-        };
-        Ok(result)
+            _ => {
+                out.push(0xff);
+                out.extend_from_slice(&size.to_le_bytes());
+            }
+        }
+        len
     }
 
     /// For numbers from 0 to 252, compactSize unsigned integers look like regular unsigned integers.
     /// For other numbers up to 0xffffffffffffffff, a byte is prefixed to the number to indicate its length—but otherwise the numbers look like regular unsigned integers in little-endian order.
     pub fn decode(bytes: &[u8]) -> Result<u64, Error> {
-        let result = match bytes[0] {
-            x if x < 0xfd => u64::from_le_bytes([bytes[0], 0, 0, 0, 0, 0, 0, 0]),
-            x if x == 0xfd => u64::from_le_bytes([bytes[1], bytes[2], 0, 0, 0, 0, 0, 0]),
-            x if x == 0xfe => {
-                u64::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4], 0, 0, 0, 0])
+        Self::decode_full(bytes)
+            .map(|decoded| decoded.value)
+            .map_err(Error::from)
+    }
+
+    /// Decodes a compactSize in a single pass, returning its value along with the number of bytes it takes
+    /// and whether it is in its shortest form.
+    ///
+    /// Single byte values return early: they are most of the lengths and counts found in transactions
+    /// and messages, so the branch is well predicted and costs nothing else. For the other prefixes, the
+    /// prefix byte selects an entry of a lookup table giving the offset and mask to apply to a single
+    /// unaligned 8 bytes load, so that no branch depends on their class.
+    #[inline]
+    pub fn decode_full(bytes: &[u8]) -> Result<Decoded, VarIntError> {
+        let prefix = *bytes.first().ok_or(VarIntError::Truncated)?;
+        if prefix < 0xfd {
+            return Ok(Decoded {
+                value: prefix as u64,
+                len: 1,
+                canonical: true,
+            });
+        }
+        Self::decode_prefixed(bytes, prefix)
+    }
+
+    /// [`VarInt::decode_full`] for a 0xfd, 0xfe or 0xff prefix.
+    fn decode_prefixed(bytes: &[u8], prefix: u8) -> Result<Decoded, VarIntError> {
+        let entry = DECODE_TABLE[prefix as usize];
+        let window = match bytes.get(..9) {
+            Some(window) => <[u8; 9]>::try_from(window).unwrap(),
+            None => {
+                let mut window = [0u8; 9];
+                window[..bytes.len()].copy_from_slice(bytes);
+                window
             }
-            x if x == 0xff => u64::from_le_bytes([
-                bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7], bytes[8],
-            ]),
-            _ => panic!("VarInt: unexpected input"),
         };
+        if (entry.len as usize) > bytes.len() {
+            return Err(VarIntError::Truncated);
+        }
+        let offset = entry.offset as usize;
+        let word = u64::from_le_bytes(window[offset..offset + 8].try_into().unwrap());
+        let value = word & entry.mask;
+        Ok(Decoded {
+            value,
+            len: entry.len as usize,
+            canonical: value >= entry.min,
+        })
+    }
+
+    /// [`VarInt::encode_append`] for a `u32`, with 32-bit arithmetic only, for targets where `u64` operations
+    /// are software routines such as Cortex-M0 and AVR. The bytes are the same.
+    pub fn encode_u32_append(size: u32, out: &mut Vec<u8>) -> usize {
+        if size < 0xfd {
+            out.push(size as u8);
+            1
+        } else if size <= 0xffff {
+            out.push(0xfd);
+            out.extend_from_slice(&(size as u16).to_le_bytes());
+            3
+        } else {
+            out.push(0xfe);
+            out.extend_from_slice(&size.to_le_bytes());
+            5
+        }
+    }
+
+    /// Decodes a compactSize into a `u32` with 32-bit arithmetic only, see [`VarInt::encode_u32_append`].
+    ///
+    /// Like [`VarInt::decode_full`], values not in their shortest form are accepted, including those with a
+    /// 0xff prefix whose upper 4 bytes are zero. Other 0xff values fail with [`VarIntError::Overflow`].
+    pub fn decode_u32(bytes: &[u8]) -> Result<(u32, usize), VarIntError> {
+        let prefix = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let len = match prefix {
+            0xfd => 3,
+            0xfe => 5,
+            0xff => 9,
+            _ => return Ok((prefix as u32, 1)),
+        };
+        let payload = bytes.get(1..len).ok_or(VarIntError::Truncated)?;
+        match payload {
+            [a, b] => Ok((u16::from_le_bytes([*a, *b]) as u32, 3)),
+            [a, b, c, d] => Ok((u32::from_le_bytes([*a, *b, *c, *d]), 5)),
+            [a, b, c, d, 0, 0, 0, 0] => Ok((u32::from_le_bytes([*a, *b, *c, *d]), 9)),
+            _ => Err(VarIntError::Overflow),
+        }
+    }
+
+    /// Decodes the compactSize at the start of `window` in constant time, for secret values.
+    ///
+    /// The whole 9 bytes window is always processed, and the prefix class only selects results through
+    /// masks, without branches or table lookups depending on it. Shorter inputs are to be zero-padded,
+    /// and the caller must check that the returned length fits in the input. This is best effort:
+    /// constant time at the source level, with [`std::hint::black_box`] keeping the compiler from
+    /// turning the masks back into branches.
+    pub fn decode_constant_time(window: &[u8; 9]) -> Decoded {
+        /// All ones when `a == b`, zero otherwise.
+        fn mask_eq(a: u8, b: u8) -> u64 {
+            let x = std::hint::black_box((a ^ b) as u64);
+            ((x | x.wrapping_neg()) >> 63).wrapping_sub(1)
+        }
+
+        let prefix = window[0];
+        let is_fd = mask_eq(prefix, 0xfd);
+        let is_fe = mask_eq(prefix, 0xfe);
+        let is_ff = mask_eq(prefix, 0xff);
+        let is_direct = !(is_fd | is_fe | is_ff);
+        let word = u64::from_le_bytes(window[1..].try_into().unwrap());
+        let value = (is_direct & prefix as u64)
+            | (is_fd & word & 0xffff)
+            | (is_fe & word & 0xffff_ffff)
+            | (is_ff & word);
+        let len = (is_direct & 1) | (is_fd & 3) | (is_fe & 5) | (is_ff & 9);
+        let min = (is_fd & 0xfd) | (is_fe & 0x10000) | (is_ff & 0x1_0000_0000);
+        // value >= min, from the borrow of value - min.
+        let below = (value.overflowing_sub(min).1 as u64).wrapping_neg();
+        Decoded {
+            value,
+            len: len as usize,
+            canonical: below == 0,
+        }
+    }
+
+    /// Reads a compactSize from a buffered reader, consuming exactly the bytes of the encoding.
+    ///
+    /// When the reader's buffer holds the whole encoding, which is the common case, the value is
+    /// decoded in place without copying. Nothing after the encoding is consumed.
+    pub fn read_buffered<R: BufRead>(reader: &mut R) -> Result<u64, Error> {
+        let buf = reader.fill_buf()?;
+        let prefix = *buf.first().ok_or(Error::from(ErrorKind::UnexpectedEof))?;
+        let len = DECODE_TABLE[prefix as usize].len as usize;
+        if buf.len() >= len {
+            let value = Self::decode_full(buf).unwrap().value;
+            reader.consume(len);
+            return Ok(value);
+        }
+        // The encoding straddles the end of the buffer.
+        let mut window = [0u8; 9];
+        let mut filled = 0;
+        while filled < len {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+            let n = buf.len().min(len - filled);
+            window[filled..filled + n].copy_from_slice(&buf[..n]);
+            reader.consume(n);
+            filled += n;
+        }
+        Ok(Self::decode_full(&window).unwrap().value)
+    }
+
+    /// Encodes a collection length, the way to write length prefixes.
+    ///
+    /// The `usize` to `u64` conversion is checked rather than cast, failing with [`VarIntError::TooLarge`]
+    /// on a target whose `usize` is wider than 64 bits. See [`VarInt::decode_len`] for reading it back.
+    ///
+    /// ```
+    /// use bitcoin_varint::VarInt;
+    ///
+    /// let items = vec![0u8; 300];
+    /// let prefix = VarInt::encode_len(items.len()).unwrap();
+    /// assert_eq!(prefix, vec![0xfd, 0x2c, 0x01]);
+    /// assert_eq!(VarInt::decode_len(&prefix), Ok((300, 3)));
+    /// ```
+    pub fn encode_len(len: usize) -> Result<Vec<u8>, VarIntError> {
+        let len = u64::try_from(len).map_err(|_| VarIntError::TooLarge)?;
+        let mut result = Vec::with_capacity(9);
+        Self::encode_append(len, &mut result);
         Ok(result)
     }
 
+    /// Decodes a compactSize used as a length, returning it with the number of bytes read.
+    ///
+    /// Fails with [`VarIntError::TooLarge`] instead of truncating when the value does not fit
+    /// in a `usize`, as on 32-bit and wasm32 targets.
+    pub fn decode_len(bytes: &[u8]) -> Result<(usize, usize), VarIntError> {
+        let decoded = Self::decode_full(bytes)?;
+        let value = usize::try_from(decoded.value).map_err(|_| VarIntError::TooLarge)?;
+        Ok((value, decoded.len))
+    }
+
+    /// Whether `bytes` is exactly one compactSize in its shortest form.
+    pub fn is_canonical(bytes: &[u8]) -> bool {
+        matches!(Self::decode_full(bytes), Ok(decoded) if decoded.canonical && decoded.len == bytes.len())
+    }
+
+    /// Orders two compactSizes as their values, without decoding them, for comparators of encoded keys.
+    ///
+    /// In shortest form, the first byte orders the values of different lengths: a single byte below 0xfd is the
+    /// value itself, below any value with a prefix, and the prefixes 0xfd, 0xfe and 0xff are in the order of
+    /// the ranges they cover. Only values with the same prefix compare their payloads, as integers of the
+    /// width of the prefix. Bytes after the encoding are ignored, and an empty slice comes first.
+    ///
+    /// Padded encodings are ordered by their prefix rather than by their value, and missing payload bytes
+    /// compare as zeros, so keys must be checked with [`VarInt::is_canonical`] when written.
+    ///
+    /// ```
+    /// use bitcoin_varint::VarInt;
+    /// use std::cmp::Ordering;
+    ///
+    /// assert_eq!(VarInt::compare_encoded(&[0xfc], &[0xfd, 0xfd, 0x00]), Ordering::Less);
+    /// assert_eq!(VarInt::compare_encoded(&[0xfd, 0x00, 0x02], &[0xfd, 0xff, 0x01]), Ordering::Greater);
+    /// ```
+    pub fn compare_encoded(a: &[u8], b: &[u8]) -> Ordering {
+        let (Some(&x), Some(&y)) = (a.first(), b.first()) else {
+            return b.is_empty().cmp(&a.is_empty());
+        };
+        if x != y || x < 0xfd {
+            return x.cmp(&y);
+        }
+        let width = DECODE_TABLE[x as usize].len as usize - 1;
+        let payload = |bytes: &[u8]| {
+            let mut buf = [0u8; 8];
+            let available = &bytes[1..bytes.len().min(1 + width)];
+            buf[..available.len()].copy_from_slice(available);
+            u64::from_le_bytes(buf)
+        };
+        payload(a).cmp(&payload(b))
+    }
+
+    /// Re-encodes the compactSize at the start of `bytes`, padded or not, in its shortest form, returning it with
+    /// the number of bytes read.
+    ///
+    /// ```
+    /// use bitcoin_varint::VarInt;
+    ///
+    /// // 42 padded to 5 bytes by a lenient producer.
+    /// assert_eq!(VarInt::canonicalize(&[0xfe, 0x2a, 0, 0, 0]), Ok((vec![0x2a], 5)));
+    /// ```
+    pub fn canonicalize(bytes: &[u8]) -> Result<(Vec<u8>, usize), VarIntError> {
+        let decoded = Self::decode_full(bytes)?;
+        let mut result = Vec::with_capacity(9);
+        Self::encode_append(decoded.value, &mut result);
+        Ok((result, decoded.len))
+    }
+
+    /// Decodes a compactSize that must take all of `bytes`, as map keys and fixed-size fields do: trailing
+    /// bytes fail with [`VarIntError::Malformed`] instead of being ignored.
+    pub fn decode_exact(bytes: &[u8]) -> Result<u64, VarIntError> {
+        VarCodec::decode_exact(&VarInt, bytes)
+    }
+
+    /// Reads a compactSize from `reader`, consuming exactly the bytes of the encoding.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<u64, Error> {
+        let mut window = [0u8; 9];
+        reader.read_exact(&mut window[..1])?;
+        let len = DECODE_TABLE[window[0] as usize].len as usize;
+        reader.read_exact(&mut window[1..len])?;
+        Ok(Self::decode_full(&window).unwrap().value)
+    }
+
+    /// Decodes a compactSize without any bounds or canonicality check, returning the value with the number of bytes read.
+    ///
+    /// Meant for data already validated, such as files written by this crate.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be non-empty and hold the whole encoding announced by its first byte:
+    /// at least 3 bytes after a 0xfd prefix, 5 after 0xfe and 9 after 0xff.
+    /// Anything shorter is undefined behavior.
+    pub unsafe fn decode_unchecked(bytes: &[u8]) -> (u64, usize) {
+        let entry = DECODE_TABLE[*bytes.get_unchecked(0) as usize];
+        let offset = entry.offset as usize;
+        let len = entry.len as usize;
+        let mut buf = [0u8; 8];
+        std::ptr::copy_nonoverlapping(bytes.as_ptr().add(offset), buf.as_mut_ptr(), len - offset);
+        (u64::from_le_bytes(buf), len)
+    }
+
     /// Returns the bytes needed to encode this varint
     pub fn get_size(varint: u64) -> Result<u8, Error> {
-        match varint {
-            x if x <= 252 => Ok(1),
-            x if (253..0xffff).contains(&x) => Ok(3),
-            x if (0x10000..0xffffffff).contains(&x) => Ok(5),
-            x if (0x10000000..u64::MAX).contains(&x) => Ok(9),
-            _ => panic!("VarInt: unexpected input"),
+        Ok(Self::encoded_len(varint) as u8)
+    }
+
+    /// Number of bytes of the encoding of `size`, usable in constants.
+    ///
+    /// Derived from the number of significant bytes of `size`, rounded up to the payload sizes.
+    #[inline]
+    pub const fn encoded_len(size: u64) -> usize {
+        if size <= 252 {
+            return 1;
+        }
+        let bytes = (64 - size.leading_zeros() as usize).div_ceil(8);
+        1 + if bytes < 2 {
+            2
+        } else {
+            bytes.next_power_of_two()
         }
     }
+
+    /// Encodes `size` in a constant context, `N` being its [`VarInt::encoded_len`].
+    ///
+    /// Panics (at compile time in a constant) when `N` is not the encoded length. See [`varint!`].
+    pub const fn encode_array<const N: usize>(size: u64) -> [u8; N] {
+        assert!(N == Self::encoded_len(size), "VarInt: wrong array length");
+        let mut result = [0u8; N];
+        let payload = size.to_le_bytes();
+        if N == 1 {
+            result[0] = payload[0];
+            return result;
+        }
+        result[0] = match N {
+            3 => 0xfd,
+            5 => 0xfe,
+            _ => 0xff,
+        };
+        let mut i = 1;
+        while i < N {
+            result[i] = payload[i - 1];
+            i += 1;
+        }
+        result
+    }
+}
+
+/// Encodes a compactSize at compile time, expanding to a byte array of the right length.
+///
+/// ```
+/// use bitcoin_varint::varint;
+///
+/// const LEN_515: [u8; 3] = varint!(515);
+/// assert_eq!(LEN_515, [0xfd, 0x03, 0x02]);
+/// assert_eq!(varint!(0xfd03), [0xfd, 0x03, 0xfd]);
+/// ```
+#[macro_export]
+macro_rules! varint {
+    ($size:expr) => {{
+        const SIZE: u64 = $size;
+        const BYTES: [u8; $crate::VarInt::encoded_len(SIZE)] =
+            $crate::VarInt::encode_array::<{ $crate::VarInt::encoded_len(SIZE) }>(SIZE);
+        BYTES
+    }};
 }
 
 #[cfg(test)]
@@ -80,13 +542,237 @@ mod tests {
         assert_eq!(VarInt::encode(515).unwrap(), vec![0xfd, 3, 2]);
     }
 
+    #[test]
+    fn test_varint_macro() {
+        assert_eq!(varint!(252), [252]);
+        assert_eq!(varint!(0x10000), [0xfe, 0, 0, 1, 0]);
+        assert_eq!(
+            &varint!(u64::MAX)[..],
+            &VarInt::encode(u64::MAX).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_varint_encode_append() {
+        let mut out = vec![0x01];
+        assert_eq!(VarInt::encode_append(515, &mut out), 3);
+        assert_eq!(VarInt::encode_append(0xffff, &mut out), 3);
+        assert_eq!(VarInt::encode_append(u64::MAX, &mut out), 9);
+        assert_eq!(out.len(), 16);
+        assert_eq!(&out[..7], &[0x01, 0xfd, 3, 2, 0xfd, 0xff, 0xff]);
+    }
+
     #[test]
     fn test_varint_decode() {
-        assert_eq!(VarInt::decode(&vec![0xfd, 3, 2]).unwrap(), 515);
+        assert_eq!(VarInt::decode(&[0xfd, 3, 2]).unwrap(), 515);
+    }
+
+    #[test]
+    fn test_varint_decode_truncated() {
+        assert_eq!(
+            VarInt::decode(&[0xfe, 3, 2]).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        assert!(VarInt::decode(&[]).is_err());
+    }
+
+    #[test]
+    fn test_varint_decode_full() {
+        let decoded = VarInt::decode_full(&[0xfd, 3, 2, 0xff]).unwrap();
+        assert_eq!(decoded.value, 515);
+        assert_eq!(decoded.len, 3);
+        assert!(decoded.canonical);
+        assert!(!VarInt::decode_full(&[0xfe, 3, 2, 0, 0]).unwrap().canonical);
+        assert_eq!(VarInt::decode_full(&[0xff, 1]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_varint_decode_constant_time() {
+        let payloads = [
+            [0u8; 8],
+            [0xff; 8],
+            [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+        ];
+        for prefix in [0x00, 0x2a, 0xfc, 0xfd, 0xfe, 0xff] {
+            for payload in payloads {
+                let mut window = [prefix; 9];
+                window[1..].copy_from_slice(&payload);
+                assert_eq!(
+                    VarInt::decode_constant_time(&window),
+                    VarInt::decode_full(&window).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_varint_decode_len() {
+        assert_eq!(VarInt::decode_len(&[0xfd, 3, 2]), Ok((515, 3)));
+        for len in [0, 252, 253, 0xffff, 0x10000, usize::MAX] {
+            let prefix = VarInt::encode_len(len).unwrap();
+            assert_eq!(VarInt::decode_len(&prefix), Ok((len, prefix.len())));
+        }
+        let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        if usize::BITS < 64 {
+            assert_eq!(VarInt::decode_len(&max), Err(VarIntError::TooLarge));
+        } else {
+            assert_eq!(VarInt::decode_len(&max), Ok((usize::MAX, 9)));
+        }
+        assert_eq!(VarInt::decode_len(&[0xfe, 0]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_varint_canonical() {
+        assert!(VarInt::is_canonical(&[0xfc]));
+        assert!(VarInt::is_canonical(&[0xfd, 0xfd, 0x00]));
+        assert!(!VarInt::is_canonical(&[0xfd, 0xfc, 0x00]));
+        assert!(!VarInt::is_canonical(&[0x01, 0x02]));
+        assert!(!VarInt::is_canonical(&[0xfe, 0x00]));
+        for value in [0, 0xfc, 0xfd, 0xffff, 0x10000, 1 << 32, u64::MAX] {
+            assert!(VarInt::is_canonical(&VarInt::encode(value).unwrap()));
+        }
+        assert_eq!(
+            VarInt::canonicalize(&[0xff, 0x2c, 0x01, 0, 0, 0, 0, 0, 0, 0x07]),
+            Ok((vec![0xfd, 0x2c, 0x01], 9))
+        );
+        assert_eq!(VarInt::canonicalize(&[0x07]), Ok((vec![0x07], 1)));
+        assert_eq!(VarInt::canonicalize(&[0xfd]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_varint_decode_exact() {
+        assert_eq!(VarInt::decode_exact(&[0xfd, 0x2c, 0x01]), Ok(300));
+        assert_eq!(
+            VarInt::decode_exact(&[0x2a, 0x00]),
+            Err(VarIntError::Malformed)
+        );
+        assert_eq!(
+            VarInt::decode_exact(&[0xfd, 0x2c]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(VarInt::decode_exact(&[]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_varint_read_from() {
+        let mut reader: &[u8] = &[0xfd, 3, 2, 0x2a];
+        assert_eq!(VarInt::read_from(&mut reader).unwrap(), 515);
+        assert_eq!(reader, &[0x2a]);
+        let mut reader: &[u8] = &[0xfe, 3];
+        assert_eq!(
+            VarInt::read_from(&mut reader).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_varint_read_buffered() {
+        let bytes = [0x2a, 0xfe, 0, 0, 1, 0, 0xfd, 3, 2, 0x07];
+        // A tiny buffer makes encodings straddle refills.
+        let mut reader = std::io::BufReader::with_capacity(3, &bytes[..]);
+        assert_eq!(VarInt::read_buffered(&mut reader).unwrap(), 0x2a);
+        assert_eq!(VarInt::read_buffered(&mut reader).unwrap(), 0x10000);
+        assert_eq!(VarInt::read_buffered(&mut reader).unwrap(), 515);
+        assert_eq!(reader.fill_buf().unwrap(), &[0x07]);
+        let mut reader: &[u8] = &[0xff, 1, 2];
+        assert_eq!(
+            VarInt::read_buffered(&mut reader).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_varint_decode_unchecked() {
+        assert_eq!(unsafe { VarInt::decode_unchecked(&[0xfd, 3, 2]) }, (515, 3));
+        assert_eq!(unsafe { VarInt::decode_unchecked(&[0x2a, 0xff]) }, (42, 1));
+        assert_eq!(
+            unsafe { VarInt::decode_unchecked(&[0xff, 1, 2, 3, 4, 5, 6, 7, 8]) },
+            (0x0807060504030201, 9)
+        );
+    }
+
+    #[test]
+    fn test_varint_u32() {
+        for value in [0, 0xfc, 0xfd, 0xffff, 0x10000, u32::MAX] {
+            let mut out = Vec::new();
+            let len = VarInt::encode_u32_append(value, &mut out);
+            assert_eq!(out, VarInt::encode(value as u64).unwrap());
+            assert_eq!(VarInt::decode_u32(&out), Ok((value, len)));
+        }
+        assert_eq!(
+            VarInt::decode_u32(&[0xff, 7, 0, 0, 0, 0, 0, 0, 0]),
+            Ok((7, 9))
+        );
+        assert_eq!(
+            VarInt::decode_u32(&[0xff, 0, 0, 0, 0, 1, 0, 0, 0]),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(
+            VarInt::decode_u32(&[0xfe, 0, 0]),
+            Err(VarIntError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_varint_compare_encoded() {
+        let values = [
+            0,
+            1,
+            0xfc,
+            0xfd,
+            0xfe,
+            0xff,
+            0x100,
+            0x1ff,
+            0xfffe,
+            0xffff,
+            0x10000,
+            0x1_0100,
+            0xffff_ffff,
+            0x1_0000_0000,
+            0x100_0000_0000,
+            u64::MAX - 1,
+            u64::MAX,
+        ];
+        for a in values {
+            for b in values {
+                let (x, y) = (VarInt::encode(a).unwrap(), VarInt::encode(b).unwrap());
+                assert_eq!(VarInt::compare_encoded(&x, &y), a.cmp(&b), "{} {}", a, b);
+            }
+        }
+        assert_eq!(VarInt::compare_encoded(&[], &[0x00]), Ordering::Less);
+        assert_eq!(VarInt::compare_encoded(&[], &[]), Ordering::Equal);
+        assert_eq!(
+            VarInt::compare_encoded(&[0x07, 0xaa], &[0x07]),
+            Ordering::Equal
+        );
+        // Missing payload bytes are zeros: 0x01 against 0x0100.
+        assert_eq!(
+            VarInt::compare_encoded(&[0xfe, 0x01], &[0xfe, 0x00, 0x01]),
+            Ordering::Less
+        );
     }
 
     #[test]
     fn test_varint_get_size() {
         assert_eq!(VarInt::get_size(515).unwrap(), 3);
     }
+
+    #[test]
+    fn test_varint_get_size_boundaries() {
+        for value in [
+            0,
+            252,
+            253,
+            0xffff,
+            0x10000,
+            0xffffffff,
+            0x100000000,
+            u64::MAX,
+        ] {
+            let encoded = VarInt::encode(value).unwrap();
+            assert_eq!(VarInt::get_size(value).unwrap() as usize, encoded.len());
+            assert_eq!(VarInt::decode(&encoded).unwrap(), value);
+        }
+    }
 }
@@ -0,0 +1,111 @@
+use crate::VarIntError;
+
+/// Lucene vInt and vLong
+///
+/// Groups of 7 bits, least significant first, the high bit of each byte telling whether another byte
+/// follows: the LEB128 layout, with Lucene's limits. A vInt is an `i32` of at most 5 bytes, negative
+/// values taking all 5. A vLong is a non-negative `i64` of at most 9 bytes.
+///
+/// https://lucene.apache.org/core/9_0_0/core/org/apache/lucene/store/DataOutput.html#writeVInt(int)
+pub struct Lucene;
+
+impl Lucene {
+    pub const MAX_VINT_LEN: usize = 5;
+    pub const MAX_VLONG_LEN: usize = 9;
+
+    pub fn encode_vint(value: i32) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::MAX_VINT_LEN);
+        encode_groups(value as u32 as u64, &mut result);
+        result
+    }
+
+    /// Decodes a vInt, returning it with the number of bytes read.
+    ///
+    /// Like Lucene's `readVInt`, the fifth byte may only carry the 4 remaining bits.
+    pub fn decode_vint(bytes: &[u8]) -> Result<(i32, usize), VarIntError> {
+        let (value, len) = decode_groups(bytes, Self::MAX_VINT_LEN, 0x0f)?;
+        Ok((value as u32 as i32, len))
+    }
+
+    /// Encodes a vLong, failing with [`VarIntError::Overflow`] for negative values which Lucene refuses to write.
+    pub fn encode_vlong(value: i64) -> Result<Vec<u8>, VarIntError> {
+        if value < 0 {
+            return Err(VarIntError::Overflow);
+        }
+        let mut result = Vec::with_capacity(Self::MAX_VLONG_LEN);
+        encode_groups(value as u64, &mut result);
+        Ok(result)
+    }
+
+    /// Decodes a vLong, returning it with the number of bytes read.
+    pub fn decode_vlong(bytes: &[u8]) -> Result<(i64, usize), VarIntError> {
+        let (value, len) = decode_groups(bytes, Self::MAX_VLONG_LEN, 0x7f)?;
+        Ok((value as i64, len))
+    }
+}
+
+fn encode_groups(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Decodes at most `max_len` groups, the last one being limited to the bits of `last_mask`.
+fn decode_groups(bytes: &[u8], max_len: usize, last_mask: u8) -> Result<(u64, usize), VarIntError> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().take(max_len).enumerate() {
+        if i == max_len - 1 && byte & !last_mask != 0 {
+            return Err(VarIntError::Overflow);
+        }
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(VarIntError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lucene_vint() {
+        // Examples of the Lucene file format documentation.
+        let vectors: [(i32, &[u8]); 9] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (129, &[0x81, 0x01]),
+            (130, &[0x82, 0x01]),
+            (16383, &[0xff, 0x7f]),
+            (16384, &[0x80, 0x80, 0x01]),
+            (16385, &[0x81, 0x80, 0x01]),
+        ];
+        for (value, bytes) in vectors {
+            assert_eq!(Lucene::encode_vint(value), bytes);
+            assert_eq!(Lucene::decode_vint(bytes), Ok((value, bytes.len())));
+        }
+        let minus_one = [0xff, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(Lucene::encode_vint(-1), minus_one);
+        assert_eq!(Lucene::decode_vint(&minus_one), Ok((-1, 5)));
+        assert_eq!(
+            Lucene::decode_vint(&[0xff, 0xff, 0xff, 0xff, 0x1f]),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(Lucene::decode_vint(&[0x80]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_lucene_vlong() {
+        let max = Lucene::encode_vlong(i64::MAX).unwrap();
+        assert_eq!(max, [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]);
+        assert_eq!(Lucene::decode_vlong(&max), Ok((i64::MAX, 9)));
+        assert_eq!(Lucene::encode_vlong(-1), Err(VarIntError::Overflow));
+        assert_eq!(Lucene::decode_vlong(&[0xff; 9]), Err(VarIntError::Overflow));
+        assert_eq!(Lucene::decode_vlong(&[0x80, 0x01]), Ok((128, 2)));
+    }
+}
@@ -0,0 +1,197 @@
+//! Counters of the streaming and framing layers, for services exporting them to Prometheus or any other
+//! monitoring system.
+//!
+//! A [`Recorder`] installed once with [`set_recorder`] is told about every value decoded by the streaming
+//! functions ([`transcode_stream`](crate::transcode_stream), [`Stats::read_from`](crate::stats::Stats::read_from),
+//! the `_stream` functions of [`bulk`](crate::bulk)), every frame read or written by
+//! [`delimited`](crate::delimited) and [`mux`](crate::mux), every non-canonical encoding rejected by a
+//! [`Decoder`](crate::decoder::Decoder) and every frame rejected by the framing layers, without wrapping any
+//! call. [`Counters`] is a recorder keeping totals in atomics.
+//!
+//! ```
+//! use bitcoin_varint::metrics::{set_recorder, Counters};
+//! use bitcoin_varint::{transcode_stream, VarInt};
+//!
+//! static COUNTERS: Counters = Counters::new();
+//! set_recorder(&COUNTERS);
+//!
+//! let mut output = Vec::new();
+//! transcode_stream(&mut &[0x01, 0xfd, 0x00, 0x01][..], &mut output, VarInt, VarInt).unwrap();
+//! let counters = COUNTERS.snapshot();
+//! assert_eq!((counters.values_decoded, counters.bytes_in), (2, 4));
+//! ```
+
+use crate::VarIntError;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Receives the events of the codec and framing layers. Every method does nothing by default.
+pub trait Recorder: Sync {
+    /// `count` values were decoded from `bytes` bytes of a stream.
+    fn values_decoded(&self, count: u64, bytes: u64) {
+        let _ = (count, bytes);
+    }
+
+    /// A frame of `bytes` bytes, prefix included, was read.
+    fn frame_read(&self, bytes: u64) {
+        let _ = bytes;
+    }
+
+    /// A frame of `bytes` bytes, prefix included, was written.
+    fn frame_written(&self, bytes: u64) {
+        let _ = bytes;
+    }
+
+    /// A value not in its shortest form was rejected.
+    fn non_canonical(&self) {}
+
+    /// A frame was rejected, for being longer than allowed or for a malformed prefix.
+    fn frame_rejected(&self, error: &VarIntError) {
+        let _ = error;
+    }
+}
+
+static RECORDER: OnceLock<&'static dyn Recorder> = OnceLock::new();
+
+/// Installs the recorder of the process, returning `false` when one is already installed.
+pub fn set_recorder(recorder: &'static dyn Recorder) -> bool {
+    RECORDER.set(recorder).is_ok()
+}
+
+pub(crate) fn record(event: impl FnOnce(&dyn Recorder)) {
+    if let Some(recorder) = RECORDER.get() {
+        event(*recorder);
+    }
+}
+
+/// A [`Recorder`] keeping the totals of every event.
+#[derive(Debug, Default)]
+pub struct Counters {
+    values_decoded: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    frames_read: AtomicU64,
+    frames_written: AtomicU64,
+    non_canonical: AtomicU64,
+    frames_rejected: AtomicU64,
+}
+
+/// The totals of [`Counters`] at some point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CounterSnapshot {
+    pub values_decoded: u64,
+    /// Bytes of the values decoded and of the frames read.
+    pub bytes_in: u64,
+    /// Bytes of the frames written.
+    pub bytes_out: u64,
+    pub frames_read: u64,
+    pub frames_written: u64,
+    pub non_canonical: u64,
+    pub frames_rejected: u64,
+}
+
+impl Counters {
+    pub const fn new() -> Self {
+        Counters {
+            values_decoded: AtomicU64::new(0),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            frames_read: AtomicU64::new(0),
+            frames_written: AtomicU64::new(0),
+            non_canonical: AtomicU64::new(0),
+            frames_rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn snapshot(&self) -> CounterSnapshot {
+        CounterSnapshot {
+            values_decoded: self.values_decoded.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            frames_read: self.frames_read.load(Ordering::Relaxed),
+            frames_written: self.frames_written.load(Ordering::Relaxed),
+            non_canonical: self.non_canonical.load(Ordering::Relaxed),
+            frames_rejected: self.frames_rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Recorder for Counters {
+    fn values_decoded(&self, count: u64, bytes: u64) {
+        self.values_decoded.fetch_add(count, Ordering::Relaxed);
+        self.bytes_in.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn frame_read(&self, bytes: u64) {
+        self.frames_read.fetch_add(1, Ordering::Relaxed);
+        self.bytes_in.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn frame_written(&self, bytes: u64) {
+        self.frames_written.fetch_add(1, Ordering::Relaxed);
+        self.bytes_out.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn non_canonical(&self) {
+        self.non_canonical.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn frame_rejected(&self, _error: &VarIntError) {
+        self.frames_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let counters = Counters::new();
+        counters.values_decoded(3, 5);
+        counters.frame_read(10);
+        counters.frame_written(4);
+        counters.non_canonical();
+        counters.frame_rejected(&VarIntError::TooLarge);
+        assert_eq!(
+            counters.snapshot(),
+            CounterSnapshot {
+                values_decoded: 3,
+                bytes_in: 15,
+                bytes_out: 4,
+                frames_read: 1,
+                frames_written: 1,
+                non_canonical: 1,
+                frames_rejected: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_recorder_hooks() {
+        use crate::decoder::Decoder;
+        use crate::delimited::{read_delimited_with, write_delimited_with};
+        use crate::VarInt;
+
+        // The recorder is global to the test binary, so only increases are checked.
+        static COUNTERS: Counters = Counters::new();
+        set_recorder(&COUNTERS);
+        if !std::ptr::addr_eq(*RECORDER.get().unwrap(), &COUNTERS) {
+            return;
+        }
+        let before = COUNTERS.snapshot();
+        let mut stream = Vec::new();
+        write_delimited_with(&VarInt, b"abc", &mut stream).unwrap();
+        read_delimited_with(&VarInt, &mut &stream[..], 16).unwrap();
+        read_delimited_with(&VarInt, &mut &[0x20][..], 16).unwrap_err();
+        Decoder::bitcoin_core()
+            .decode(&[0xfd, 0x01, 0x00])
+            .unwrap_err();
+        let after = COUNTERS.snapshot();
+        assert!(after.frames_written > before.frames_written);
+        assert!(after.bytes_out >= before.bytes_out + 4);
+        assert!(after.frames_read > before.frames_read);
+        assert!(after.frames_rejected > before.frames_rejected);
+        assert!(after.non_canonical > before.non_canonical);
+    }
+}
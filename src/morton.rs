@@ -0,0 +1,108 @@
+//! Pairs of coordinates as a single varint, interleaving their bits into a Morton code (Z-order curve).
+//!
+//! Points close on the grid get close codes, so sorted points keep their locality, and small coordinates
+//! give small codes, so points near the origin take few bytes: (3, 5) is the single byte `0x27`.
+//!
+//! ```
+//! use bitcoin_varint::morton::{decode_pair, encode_pair};
+//!
+//! let bytes = encode_pair(100, 50);
+//! assert_eq!(bytes.len(), 3);
+//! assert_eq!(decode_pair(&bytes), Ok(((100, 50), 3)));
+//! ```
+//!
+//! https://en.wikipedia.org/wiki/Z-order_curve
+
+use crate::bulk::EncodedVarInt;
+use crate::{VarCodec, VarInt, VarIntError};
+
+/// The Morton code of `(x, y)`: the bits of `x` in the even positions, those of `y` in the odd ones.
+pub fn interleave(x: u32, y: u32) -> u64 {
+    spread(x) | spread(y) << 1
+}
+
+/// The coordinates of a Morton code, the inverse of [`interleave`].
+pub fn deinterleave(code: u64) -> (u32, u32) {
+    (compact(code), compact(code >> 1))
+}
+
+/// Encodes the Morton code of `(x, y)` as a compactSize.
+pub fn encode_pair(x: u32, y: u32) -> Vec<u8> {
+    EncodedVarInt::new(interleave(x, y)).to_vec()
+}
+
+/// Decodes a pair encoded by [`encode_pair`], returning it with the number of bytes read.
+pub fn decode_pair(bytes: &[u8]) -> Result<((u32, u32), usize), VarIntError> {
+    decode_pair_with(&VarInt, bytes)
+}
+
+/// [`encode_pair`] with the code encoded by `codec`.
+pub fn encode_pair_with<C: VarCodec + ?Sized>(
+    codec: &C,
+    x: u32,
+    y: u32,
+) -> Result<Vec<u8>, VarIntError> {
+    codec.encode(interleave(x, y))
+}
+
+/// [`decode_pair`] with the code encoded by `codec`.
+pub fn decode_pair_with<C: VarCodec + ?Sized>(
+    codec: &C,
+    bytes: &[u8],
+) -> Result<((u32, u32), usize), VarIntError> {
+    let (code, len) = codec.decode(bytes)?;
+    Ok((deinterleave(code), len))
+}
+
+/// Moves bit `i` of `value` to bit `2i`.
+fn spread(value: u32) -> u64 {
+    let mut x = value as u64;
+    x = (x | x << 16) & 0x0000_ffff_0000_ffff;
+    x = (x | x << 8) & 0x00ff_00ff_00ff_00ff;
+    x = (x | x << 4) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | x << 2) & 0x3333_3333_3333_3333;
+    (x | x << 1) & 0x5555_5555_5555_5555
+}
+
+/// Moves bit `2i` of `code` to bit `i`, dropping the odd bits.
+fn compact(code: u64) -> u32 {
+    let mut x = code & 0x5555_5555_5555_5555;
+    x = (x | x >> 1) & 0x3333_3333_3333_3333;
+    x = (x | x >> 2) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | x >> 4) & 0x00ff_00ff_00ff_00ff;
+    x = (x | x >> 8) & 0x0000_ffff_0000_ffff;
+    (x | x >> 16) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interleave() {
+        assert_eq!(interleave(0b11, 0b101), 0b100111);
+        assert_eq!(interleave(u32::MAX, 0), 0x5555_5555_5555_5555);
+        assert_eq!(interleave(0, u32::MAX), 0xaaaa_aaaa_aaaa_aaaa);
+        assert_eq!(interleave(u32::MAX, u32::MAX), u64::MAX);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (12345, 678910), (u32::MAX, 7)] {
+            assert_eq!(deinterleave(interleave(x, y)), (x, y));
+        }
+    }
+
+    #[test]
+    fn test_encode_pair() {
+        assert_eq!(encode_pair(3, 5), [0x27]);
+        assert_eq!(encode_pair(u32::MAX, u32::MAX).len(), 9);
+        let bytes = encode_pair(u32::MAX, 1);
+        assert_eq!(decode_pair(&bytes), Ok(((u32::MAX, 1), 9)));
+        assert_eq!(decode_pair(&[0xfd, 0x00]), Err(VarIntError::Truncated));
+        #[cfg(feature = "leb128")]
+        {
+            let bytes = encode_pair_with(&crate::Leb128, 300, 200).unwrap();
+            assert_eq!(
+                decode_pair_with(&crate::Leb128, &bytes),
+                Ok(((300, 200), 3))
+            );
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Several logical streams over one byte stream: every frame is a stream id and a payload length, both encoded
+//! by a [`VarCodec`], followed by the payload.
+//!
+//! [`Muxer`] queues the data of every stream and writes it in frames taking turns between the streams, so a
+//! large transfer can't hold back the others. [`Demuxer`] reads the frames back, in the order they were
+//! written or stream by stream.
+//!
+//! ```
+//! use bitcoin_varint::mux::{Demuxer, Muxer};
+//! use bitcoin_varint::VarInt;
+//!
+//! let mut muxer = Muxer::new(VarInt, Vec::new(), 1024);
+//! muxer.send(1, b"hello").unwrap();
+//! muxer.send(2, b"world").unwrap();
+//! muxer.flush().unwrap();
+//!
+//! let bytes = muxer.into_inner();
+//! let mut demuxer = Demuxer::new(VarInt, &bytes[..], 1024);
+//! assert_eq!(demuxer.recv(2).unwrap(), Some(b"world".to_vec()));
+//! assert_eq!(demuxer.recv(1).unwrap(), Some(b"hello".to_vec()));
+//! assert_eq!(demuxer.recv(1).unwrap(), None);
+//! ```
+
+use crate::delimited::{read_prefix, write_prefixed};
+use crate::{VarCodec, VarIntError};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, Read, Write};
+
+/// Writes the data of several streams as interleaved frames of at most `max_frame_len` bytes of payload.
+pub struct Muxer<C, W> {
+    codec: C,
+    writer: W,
+    max_frame_len: usize,
+    /// Streams with queued data, in their turn order, with the data and the part of it already written.
+    pending: VecDeque<(u64, Vec<u8>, usize)>,
+    /// The header of the frame being written, reused for every frame.
+    header: Vec<u8>,
+}
+
+impl<C: VarCodec, W: Write> Muxer<C, W> {
+    /// Frames with `codec`, cutting data into payloads of `max_frame_len` bytes, at least 1.
+    pub fn new(codec: C, writer: W, max_frame_len: usize) -> Self {
+        Muxer {
+            codec,
+            writer,
+            max_frame_len: max_frame_len.max(1),
+            pending: VecDeque::new(),
+            header: Vec::new(),
+        }
+    }
+
+    /// Queues `data` after the data already queued for `stream_id`. An empty `data` queued on its own is
+    /// written as an empty frame.
+    ///
+    /// Stream ids the codec can't encode fail with [`io::ErrorKind::InvalidInput`].
+    pub fn send(&mut self, stream_id: u64, data: &[u8]) -> io::Result<()> {
+        self.codec
+            .encoded_len(stream_id)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        match self.pending.iter_mut().find(|(id, _, _)| *id == stream_id) {
+            Some((_, queued, _)) => queued.extend_from_slice(data),
+            None => self.pending.push_back((stream_id, data.to_vec(), 0)),
+        }
+        Ok(())
+    }
+
+    /// Number of bytes of payload queued and not written yet.
+    pub fn pending_len(&self) -> usize {
+        self.pending
+            .iter()
+            .map(|(_, queued, written)| queued.len() - written)
+            .sum()
+    }
+
+    /// Writes one frame for every stream with queued data in turn, until nothing is left, then flushes the
+    /// writer.
+    ///
+    /// On failure, the frame being written may be partly written. It stays queued, with the data after it.
+    pub fn flush(&mut self) -> io::Result<()> {
+        while let Some((stream_id, queued, written)) = self.pending.pop_front() {
+            let end = queued.len().min(written + self.max_frame_len);
+            if let Err(e) = self.write_frame(stream_id, &queued[written..end]) {
+                self.pending.push_front((stream_id, queued, written));
+                return Err(e);
+            }
+            if end < queued.len() {
+                self.pending.push_back((stream_id, queued, end));
+            }
+        }
+        self.writer.flush()
+    }
+
+    /// Writes a frame right away, ahead of the queued data.
+    pub fn write_frame(&mut self, stream_id: u64, payload: &[u8]) -> io::Result<()> {
+        self.header.clear();
+        self.codec
+            .encode_append(stream_id, &mut self.header)
+            .and_then(|_| {
+                self.codec
+                    .encode_append(payload.len() as u64, &mut self.header)
+            })
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        write_prefixed(&mut self.writer, &self.header, payload)
+    }
+
+    /// Returns the writer, dropping the data not written yet.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads frames written by a [`Muxer`], rejecting payloads longer than `max_frame_len`.
+pub struct Demuxer<C, R> {
+    codec: C,
+    reader: R,
+    max_frame_len: usize,
+    /// Payloads read while looking for another stream, by stream id.
+    buffered: BTreeMap<u64, VecDeque<Vec<u8>>>,
+}
+
+impl<C: VarCodec, R: Read> Demuxer<C, R> {
+    pub fn new(codec: C, reader: R, max_frame_len: usize) -> Self {
+        Demuxer {
+            codec,
+            reader,
+            max_frame_len,
+            buffered: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the next frame, or `None` at the end of the input.
+    ///
+    /// Frames buffered by [`Demuxer::recv`] come first, by stream id, then the frames of the reader in order.
+    pub fn next_frame(&mut self) -> io::Result<Option<(u64, Vec<u8>)>> {
+        if let Some(mut entry) = self.buffered.first_entry() {
+            let stream_id = *entry.key();
+            let payload = entry.get_mut().pop_front();
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+            return Ok(payload.map(|payload| (stream_id, payload)));
+        }
+        self.read_frame()
+    }
+
+    /// Returns the next payload of `stream_id`, or `None` when the input ends without one, buffering the
+    /// frames of other streams read on the way.
+    pub fn recv(&mut self, stream_id: u64) -> io::Result<Option<Vec<u8>>> {
+        if let Some(queue) = self.buffered.get_mut(&stream_id) {
+            let payload = queue.pop_front();
+            if queue.is_empty() {
+                self.buffered.remove(&stream_id);
+            }
+            return Ok(payload);
+        }
+        while let Some((id, payload)) = self.read_frame()? {
+            if id == stream_id {
+                return Ok(Some(payload));
+            }
+            self.buffered.entry(id).or_default().push_back(payload);
+        }
+        Ok(None)
+    }
+
+    /// Number of payloads buffered by [`Demuxer::recv`] and not returned yet.
+    pub fn buffered(&self) -> usize {
+        self.buffered.values().map(VecDeque::len).sum()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads a frame from the reader. An end of input within a frame is reported as
+    /// [`io::ErrorKind::UnexpectedEof`], and a payload longer than the maximum as [`io::ErrorKind::InvalidData`]
+    /// wrapping [`VarIntError::TooLarge`], before anything is allocated.
+    fn read_frame(&mut self) -> io::Result<Option<(u64, Vec<u8>)>> {
+        let Some((stream_id, _id_len)) = read_prefix(&self.codec, &mut self.reader)? else {
+            return Ok(None);
+        };
+        let (len, _len_len) = read_prefix(&self.codec, &mut self.reader)?
+            .ok_or(io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        if len > self.max_frame_len as u64 {
+            #[cfg(feature = "metrics")]
+            crate::metrics::record(|r| r.frame_rejected(&VarIntError::TooLarge));
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                VarIntError::TooLarge,
+            ));
+        }
+        let mut payload = vec![0u8; len as usize];
+        self.reader.read_exact(&mut payload)?;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(|r| r.frame_read((_id_len + _len_len + payload.len()) as u64));
+        Ok(Some((stream_id, payload)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VarInt;
+
+    #[test]
+    fn test_mux_interleaving() {
+        let mut muxer = Muxer::new(VarInt, Vec::new(), 4);
+        muxer.send(7, &[0xaa; 10]).unwrap();
+        muxer.send(3, b"abc").unwrap();
+        muxer.send(7, &[0xbb; 2]).unwrap();
+        muxer.send(5, &[]).unwrap();
+        assert_eq!(muxer.pending_len(), 15);
+        muxer.flush().unwrap();
+        assert_eq!(muxer.pending_len(), 0);
+        let bytes = muxer.into_inner();
+
+        let mut demuxer = Demuxer::new(VarInt, &bytes[..], 4);
+        let mut frames = Vec::new();
+        while let Some((stream_id, payload)) = demuxer.next_frame().unwrap() {
+            frames.push((stream_id, payload.len()));
+        }
+        // The long stream takes its turn with the others instead of going first in full.
+        assert_eq!(frames, [(7, 4), (3, 3), (5, 0), (7, 4), (7, 4)]);
+    }
+
+    #[test]
+    fn test_demux_recv() {
+        let mut muxer = Muxer::new(VarInt, Vec::new(), 2);
+        muxer.send(1, b"abcd").unwrap();
+        muxer.send(2, b"xy").unwrap();
+        muxer.flush().unwrap();
+        let bytes = muxer.into_inner();
+
+        let mut demuxer = Demuxer::new(VarInt, &bytes[..], 2);
+        assert_eq!(demuxer.recv(2).unwrap(), Some(b"xy".to_vec()));
+        assert_eq!(demuxer.buffered(), 1);
+        assert_eq!(demuxer.recv(1).unwrap(), Some(b"ab".to_vec()));
+        assert_eq!(demuxer.recv(2).unwrap(), None);
+        assert_eq!(demuxer.buffered(), 1);
+        assert_eq!(demuxer.next_frame().unwrap(), Some((1, b"cd".to_vec())));
+        assert_eq!(demuxer.next_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn test_demux_errors() {
+        let mut demuxer = Demuxer::new(VarInt, &[0x01, 0x03, 0xaa][..], 16);
+        let err = demuxer.next_frame().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let mut demuxer = Demuxer::new(VarInt, &[0x01][..], 16);
+        let err = demuxer.next_frame().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let mut demuxer = Demuxer::new(VarInt, &[0x01, 0x11][..], 16);
+        let err = demuxer.next_frame().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,131 @@
+//! X.690 object identifiers, as found in DER certificates and keys.
+//!
+//! The body of an OID is a sequence of subidentifiers, each in base 128 most significant group first with the
+//! high bit of every byte but the last set, as [`Vlq`]. DER forbids the leading `0x80` padding that VLQ accepts.
+//! The first two arcs share a subidentifier, `40 * first + second`.
+//!
+//! ```
+//! use bitcoin_varint::oid::{decode_oid, encode_oid};
+//!
+//! // sha256WithRSAEncryption
+//! let body = encode_oid("1.2.840.113549.1.1.11").unwrap();
+//! assert_eq!(body, [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]);
+//! assert_eq!(decode_oid(&body).unwrap(), "1.2.840.113549.1.1.11");
+//! ```
+//!
+//! https://www.itu.int/rec/T-REC-X.690
+
+use crate::vlq::Vlq;
+use crate::VarIntError;
+
+/// Appends a subidentifier to `out`, returning the number of bytes written.
+pub fn encode_subidentifier(value: u64, out: &mut Vec<u8>) -> usize {
+    Vlq::encode_append(value, out)
+}
+
+/// Decodes a subidentifier, returning it with the number of bytes read.
+///
+/// A leading `0x80` byte is [`VarIntError::NonCanonical`].
+pub fn decode_subidentifier(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+    if bytes.first() == Some(&0x80) {
+        return Err(VarIntError::NonCanonical);
+    }
+    Vlq::decode(bytes)
+}
+
+/// Encodes the arcs of an OID into its DER body, without the tag and length.
+///
+/// Fails with [`VarIntError::Malformed`] when there are fewer than two arcs, the first is over 2, or the second
+/// is over 39 under a first arc of 0 or 1.
+pub fn encode_arcs(arcs: &[u64]) -> Result<Vec<u8>, VarIntError> {
+    let [first, second, rest @ ..] = arcs else {
+        return Err(VarIntError::Malformed);
+    };
+    if *first > 2 || (*first < 2 && *second >= 40) {
+        return Err(VarIntError::Malformed);
+    }
+    let combined = (first * 40)
+        .checked_add(*second)
+        .ok_or(VarIntError::Overflow)?;
+    let mut result = Vec::with_capacity(arcs.len() + 1);
+    encode_subidentifier(combined, &mut result);
+    for arc in rest {
+        encode_subidentifier(*arc, &mut result);
+    }
+    Ok(result)
+}
+
+/// Decodes the DER body of an OID into its arcs.
+pub fn decode_arcs(bytes: &[u8]) -> Result<Vec<u64>, VarIntError> {
+    let (combined, mut pos) = decode_subidentifier(bytes)?;
+    let first = (combined / 40).min(2);
+    let mut arcs = vec![first, combined - first * 40];
+    while pos < bytes.len() {
+        let (arc, len) = decode_subidentifier(&bytes[pos..])?;
+        arcs.push(arc);
+        pos += len;
+    }
+    Ok(arcs)
+}
+
+/// Encodes a dotted OID such as `"2.5.4.3"` into its DER body, see [`encode_arcs`] for the errors.
+pub fn encode_oid(oid: &str) -> Result<Vec<u8>, VarIntError> {
+    let arcs = oid
+        .split('.')
+        .map(|arc| {
+            // `u64::from_str` takes a leading `+`, which is not part of the notation.
+            if arc.is_empty() || !arc.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(VarIntError::Malformed);
+            }
+            arc.parse().map_err(|_| VarIntError::Overflow)
+        })
+        .collect::<Result<Vec<u64>, _>>()?;
+    encode_arcs(&arcs)
+}
+
+/// Decodes the DER body of an OID into its dotted form.
+pub fn decode_oid(bytes: &[u8]) -> Result<String, VarIntError> {
+    let arcs = decode_arcs(bytes)?;
+    let arcs: Vec<String> = arcs.iter().map(u64::to_string).collect();
+    Ok(arcs.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oid_round_trip() {
+        let vectors: [(&str, &[u8]); 4] = [
+            // commonName
+            ("2.5.4.3", &[0x55, 0x04, 0x03]),
+            // secp256k1
+            ("1.3.132.0.10", &[0x2b, 0x81, 0x04, 0x00, 0x0a]),
+            ("0.39", &[0x27]),
+            // The second arc is unbounded under a first arc of 2.
+            ("2.999.3", &[0x88, 0x37, 0x03]),
+        ];
+        for (oid, bytes) in vectors {
+            assert_eq!(encode_oid(oid).unwrap(), bytes, "{}", oid);
+            assert_eq!(decode_oid(bytes).unwrap(), oid);
+        }
+    }
+
+    #[test]
+    fn test_oid_errors() {
+        assert_eq!(
+            decode_subidentifier(&[0x80, 0x01]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(decode_arcs(&[0x2a, 0x86]), Err(VarIntError::Truncated));
+        assert_eq!(decode_arcs(&[]), Err(VarIntError::Truncated));
+        for oid in ["1", "3.1", "1.40", "1..2", "1.+2", ""] {
+            assert_eq!(encode_oid(oid), Err(VarIntError::Malformed), "{}", oid);
+        }
+        assert_eq!(
+            encode_oid("1.2.18446744073709551616"),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(encode_arcs(&[2, u64::MAX]), Err(VarIntError::Overflow));
+    }
+}
@@ -0,0 +1,132 @@
+//! Packed repeated fields of protobuf: the byte length of the field, then its values concatenated, all
+//! LEB128.
+//!
+//! The field tag, `field_number << 3 | 2` for a length-delimited field, is left to the caller.
+//!
+//! ```
+//! use bitcoin_varint::packed::{decode_packed, encode_packed};
+//!
+//! // Field 4 of protobuf's example, without its tag 0x22.
+//! let bytes = encode_packed(&[3, 270, 86942]);
+//! assert_eq!(bytes, [0x06, 0x03, 0x8e, 0x02, 0x9e, 0xa7, 0x05]);
+//! assert_eq!(decode_packed(&bytes).unwrap(), (vec![3, 270, 86942], 7));
+//! ```
+//!
+//! https://protobuf.dev/programming-guides/encoding/#packed
+
+use crate::{DecodeError, Leb128, VarIntError};
+
+/// Encodes `values` as a packed field.
+pub fn encode_packed(values: &[u64]) -> Vec<u8> {
+    let mut result = Vec::new();
+    encode_packed_append(values, &mut result);
+    result
+}
+
+/// Appends `values` as a packed field to `out`, returning the number of bytes written.
+pub fn encode_packed_append(values: &[u64], out: &mut Vec<u8>) -> usize {
+    let len: usize = values.iter().map(|value| Leb128::get_size(*value)).sum();
+    out.reserve(Leb128::get_size(len as u64) + len);
+    let mut written = Leb128::encode_append(len as u64, out);
+    for value in values {
+        written += Leb128::encode_append(*value, out);
+    }
+    written
+}
+
+/// Decodes a packed field, returning its values with the number of bytes read.
+///
+/// A value running past the end of the field is [`VarIntError::Malformed`], the field running past the end of
+/// `bytes` [`VarIntError::Truncated`]. Errors are located in `bytes`.
+pub fn decode_packed(bytes: &[u8]) -> Result<(Vec<u64>, usize), DecodeError> {
+    let mut values = Vec::new();
+    let read = for_each_packed(bytes, |value| {
+        values.push(value);
+        Ok(())
+    })?;
+    Ok((values, read))
+}
+
+/// Decodes a packed field into `out` without allocating, returning the number of values with the number of bytes
+/// read.
+///
+/// A field of more values than `out` holds fails with [`VarIntError::TooLarge`] at the first that doesn't fit.
+pub fn decode_packed_into(bytes: &[u8], out: &mut [u64]) -> Result<(usize, usize), DecodeError> {
+    let mut count = 0;
+    let read = for_each_packed(bytes, |value| {
+        *out.get_mut(count).ok_or(VarIntError::TooLarge)? = value;
+        count += 1;
+        Ok(())
+    })?;
+    Ok((count, read))
+}
+
+/// Calls `f` with every value of the packed field at the start of `bytes`, returning the length of the field.
+fn for_each_packed(
+    bytes: &[u8],
+    mut f: impl FnMut(u64) -> Result<(), VarIntError>,
+) -> Result<usize, DecodeError> {
+    let (len, mut pos) = Leb128::decode(bytes).map_err(|e| DecodeError::new(e, bytes, 0, 0))?;
+    let end = usize::try_from(len)
+        .ok()
+        .and_then(|len| pos.checked_add(len))
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| DecodeError::new(VarIntError::Truncated, bytes, 0, 0))?;
+    let field = &bytes[..end];
+    let mut index = 0;
+    while pos < end {
+        let error = |e| DecodeError::new(e, bytes, pos, index);
+        let (value, read) = Leb128::decode(&field[pos..]).map_err(|e| match e {
+            VarIntError::Truncated => error(VarIntError::Malformed),
+            e => error(e),
+        })?;
+        f(value).map_err(error)?;
+        pos += read;
+        index += 1;
+    }
+    Ok(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_round_trip() {
+        let values: Vec<u64> = (0..1000).map(|i| i << (i % 64)).collect();
+        let mut bytes = vec![0x22];
+        let written = encode_packed_append(&values, &mut bytes);
+        assert_eq!(written, bytes.len() - 1);
+        bytes.push(0x2a);
+        assert_eq!(decode_packed(&bytes[1..]).unwrap(), (values, written));
+        assert_eq!(decode_packed(&encode_packed(&[])).unwrap(), (vec![], 1));
+
+        let mut out = [0u64; 4];
+        assert_eq!(
+            decode_packed_into(&[0x02, 0x01, 0x02], &mut out),
+            Ok((2, 3))
+        );
+        assert_eq!(out[..2], [1, 2]);
+        let err = decode_packed_into(&[0x02, 0x01, 0x02], &mut out[..1]).unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::TooLarge, 2, 1)
+        );
+    }
+
+    #[test]
+    fn test_packed_errors() {
+        // The second value runs past the 3 bytes of the field.
+        let err = decode_packed(&[0x03, 0x01, 0x80, 0x80, 0x01]).unwrap_err();
+        assert_eq!(
+            (err.error, err.offset, err.index),
+            (VarIntError::Malformed, 2, 1)
+        );
+        let err = decode_packed(&[0x04, 0x01, 0x02]).unwrap_err();
+        assert_eq!((err.error, err.offset), (VarIntError::Truncated, 0));
+        assert_eq!(
+            decode_packed(&[0x80]).unwrap_err().error,
+            VarIntError::Truncated
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Posting lists of search engines: increasing document ids with the frequency of a term in each.
+//!
+//! After the number of postings, a skip table gives for every block of [`SKIP_INTERVAL`] postings the last
+//! document before it and its offset, so that [`PostingsIter::advance`] jumps to the block of its target
+//! instead of decoding every posting before. Postings then follow as LEB128, in Lucene's layout: the
+//! document delta shifted left, its low bit set when the frequency is 1, else followed by the frequency.
+//!
+//! ```
+//! use bitcoin_varint::postings::{encode_postings, PostingsIter};
+//!
+//! let postings: Vec<(u64, u32)> = (0..1000).map(|i| (i * 3, 1 + i as u32 % 4)).collect();
+//! let bytes = encode_postings(&postings).unwrap();
+//! let mut iter = PostingsIter::new(&bytes).unwrap();
+//! assert_eq!(iter.advance(2000), Some(Ok((2001, 4))));
+//! assert_eq!(iter.next(), Some(Ok((2004, 1))));
+//! ```
+//!
+//! https://lucene.apache.org/core/3_0_3/fileformats.html#Frequencies
+
+use crate::{Leb128, VarIntError};
+
+/// Number of postings between two entries of the skip table.
+pub const SKIP_INTERVAL: usize = 128;
+
+/// Encodes `(document, frequency)` postings, failing with [`VarIntError::OutOfOrder`] when the documents
+/// aren't strictly increasing and with [`VarIntError::Malformed`] on a frequency of zero.
+pub fn encode_postings(postings: &[(u64, u32)]) -> Result<Vec<u8>, VarIntError> {
+    if postings.windows(2).any(|w| w[0].0 >= w[1].0) {
+        return Err(VarIntError::OutOfOrder);
+    }
+    let mut body = Vec::new();
+    let mut skips = Vec::new();
+    let mut last = 0;
+    for (i, (doc, freq)) in postings.iter().enumerate() {
+        if *freq == 0 {
+            return Err(VarIntError::Malformed);
+        }
+        if i > 0 && i % SKIP_INTERVAL == 0 {
+            skips.push((last, body.len() as u64));
+        }
+        let delta = doc - last;
+        if delta > u64::MAX >> 1 {
+            return Err(VarIntError::Overflow);
+        }
+        if *freq == 1 {
+            Leb128::encode_append(delta << 1 | 1, &mut body);
+        } else {
+            Leb128::encode_append(delta << 1, &mut body);
+            Leb128::encode_append(*freq as u64, &mut body);
+        }
+        last = *doc;
+    }
+
+    let mut result = Vec::with_capacity(body.len() + 3 * skips.len() + 10);
+    Leb128::encode_append(postings.len() as u64, &mut result);
+    let (mut last_doc, mut last_offset) = (0, 0);
+    for (doc, offset) in skips {
+        Leb128::encode_append(doc - last_doc, &mut result);
+        Leb128::encode_append(offset - last_offset, &mut result);
+        (last_doc, last_offset) = (doc, offset);
+    }
+    result.extend_from_slice(&body);
+    Ok(result)
+}
+
+/// Decodes all the postings of `bytes`.
+pub fn decode_postings(bytes: &[u8]) -> Result<Vec<(u64, u32)>, VarIntError> {
+    PostingsIter::new(bytes)?.collect()
+}
+
+/// An iterator over encoded postings, which stops after the first error.
+#[derive(Debug, Clone)]
+pub struct PostingsIter<'a> {
+    postings: &'a [u8],
+    /// Last document before every block but the first, with the offset of the block in `postings`.
+    skips: Vec<(u64, usize)>,
+    pos: usize,
+    doc: u64,
+    /// Index of the next posting.
+    index: usize,
+    len: usize,
+    failed: bool,
+}
+
+impl<'a> PostingsIter<'a> {
+    /// Reads the header and the skip table of `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, VarIntError> {
+        let (len, mut pos) = Leb128::decode(bytes)?;
+        // Every posting takes at least one byte.
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|len| *len <= bytes.len() - pos)
+            .ok_or(VarIntError::Truncated)?;
+        let mut skips = Vec::with_capacity(len.saturating_sub(1) / SKIP_INTERVAL);
+        let (mut doc, mut offset) = (0u64, 0u64);
+        for _ in 0..len.saturating_sub(1) / SKIP_INTERVAL {
+            let (doc_delta, read) = Leb128::decode(&bytes[pos..])?;
+            pos += read;
+            let (offset_delta, read) = Leb128::decode(&bytes[pos..])?;
+            pos += read;
+            doc = doc.checked_add(doc_delta).ok_or(VarIntError::Overflow)?;
+            offset = offset
+                .checked_add(offset_delta)
+                .ok_or(VarIntError::Overflow)?;
+            skips.push((doc, offset));
+        }
+        let postings = &bytes[pos..];
+        let skips = skips
+            .into_iter()
+            .map(|(doc, offset)| match usize::try_from(offset) {
+                Ok(offset) if offset < postings.len() => Ok((doc, offset)),
+                _ => Err(VarIntError::Malformed),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(PostingsIter {
+            postings,
+            skips,
+            pos: 0,
+            doc: 0,
+            index: 0,
+            len,
+            failed: false,
+        })
+    }
+
+    /// Moves to the first posting whose document is at least `target`, skipping whole blocks when possible,
+    /// and returns it. Postings already returned aren't returned again, even when `target` is below the last one.
+    pub fn advance(&mut self, target: u64) -> Option<Result<(u64, u32), VarIntError>> {
+        let block = self.skips.partition_point(|(doc, _)| *doc < target);
+        if block > 0 && block * SKIP_INTERVAL > self.index && !self.failed {
+            (self.doc, self.pos) = self.skips[block - 1];
+            self.index = block * SKIP_INTERVAL;
+        }
+        loop {
+            match self.next()? {
+                Ok((doc, _)) if doc < target => continue,
+                result => return Some(result),
+            }
+        }
+    }
+
+    fn read(&mut self) -> Result<(u64, u32), VarIntError> {
+        let (code, read) = Leb128::decode(&self.postings[self.pos..])?;
+        self.pos += read;
+        let freq = if code & 1 == 1 {
+            1
+        } else {
+            let (freq, read) = Leb128::decode(&self.postings[self.pos..])?;
+            self.pos += read;
+            u32::try_from(freq)
+                .ok()
+                .filter(|freq| *freq > 1)
+                .ok_or(VarIntError::Malformed)?
+        };
+        let delta = code >> 1;
+        if delta == 0 && self.index > 0 {
+            return Err(VarIntError::OutOfOrder);
+        }
+        self.doc = self.doc.checked_add(delta).ok_or(VarIntError::Overflow)?;
+        self.index += 1;
+        Ok((self.doc, freq))
+    }
+}
+
+impl Iterator for PostingsIter<'_> {
+    type Item = Result<(u64, u32), VarIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.index >= self.len {
+            return None;
+        }
+        let result = self.read();
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postings_layout() {
+        // Lucene's example: document 7 once, then document 11 three times.
+        let bytes = encode_postings(&[(7, 1), (11, 3)]).unwrap();
+        assert_eq!(bytes, vec![0x02, 0x0f, 0x08, 0x03]);
+        assert_eq!(decode_postings(&bytes), Ok(vec![(7, 1), (11, 3)]));
+        assert_eq!(decode_postings(&[0x00]), Ok(vec![]));
+
+        assert_eq!(
+            encode_postings(&[(7, 1), (7, 2)]),
+            Err(VarIntError::OutOfOrder)
+        );
+        assert_eq!(encode_postings(&[(7, 0)]), Err(VarIntError::Malformed));
+        assert_eq!(
+            decode_postings(&[0x02, 0x0f, 0x01]),
+            Err(VarIntError::OutOfOrder)
+        );
+        assert_eq!(
+            decode_postings(&[0x01, 0x0e, 0x01]),
+            Err(VarIntError::Malformed)
+        );
+        assert!(PostingsIter::new(&[0x03, 0x01]).is_err());
+    }
+
+    #[test]
+    fn test_postings_advance() {
+        let postings: Vec<(u64, u32)> = (0..1000u64).map(|i| (i * i, 1 + (i % 3) as u32)).collect();
+        let bytes = encode_postings(&postings).unwrap();
+        assert_eq!(decode_postings(&bytes).unwrap(), postings);
+
+        let mut iter = PostingsIter::new(&bytes).unwrap();
+        assert_eq!(iter.skips.len(), 7);
+        for target in [0, 1, 2, 16_000, 16_130, 300_000, 998_001] {
+            let expected = postings.iter().find(|(doc, _)| *doc >= target).copied();
+            assert_eq!(iter.advance(target), expected.map(Ok), "{}", target);
+        }
+        // Once past the end, whatever the target.
+        assert_eq!(iter.advance(5), None);
+
+        let mut iter = PostingsIter::new(&bytes).unwrap();
+        assert_eq!(iter.advance(500_000), Some(Ok((708 * 708, 1))));
+        assert_eq!(iter.next(), Some(Ok((709 * 709, 2))));
+        assert_eq!(iter.advance(1 << 40), None);
+    }
+}
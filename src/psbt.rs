@@ -0,0 +1,128 @@
+//! BIP174 PSBT key-value records.
+//!
+//! A PSBT is the magic bytes followed by maps (the global map, then one per input and output), each a sequence of
+//! key-value pairs ended by a 0x00 separator. Keys and values are prefixed by their compactSize length, and a key
+//! starts with its compactSize type.
+//!
+//! ```
+//! use bitcoin_varint::psbt::{read_map, write_pair, write_separator, MAGIC};
+//!
+//! let mut psbt = MAGIC.to_vec();
+//! write_pair(0xfc, b"proprietary", &[0x01], &mut psbt);
+//! write_separator(&mut psbt);
+//! let (pairs, len) = read_map(&psbt[MAGIC.len()..]).unwrap();
+//! assert_eq!(pairs[0].key_type, 0xfc);
+//! assert_eq!(len, psbt.len() - MAGIC.len());
+//! ```
+//!
+//! https://github.com/bitcoin/bips/blob/master/bip-0174.mediawiki
+
+use crate::{VarInt, VarIntError};
+
+/// Bytes every PSBT starts with: "psbt" then 0xff.
+pub const MAGIC: [u8; 5] = *b"psbt\xff";
+
+/// A key-value pair of a PSBT map, borrowing from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PsbtPair<'a> {
+    pub key_type: u64,
+    /// Rest of the key after its type.
+    pub key_data: &'a [u8],
+    pub value: &'a [u8],
+}
+
+/// Appends a key-value pair to `out`.
+pub fn write_pair(key_type: u64, key_data: &[u8], value: &[u8], out: &mut Vec<u8>) {
+    let key_len = VarInt::encoded_len(key_type) + key_data.len();
+    VarInt::encode_append(key_len as u64, out);
+    VarInt::encode_append(key_type, out);
+    out.extend_from_slice(key_data);
+    VarInt::encode_append(value.len() as u64, out);
+    out.extend_from_slice(value);
+}
+
+/// Appends the separator ending a map.
+pub fn write_separator(out: &mut Vec<u8>) {
+    out.push(0x00);
+}
+
+/// Reads a key-value pair, or `None` for the separator ending a map, returning it with the number of bytes read.
+///
+/// A key type running past the end of its key is [`VarIntError::Malformed`].
+pub fn read_pair(bytes: &[u8]) -> Result<(Option<PsbtPair<'_>>, usize), VarIntError> {
+    let (key, mut pos) = read_prefixed(bytes)?;
+    if key.is_empty() {
+        return Ok((None, pos));
+    }
+    let decoded = VarInt::decode_full(key).map_err(|_| VarIntError::Malformed)?;
+    let (value, len) = read_prefixed(&bytes[pos..])?;
+    pos += len;
+    let pair = PsbtPair {
+        key_type: decoded.value,
+        key_data: &key[decoded.len..],
+        value,
+    };
+    Ok((Some(pair), pos))
+}
+
+/// Reads the pairs of a map up to and including its separator, returning them with the number of bytes read.
+pub fn read_map(bytes: &[u8]) -> Result<(Vec<PsbtPair<'_>>, usize), VarIntError> {
+    let mut pairs = Vec::new();
+    let mut pos = 0;
+    loop {
+        let (pair, len) = read_pair(&bytes[pos..])?;
+        pos += len;
+        match pair {
+            Some(pair) => pairs.push(pair),
+            None => return Ok((pairs, pos)),
+        }
+    }
+}
+
+/// Reads a compactSize length and the bytes it announces.
+fn read_prefixed(bytes: &[u8]) -> Result<(&[u8], usize), VarIntError> {
+    let (len, prefix_len) = VarInt::decode_len(bytes)?;
+    let data = bytes
+        .get(prefix_len..prefix_len.saturating_add(len))
+        .ok_or(VarIntError::Truncated)?;
+    Ok((data, prefix_len + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_psbt_maps() {
+        // Global unsigned transaction, then an input map with a partial signature.
+        let mut bytes = Vec::new();
+        write_pair(0x00, &[], &[0x02, 0x00, 0x00, 0x00], &mut bytes);
+        write_separator(&mut bytes);
+        write_pair(0x02, &[0x03; 33], &[0x30; 71], &mut bytes);
+        write_separator(&mut bytes);
+        assert_eq!(bytes[..3], [0x01, 0x00, 0x04]);
+
+        let (global, len) = read_map(&bytes).unwrap();
+        assert_eq!(
+            global,
+            vec![PsbtPair {
+                key_type: 0,
+                key_data: &[],
+                value: &[0x02, 0x00, 0x00, 0x00],
+            }]
+        );
+        let (input, rest) = read_map(&bytes[len..]).unwrap();
+        assert_eq!(input.len(), 1);
+        assert_eq!((input[0].key_type, input[0].key_data.len()), (2, 33));
+        assert_eq!(len + rest, bytes.len());
+    }
+
+    #[test]
+    fn test_psbt_errors() {
+        // The key type claims 3 bytes in a 1 byte key.
+        assert_eq!(read_pair(&[0x01, 0xfd, 0x00]), Err(VarIntError::Malformed));
+        assert_eq!(read_pair(&[0x02, 0x01]), Err(VarIntError::Truncated));
+        assert_eq!(read_map(&[0x01, 0x00, 0x00]), Err(VarIntError::Truncated));
+        assert_eq!(read_pair(&[0x00, 0xaa]), Ok((None, 1)));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::VarIntError;
+
+/// QUIC variable-length integer
+///
+/// The two high bits of the first byte give the length (1, 2, 4 or 8 bytes), the remaining bits
+/// hold the value in big-endian order, so values are limited to 62 bits.
+///
+/// https://www.rfc-editor.org/rfc/rfc9000.html#name-variable-length-integer-enc
+pub struct Quic;
+
+impl Quic {
+    /// Largest value that can be encoded.
+    pub const MAX_VALUE: u64 = (1 << 62) - 1;
+
+    /// Encodes a value, failing with [`VarIntError::Overflow`] above [`Quic::MAX_VALUE`].
+    pub fn encode(value: u64) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::with_capacity(8);
+        Self::encode_append(value, &mut result)?;
+        Ok(result)
+    }
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    pub fn encode_append(value: u64, out: &mut Vec<u8>) -> Result<usize, VarIntError> {
+        let len = Self::get_size(value)?;
+        let tag = (len.trailing_zeros() as u64) << (8 * len - 2);
+        out.extend_from_slice(&(value | tag).to_be_bytes()[8 - len..]);
+        Ok(len)
+    }
+
+    /// Decodes a value, returning it with the number of bytes read.
+    ///
+    /// As allowed by the RFC, values are not required to use their shortest form.
+    pub fn decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let len = 1 << (first >> 6);
+        let payload = bytes.get(..len).ok_or(VarIntError::Truncated)?;
+        let mut buf = [0u8; 8];
+        buf[8 - len..].copy_from_slice(payload);
+        buf[8 - len] &= 0x3f;
+        Ok((u64::from_be_bytes(buf), len))
+    }
+
+    /// Returns the bytes needed to encode this value
+    pub fn get_size(value: u64) -> Result<usize, VarIntError> {
+        match value {
+            0..=0x3f => Ok(1),
+            0x40..=0x3fff => Ok(2),
+            0x4000..=0x3fff_ffff => Ok(4),
+            0x4000_0000..=Self::MAX_VALUE => Ok(8),
+            _ => Err(VarIntError::Overflow),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::hex;
+
+    #[test]
+    fn test_quic_rfc_examples() {
+        // RFC 9000 appendix A.1.
+        let vectors = [
+            ("c2197c5eff14e88c", 151288809941952652),
+            ("9d7f3e7d", 494878333),
+            ("7bbd", 15293),
+            ("25", 37),
+        ];
+        for (bytes, value) in vectors {
+            let bytes = hex(bytes);
+            assert_eq!(Quic::decode(&bytes), Ok((value, bytes.len())));
+            assert_eq!(Quic::encode(value).unwrap(), bytes);
+        }
+        assert_eq!(Quic::decode(&hex("4025")), Ok((37, 2)));
+    }
+
+    #[test]
+    fn test_quic_errors() {
+        assert_eq!(Quic::encode(1 << 62), Err(VarIntError::Overflow));
+        assert_eq!(Quic::decode(&hex("9d7f3e")), Err(VarIntError::Truncated));
+        assert_eq!(Quic::decode(&[]), Err(VarIntError::Truncated));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::VarIntError;
+
+/// What an RDB length field holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RdbLength {
+    Length(u64),
+    /// The `11` type selector: the object that follows is stored in a special format, given by the 6 low bits
+    /// (0, 1 and 2 for 8, 16 and 32 bits integers, 3 for an LZF compressed string).
+    Encoded(u8),
+}
+
+/// Redis RDB length encoding
+///
+/// The two high bits of the first byte select the format: `00` for a 6 bits length, `01` for a 14 bits one
+/// spanning the next byte, `10` followed by 0x80 or 0x81 for a 32 or 64 bits big-endian length in the next
+/// bytes, and `11` for an encoded object.
+///
+/// https://rdb.fnordig.de/file_format.html#length-encoding
+pub struct Rdb;
+
+impl Rdb {
+    const LEN_32: u8 = 0x80;
+    const LEN_64: u8 = 0x81;
+
+    pub fn encode_length(len: u64) -> Vec<u8> {
+        match len {
+            0..=0x3f => vec![len as u8],
+            0x40..=0x3fff => vec![0x40 | (len >> 8) as u8, len as u8],
+            0x4000..=0xffffffff => {
+                let mut result = vec![Self::LEN_32];
+                result.extend_from_slice(&(len as u32).to_be_bytes());
+                result
+            }
+            _ => {
+                let mut result = vec![Self::LEN_64];
+                result.extend_from_slice(&len.to_be_bytes());
+                result
+            }
+        }
+    }
+
+    /// Decodes a length field, returning it with the number of bytes read.
+    ///
+    /// Like Redis, lengths are not required to use their shortest form.
+    pub fn decode_length(bytes: &[u8]) -> Result<(RdbLength, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)?;
+        match first >> 6 {
+            0 => Ok((RdbLength::Length(first as u64), 1)),
+            1 => {
+                let second = *bytes.get(1).ok_or(VarIntError::Truncated)?;
+                let len = ((first & 0x3f) as u64) << 8 | second as u64;
+                Ok((RdbLength::Length(len), 2))
+            }
+            3 => Ok((RdbLength::Encoded(first & 0x3f), 1)),
+            _ => {
+                let size = match first {
+                    Self::LEN_32 => 4,
+                    Self::LEN_64 => 8,
+                    _ => return Err(VarIntError::Malformed),
+                };
+                let payload = bytes.get(1..1 + size).ok_or(VarIntError::Truncated)?;
+                let mut buf = [0u8; 8];
+                buf[8 - size..].copy_from_slice(payload);
+                Ok((RdbLength::Length(u64::from_be_bytes(buf)), 1 + size))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rdb_length() {
+        let vectors: [(u64, &[u8]); 5] = [
+            (10, &[0x0a]),
+            (700, &[0x42, 0xbc]),
+            (16383, &[0x7f, 0xff]),
+            (16384, &[0x80, 0x00, 0x00, 0x40, 0x00]),
+            (1 << 32, &[0x81, 0, 0, 0, 1, 0, 0, 0, 0]),
+        ];
+        for (len, bytes) in vectors {
+            assert_eq!(Rdb::encode_length(len), bytes);
+            assert_eq!(
+                Rdb::decode_length(bytes),
+                Ok((RdbLength::Length(len), bytes.len()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_rdb_special_encodings() {
+        assert_eq!(
+            Rdb::decode_length(&[0xc3, 0x12]),
+            Ok((RdbLength::Encoded(3), 1))
+        );
+        assert_eq!(Rdb::decode_length(&[0x82]), Err(VarIntError::Malformed));
+        assert_eq!(
+            Rdb::decode_length(&[0x80, 0, 0]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(Rdb::decode_length(&[0x41]), Err(VarIntError::Truncated));
+    }
+}
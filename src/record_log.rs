@@ -0,0 +1,413 @@
+//! Append-only log of records, each stored as a compactSize length followed by the payload.
+//!
+//! A crash while appending leaves a partial record at the end of the log. [`recover`]
+//! finds the end of the last complete record so the tail can be truncated before appending again.
+//!
+//! Logs written with [`RecordLog::with_checksums`] end every record with the CRC32C of its prefix and
+//! payload, little-endian, counted in the length. [`checked_iter`] then detects bit rot, and can skip the
+//! records whose checksum doesn't match rather than stop at the first one.
+//! [`RecordLog::open_recovered_with_checksums`] reopens such a log after a crash.
+
+use crate::delimited::write_prefixed;
+use crate::{VarInt, VarIntError};
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+/// Writer appending records to a log.
+pub struct RecordLog<W> {
+    writer: W,
+    /// Bytes written through this writer.
+    written: u64,
+    checksums: bool,
+}
+
+impl<W: Write> RecordLog<W> {
+    pub fn new(writer: W) -> Self {
+        RecordLog {
+            writer,
+            written: 0,
+            checksums: false,
+        }
+    }
+
+    /// A writer ending every record with a CRC32C, to be read with [`checked_iter`].
+    pub fn with_checksums(writer: W) -> Self {
+        RecordLog {
+            checksums: true,
+            ..RecordLog::new(writer)
+        }
+    }
+
+    /// Appends one record, returning its offset relative to where this writer started.
+    pub fn append(&mut self, payload: &[u8]) -> io::Result<u64> {
+        let offset = self.written;
+        if self.checksums {
+            let mut record = VarInt::encode_len(payload.len() + 4)?;
+            record.extend_from_slice(payload);
+            let crc = crc32c(&record);
+            record.extend_from_slice(&crc.to_le_bytes());
+            self.writer.write_all(&record)?;
+            self.written += record.len() as u64;
+        } else {
+            let prefix = VarInt::encode_len(payload.len())?;
+            write_prefixed(&mut self.writer, &prefix, payload)?;
+            self.written += (prefix.len() + payload.len()) as u64;
+        }
+        Ok(offset)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl RecordLog<File> {
+    /// Opens a log file for appending, first truncating any partial record left by a crash.
+    ///
+    /// Returns the writer with the number of bytes dropped from the tail.
+    pub fn open_recovered(path: impl AsRef<std::path::Path>) -> io::Result<(Self, u64)> {
+        Self::open_recovered_as(path.as_ref(), false)
+    }
+
+    /// Same as [`RecordLog::open_recovered`] for a log written with [`RecordLog::with_checksums`], whose
+    /// appended records then also end with their checksum.
+    pub fn open_recovered_with_checksums(
+        path: impl AsRef<std::path::Path>,
+    ) -> io::Result<(Self, u64)> {
+        Self::open_recovered_as(path.as_ref(), true)
+    }
+
+    fn open_recovered_as(path: &std::path::Path, checksums: bool) -> io::Result<(Self, u64)> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let len = file.metadata()?.len();
+        let valid_len = recover_reader(io::BufReader::new(&mut file))?;
+        file.set_len(valid_len)?;
+        let log = if checksums {
+            RecordLog::with_checksums(file)
+        } else {
+            RecordLog::new(file)
+        };
+        Ok((log, len - valid_len))
+    }
+}
+
+/// Result of scanning a log for its last complete record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recovery {
+    /// Number of complete records.
+    pub records: usize,
+    /// Length of the log up to the end of the last complete record.
+    pub valid_len: usize,
+}
+
+/// Iterates over the records of a log held in memory.
+pub fn iter(log: &[u8]) -> RecordIter<'_> {
+    RecordIter { log, pos: 0 }
+}
+
+/// Scans a log and reports where its complete records end. Anything after `valid_len` is a torn write.
+pub fn recover(log: &[u8]) -> Recovery {
+    let mut iter = iter(log);
+    let mut records = 0;
+    while let Some(Ok(_)) = iter.next() {
+        records += 1;
+    }
+    Recovery {
+        records,
+        valid_len: iter.valid_len(),
+    }
+}
+
+/// Streaming version of [`recover`], returning the length up to the end of the last complete record.
+fn recover_reader<R: Read>(mut reader: R) -> io::Result<u64> {
+    let mut valid_len = 0;
+    let mut prefix = [0u8; 9];
+    loop {
+        if reader.read(&mut prefix[..1])? == 0 {
+            return Ok(valid_len);
+        }
+        let prefix_len = match prefix[0] {
+            0xfd => 3,
+            0xfe => 5,
+            0xff => 9,
+            _ => 1,
+        };
+        match reader.read_exact(&mut prefix[1..prefix_len]) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(valid_len),
+            result => result?,
+        }
+        let decoded = VarInt::decode_full(&prefix[..prefix_len]).map_err(io::Error::from)?;
+        if !decoded.canonical {
+            return Ok(valid_len);
+        }
+        let len = decoded.value;
+        let skipped = io::copy(&mut (&mut reader).take(len), &mut io::sink())?;
+        if skipped < len {
+            return Ok(valid_len);
+        }
+        valid_len += prefix_len as u64 + len;
+    }
+}
+
+/// Iterator over the records of a log, see [`iter`].
+///
+/// A truncated tail is reported as a single [`VarIntError::Truncated`] error, after which the iteration stops,
+/// and so is a length prefix not in its shortest form, which [`RecordLog`] never writes, as
+/// [`VarIntError::NonCanonical`].
+pub struct RecordIter<'a> {
+    log: &'a [u8],
+    pos: usize,
+}
+
+impl RecordIter<'_> {
+    /// Length of the log up to the end of the last record returned.
+    pub fn valid_len(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<'a> Iterator for RecordIter<'a> {
+    type Item = Result<&'a [u8], VarIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.log.len() {
+            return None;
+        }
+        let rest = &self.log[self.pos..];
+        let record = VarInt::decode_full(rest).and_then(|decoded| {
+            if !decoded.canonical {
+                return Err(VarIntError::NonCanonical);
+            }
+            let end = usize::try_from(decoded.value)
+                .ok()
+                .and_then(|len| decoded.len.checked_add(len))
+                .filter(|end| *end <= rest.len())
+                .ok_or(VarIntError::Truncated)?;
+            Ok((&rest[decoded.len..end], end))
+        });
+        match record {
+            Ok((payload, end)) => {
+                self.pos += end;
+                Some(Ok(payload))
+            }
+            Err(e) => {
+                // Stop here, leaving `pos` at the end of the last complete record.
+                self.log = &self.log[..self.pos];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterates over the records of a log written with checksums, see [`CheckedRecordIter`].
+pub fn checked_iter(log: &[u8]) -> CheckedRecordIter<'_> {
+    CheckedRecordIter {
+        records: iter(log),
+        skip_corrupt: false,
+        corrupt: 0,
+    }
+}
+
+/// Iterator over the records of a log written with checksums, see [`checked_iter`].
+///
+/// A record whose checksum doesn't match is reported as a [`VarIntError::Malformed`] error, after which the
+/// iteration stops, unless [`skip_corrupt`](CheckedRecordIter::skip_corrupt) was called. A truncated tail
+/// always ends the iteration as with [`RecordIter`].
+pub struct CheckedRecordIter<'a> {
+    records: RecordIter<'a>,
+    skip_corrupt: bool,
+    corrupt: usize,
+}
+
+impl CheckedRecordIter<'_> {
+    /// Recovery mode: records whose checksum doesn't match are skipped and counted instead of ending the
+    /// iteration. Their length prefix is trusted, so a corrupt length still desynchronizes the rest of the log.
+    pub fn skip_corrupt(mut self) -> Self {
+        self.skip_corrupt = true;
+        self
+    }
+
+    /// Number of records skipped so far.
+    pub fn corrupt(&self) -> usize {
+        self.corrupt
+    }
+
+    /// Length of the log up to the end of the last record returned or skipped.
+    pub fn valid_len(&self) -> usize {
+        self.records.valid_len()
+    }
+}
+
+impl<'a> Iterator for CheckedRecordIter<'a> {
+    type Item = Result<&'a [u8], VarIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.records.pos;
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            };
+            let end = self.records.pos;
+            if let Some(payload_len) = record.len().checked_sub(4) {
+                let crc = u32::from_le_bytes(record[payload_len..].try_into().unwrap());
+                if crc32c(&self.records.log[start..end - 4]) == crc {
+                    return Some(Ok(&record[..payload_len]));
+                }
+            }
+            if !self.skip_corrupt {
+                self.records.log = &self.records.log[..start];
+                self.records.pos = start;
+                return Some(Err(VarIntError::Malformed));
+            }
+            self.corrupt += 1;
+        }
+    }
+}
+
+/// CRC32C (Castagnoli) of `bytes`, as used by iSCSI, ext4 and LevelDB logs.
+pub fn crc32c(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, byte| {
+        CRC32C_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0x82f63b78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_log_append_iter() {
+        let mut log = RecordLog::new(Vec::new());
+        assert_eq!(log.append(b"first").unwrap(), 0);
+        assert_eq!(log.append(&[0x2a; 300]).unwrap(), 6);
+        let bytes = log.into_inner();
+        let records: Vec<_> = iter(&bytes).map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![&b"first"[..], &[0x2a; 300][..]]);
+    }
+
+    #[test]
+    fn test_record_log_recover() {
+        let mut log = RecordLog::new(Vec::new());
+        log.append(b"first").unwrap();
+        log.append(b"second").unwrap();
+        let mut bytes = log.into_inner();
+        let complete = bytes.len();
+        bytes.extend_from_slice(&[0xfd, 0x00, 0x01, 0xaa]);
+
+        let recovery = recover(&bytes);
+        assert_eq!(recovery.records, 2);
+        assert_eq!(recovery.valid_len, complete);
+        let mut iter = iter(&bytes);
+        assert_eq!(iter.nth(2), Some(Err(VarIntError::Truncated)));
+        assert_eq!(iter.next(), None);
+
+        // A complete record behind a non-canonical prefix ends the log for both scans.
+        bytes.truncate(complete);
+        bytes.extend_from_slice(&[0xfd, 0x02, 0x00, 0xaa, 0xbb, 0x01, 0xcc]);
+        assert_eq!(recover(&bytes).valid_len, complete);
+        assert_eq!(recover_reader(&bytes[..]).unwrap(), complete as u64);
+        let mut records = super::iter(&bytes);
+        assert_eq!(records.nth(2), Some(Err(VarIntError::NonCanonical)));
+        assert_eq!(records.next(), None);
+        assert_eq!(records.valid_len(), complete);
+    }
+
+    #[test]
+    fn test_record_log_checksums() {
+        assert_eq!(crc32c(b"123456789"), 0xe3069283);
+        assert_eq!(crc32c(&[]), 0);
+
+        let mut log = RecordLog::with_checksums(Vec::new());
+        log.append(b"first").unwrap();
+        let second = log.append(b"second").unwrap();
+        log.append(b"third").unwrap();
+        let mut bytes = log.into_inner();
+        assert_eq!(bytes[0], 5 + 4);
+        let records: Vec<_> = checked_iter(&bytes).map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![&b"first"[..], b"second", b"third"]);
+
+        // A flipped bit in the second payload.
+        bytes[second as usize + 3] ^= 0x10;
+        let mut iter = checked_iter(&bytes);
+        assert_eq!(iter.nth(1), Some(Err(VarIntError::Malformed)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.valid_len(), second as usize);
+
+        let mut iter = checked_iter(&bytes).skip_corrupt();
+        assert_eq!(iter.next(), Some(Ok(&b"first"[..])));
+        assert_eq!(iter.next(), Some(Ok(&b"third"[..])));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.corrupt(), 1);
+        // Records too short to hold a checksum, and a torn tail.
+        bytes.extend_from_slice(&[0x02, 0xaa, 0xbb, 0x09, 0xcc]);
+        let mut iter = checked_iter(&bytes).skip_corrupt();
+        assert_eq!(iter.nth(2), Some(Err(VarIntError::Truncated)));
+        assert_eq!(iter.corrupt(), 2);
+    }
+
+    #[test]
+    fn test_record_log_open_recovered() {
+        let path = std::env::temp_dir().join(format!("varint-record-log-{}", std::process::id()));
+        std::fs::write(&path, [0x01, 0xaa, 0x05, 0xbb]).unwrap();
+        let (mut log, dropped) = RecordLog::open_recovered(&path).unwrap();
+        assert_eq!(dropped, 2);
+        log.append(b"ok").unwrap();
+        drop(log);
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            vec![0x01, 0xaa, 0x02, b'o', b'k']
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_log_open_recovered_with_checksums() {
+        let path = std::env::temp_dir().join(format!(
+            "varint-record-log-checksums-{}",
+            std::process::id()
+        ));
+        let mut log = RecordLog::with_checksums(Vec::new());
+        log.append(b"first").unwrap();
+        let mut bytes = log.into_inner();
+        bytes.extend_from_slice(&[0x09, b's', b'e']);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let (mut log, dropped) = RecordLog::open_recovered_with_checksums(&path).unwrap();
+        assert_eq!(dropped, 3);
+        log.append(b"second").unwrap();
+        drop(log);
+        let bytes = std::fs::read(&path).unwrap();
+        let records: Vec<_> = checked_iter(&bytes).collect();
+        assert_eq!(records, vec![Ok(&b"first"[..]), Ok(b"second")]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+use crate::VarIntError;
+
+/// Kind of an RLP item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RlpKind {
+    String,
+    List,
+}
+
+impl RlpKind {
+    fn offset(self) -> u8 {
+        match self {
+            RlpKind::String => 0x80,
+            RlpKind::List => 0xc0,
+        }
+    }
+}
+
+/// A decoded RLP prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RlpHeader {
+    pub kind: RlpKind,
+    /// Length of the payload following the header.
+    pub len: u64,
+    /// Number of bytes taken by the header, 0 for a single byte below 0x80 which is its own encoding.
+    pub header_size: usize,
+}
+
+/// Ethereum Recursive Length Prefix (length layer only)
+///
+/// Payloads of up to 55 bytes are prefixed by a single byte `offset + len`, longer payloads by `offset + 55 + n`
+/// followed by the length on `n` big-endian bytes. The offset is 0x80 for strings and 0xc0 for lists.
+/// A string made of a single byte below 0x80 is written as is, without any prefix.
+///
+/// https://ethereum.org/en/developers/docs/data-structures-and-encoding/rlp/
+pub struct Rlp;
+
+impl Rlp {
+    /// Returns the prefix bytes for a payload of `len` bytes.
+    ///
+    /// The single byte case has no prefix and is left to the caller, who is the only one knowing the payload.
+    pub fn encode_header(kind: RlpKind, len: u64) -> Vec<u8> {
+        if len <= 55 {
+            return vec![kind.offset() + len as u8];
+        }
+        let len_bytes = len.to_be_bytes();
+        let n = 8 - (len.leading_zeros() as usize / 8);
+        let mut v = vec![kind.offset() + 55 + n as u8];
+        v.extend_from_slice(&len_bytes[8 - n..]);
+        v
+    }
+
+    /// Parses the prefix at the start of `bytes`, rejecting non-canonical forms:
+    /// long forms for lengths up to 55, lengths with leading zero bytes,
+    /// and a one byte string below 0x80 that carries a prefix (checked when the payload byte is present).
+    pub fn decode_header(bytes: &[u8]) -> Result<RlpHeader, VarIntError> {
+        let prefix = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let (kind, offset) = match prefix {
+            x if x < 0x80 => {
+                return Ok(RlpHeader {
+                    kind: RlpKind::String,
+                    len: 1,
+                    header_size: 0,
+                })
+            }
+            x if x < 0xc0 => (RlpKind::String, x - 0x80),
+            x => (RlpKind::List, x - 0xc0),
+        };
+
+        if offset <= 55 {
+            if kind == RlpKind::String && offset == 1 && bytes.get(1).is_some_and(|b| *b < 0x80) {
+                return Err(VarIntError::NonCanonical);
+            }
+            return Ok(RlpHeader {
+                kind,
+                len: offset as u64,
+                header_size: 1,
+            });
+        }
+
+        let n = (offset - 55) as usize;
+        let len_bytes = bytes.get(1..1 + n).ok_or(VarIntError::Truncated)?;
+        if len_bytes[0] == 0 {
+            return Err(VarIntError::NonCanonical);
+        }
+        let mut buf = [0u8; 8];
+        buf[8 - n..].copy_from_slice(len_bytes);
+        let len = u64::from_be_bytes(buf);
+        if len <= 55 {
+            return Err(VarIntError::NonCanonical);
+        }
+        Ok(RlpHeader {
+            kind,
+            len,
+            header_size: 1 + n,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rlp_encode_header() {
+        assert_eq!(Rlp::encode_header(RlpKind::String, 3), vec![0x83]);
+        assert_eq!(Rlp::encode_header(RlpKind::List, 0), vec![0xc0]);
+        assert_eq!(Rlp::encode_header(RlpKind::String, 56), vec![0xb8, 0x38]);
+        assert_eq!(
+            Rlp::encode_header(RlpKind::List, 1024),
+            vec![0xf9, 0x04, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_rlp_decode_header() {
+        let header = Rlp::decode_header(&[0xb8, 0x38]).unwrap();
+        assert_eq!(header.kind, RlpKind::String);
+        assert_eq!(header.len, 56);
+        assert_eq!(header.header_size, 2);
+        assert_eq!(Rlp::decode_header(&[0x0f]).unwrap().header_size, 0);
+        assert_eq!(Rlp::decode_header(&[0xf9, 0x04, 0x00]).unwrap().len, 1024);
+        assert_eq!(
+            Rlp::decode_header(&[0xf9, 0x04]),
+            Err(VarIntError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_rlp_decode_header_non_canonical() {
+        assert_eq!(
+            Rlp::decode_header(&[0x81, 0x05]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(Rlp::decode_header(&[0x81, 0x80]).unwrap().len, 1);
+        assert_eq!(
+            Rlp::decode_header(&[0xb8, 0x05]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(
+            Rlp::decode_header(&[0xb9, 0x00, 0x40]),
+            Err(VarIntError::NonCanonical)
+        );
+    }
+}
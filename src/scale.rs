@@ -0,0 +1,122 @@
+use crate::VarIntError;
+
+/// Polkadot SCALE compact integers (`Compact<u*>`)
+///
+/// The two low bits of the first byte give the mode: `00` for a 6 bits value in the upper bits of a
+/// single byte, `01` and `10` for 14 and 30 bits values on 2 and 4 little-endian bytes, and `11` for
+/// a big integer, the upper 6 bits then telling how many little-endian bytes follow (minus 4).
+///
+/// https://docs.polkadot.com/polkadot-protocol/parachain-basics/data-encoding/#data-types
+pub struct Scale;
+
+impl Scale {
+    /// Maximum number of bytes of an encoded u128.
+    pub const MAX_LEN: usize = 17;
+
+    pub fn encode(value: u128) -> Vec<u8> {
+        match value {
+            0..=0x3f => vec![(value as u8) << 2],
+            0x40..=0x3fff => ((value as u16) << 2 | 0b01).to_le_bytes().to_vec(),
+            0x4000..=0x3fff_ffff => ((value as u32) << 2 | 0b10).to_le_bytes().to_vec(),
+            _ => {
+                let len = 16 - value.leading_zeros() as usize / 8;
+                let mut result = Vec::with_capacity(1 + len);
+                result.push(((len - 4) as u8) << 2 | 0b11);
+                result.extend_from_slice(&value.to_le_bytes()[..len]);
+                result
+            }
+        }
+    }
+
+    /// Decodes a compact integer, returning it with the number of bytes read.
+    ///
+    /// As in `parity-scale-codec`, values must use their shortest form (else [`VarIntError::NonCanonical`]).
+    pub fn decode(bytes: &[u8]) -> Result<(u128, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)?;
+        let (len, min) = match first & 0b11 {
+            0b00 => return Ok(((first >> 2) as u128, 1)),
+            0b01 => (2, 0x40),
+            0b10 => (4, 0x4000),
+            _ => {
+                let len = (first >> 2) as usize + 4;
+                if len > 16 {
+                    return Err(VarIntError::Overflow);
+                }
+                let min = if len == 4 {
+                    0x4000_0000
+                } else {
+                    1 << ((len - 1) * 8)
+                };
+                let payload = bytes.get(1..1 + len).ok_or(VarIntError::Truncated)?;
+                let mut buf = [0u8; 16];
+                buf[..len].copy_from_slice(payload);
+                let value = u128::from_le_bytes(buf);
+                if value < min {
+                    return Err(VarIntError::NonCanonical);
+                }
+                return Ok((value, 1 + len));
+            }
+        };
+        let payload = bytes.get(..len).ok_or(VarIntError::Truncated)?;
+        let mut buf = [0u8; 4];
+        buf[..len].copy_from_slice(payload);
+        let value = (u32::from_le_bytes(buf) >> 2) as u128;
+        if value < min {
+            return Err(VarIntError::NonCanonical);
+        }
+        Ok((value, len))
+    }
+
+    /// [`Scale::decode`] for `Compact<u64>`, failing with [`VarIntError::Overflow`] above `u64::MAX`.
+    pub fn decode_u64(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let (value, len) = Self::decode(bytes)?;
+        let value = u64::try_from(value).map_err(|_| VarIntError::Overflow)?;
+        Ok((value, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_compact() {
+        let vectors: [(u128, &[u8]); 7] = [
+            (0, &[0x00]),
+            (1, &[0x04]),
+            (42, &[0xa8]),
+            (69, &[0x15, 0x01]),
+            (65535, &[0xfe, 0xff, 0x03, 0x00]),
+            (1 << 30, &[0x03, 0x00, 0x00, 0x00, 0x40]),
+            (100000000000000, &[0x0b, 0x00, 0x40, 0x7a, 0x10, 0xf3, 0x5a]),
+        ];
+        for (value, bytes) in vectors {
+            assert_eq!(Scale::encode(value), bytes);
+            assert_eq!(Scale::decode(bytes), Ok((value, bytes.len())));
+        }
+        let max = Scale::encode(u128::MAX);
+        assert_eq!(max.len(), Scale::MAX_LEN);
+        assert_eq!(max[0], 0x33);
+        assert_eq!(Scale::decode(&max), Ok((u128::MAX, 17)));
+        assert_eq!(Scale::decode_u64(&max), Err(VarIntError::Overflow));
+    }
+
+    #[test]
+    fn test_scale_compact_canonical() {
+        assert_eq!(Scale::decode(&[0x05, 0x00]), Err(VarIntError::NonCanonical));
+        assert_eq!(
+            Scale::decode(&[0xfe, 0xff, 0x00, 0x00]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(
+            Scale::decode(&[0x03, 0xff, 0xff, 0xff, 0x3f]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(
+            Scale::decode(&[0x07, 0xff, 0xff, 0xff, 0xff, 0x00]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(Scale::decode(&[0xfe, 0xff]), Err(VarIntError::Truncated));
+        assert_eq!(Scale::decode(&[0x37]), Err(VarIntError::Overflow));
+    }
+}
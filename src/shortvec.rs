@@ -0,0 +1,85 @@
+use crate::VarIntError;
+
+/// Solana compact-u16 ("shortvec") length encoding
+///
+/// LEB128 limited to a u16: at most 3 bytes, the third one carrying the remaining 2 bits.
+/// Used for the lengths of arrays in transaction messages.
+///
+/// https://solana.com/docs/core/transactions#compact-array-format
+pub struct ShortVec;
+
+impl ShortVec {
+    pub const MAX_LEN: usize = 3;
+
+    pub fn encode(value: u16) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::MAX_LEN);
+        let mut value = value;
+        while value >= 0x80 {
+            result.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        result.push(value as u8);
+        result
+    }
+
+    /// Decodes a length, returning it with the number of bytes read.
+    ///
+    /// Following the Solana runtime, a zero byte after the first one (an alias of a shorter encoding)
+    /// is [`VarIntError::NonCanonical`], and a third byte continuing or exceeding the u16 range is
+    /// [`VarIntError::Overflow`].
+    pub fn decode(bytes: &[u8]) -> Result<(u16, usize), VarIntError> {
+        let mut value = 0u16;
+        for (i, byte) in bytes.iter().take(Self::MAX_LEN).enumerate() {
+            if i > 0 && *byte == 0 {
+                return Err(VarIntError::NonCanonical);
+            }
+            if i == Self::MAX_LEN - 1 && *byte > 0x03 {
+                return Err(VarIntError::Overflow);
+            }
+            value |= ((byte & 0x7f) as u16) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok((value, i + 1));
+            }
+        }
+        Err(VarIntError::Truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortvec() {
+        let vectors: [(u16, &[u8]); 7] = [
+            (0, &[0x00]),
+            (0x7f, &[0x7f]),
+            (0x80, &[0x80, 0x01]),
+            (0xff, &[0xff, 0x01]),
+            (0x100, &[0x80, 0x02]),
+            (0x7fff, &[0xff, 0xff, 0x01]),
+            (0xffff, &[0xff, 0xff, 0x03]),
+        ];
+        for (value, bytes) in vectors {
+            assert_eq!(ShortVec::encode(value), bytes);
+            assert_eq!(ShortVec::decode(bytes), Ok((value, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn test_shortvec_errors() {
+        assert_eq!(
+            ShortVec::decode(&[0x80, 0x00]),
+            Err(VarIntError::NonCanonical)
+        );
+        assert_eq!(
+            ShortVec::decode(&[0x80, 0x80, 0x04]),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(
+            ShortVec::decode(&[0x80, 0x80, 0x80, 0x01]),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(ShortVec::decode(&[0x80]), Err(VarIntError::Truncated));
+    }
+}
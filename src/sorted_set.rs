@@ -0,0 +1,204 @@
+//! Sets of integers stored as the gaps between their sorted values, and their union and intersection
+//! computed on that form.
+//!
+//! A set is the compactSize of its smallest value followed by the compactSize of the gap from each value to
+//! the next, so dense sets take about a byte per value. [`union`] and [`intersection`] merge two sets as they
+//! decode them and write the result in the same form, without building the sets of values.
+//!
+//! ```
+//! use bitcoin_varint::sorted_set::{encode_sorted_set, intersection, union, SortedSetIter};
+//!
+//! let a = encode_sorted_set(&[1, 5, 1000]).unwrap();
+//! let b = encode_sorted_set(&[5, 6, 1000, 1 << 40]).unwrap();
+//! let both = intersection(&a, &b).unwrap();
+//! assert_eq!(SortedSetIter::new(&both).collect::<Result<Vec<_>, _>>(), Ok(vec![5, 1000]));
+//! assert_eq!(union(&a, &b).unwrap(), encode_sorted_set(&[1, 5, 6, 1000, 1 << 40]).unwrap());
+//! ```
+
+use crate::{VarInt, VarIntError};
+
+/// Encodes strictly increasing `values` as a set, failing with [`VarIntError::OutOfOrder`] otherwise.
+pub fn encode_sorted_set(values: &[u64]) -> Result<Vec<u8>, VarIntError> {
+    let mut writer = SetWriter::default();
+    for value in values {
+        writer.push(*value)?;
+    }
+    Ok(writer.out)
+}
+
+/// Iterator over the values of an encoded set, see [`encode_sorted_set`].
+///
+/// A gap of 0 fails with [`VarIntError::OutOfOrder`], and values past `u64::MAX` with
+/// [`VarIntError::Overflow`]. Stops after the first error.
+#[derive(Debug, Clone)]
+pub struct SortedSetIter<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    last: Option<u64>,
+}
+
+impl<'a> SortedSetIter<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        SortedSetIter {
+            bytes,
+            pos: 0,
+            last: None,
+        }
+    }
+
+    /// Byte offset of the next gap.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    fn decode_next(&mut self) -> Result<u64, VarIntError> {
+        let decoded = VarInt::decode_full(&self.bytes[self.pos..])?;
+        let value = match self.last {
+            None => decoded.value,
+            Some(_) if decoded.value == 0 => return Err(VarIntError::OutOfOrder),
+            Some(last) => last
+                .checked_add(decoded.value)
+                .ok_or(VarIntError::Overflow)?,
+        };
+        self.pos += decoded.len;
+        self.last = Some(value);
+        Ok(value)
+    }
+}
+
+impl Iterator for SortedSetIter<'_> {
+    type Item = Result<u64, VarIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        let result = self.decode_next();
+        if result.is_err() {
+            self.pos = self.bytes.len();
+        }
+        Some(result)
+    }
+}
+
+/// The union of two encoded sets, encoded.
+///
+/// Once one set is exhausted, the gaps left in the other are copied as they are, without decoding them.
+pub fn union(a: &[u8], b: &[u8]) -> Result<Vec<u8>, VarIntError> {
+    let (mut a, mut b) = (SortedSetIter::new(a), SortedSetIter::new(b));
+    let (mut x, mut y) = (a.next().transpose()?, b.next().transpose()?);
+    let mut writer = SetWriter::default();
+    loop {
+        let (value, rest) = match (x, y) {
+            (Some(v), Some(w)) if v < w => {
+                writer.push(v)?;
+                x = a.next().transpose()?;
+                continue;
+            }
+            (Some(v), Some(w)) if v > w => {
+                writer.push(w)?;
+                y = b.next().transpose()?;
+                continue;
+            }
+            (Some(v), Some(_)) => {
+                writer.push(v)?;
+                (x, y) = (a.next().transpose()?, b.next().transpose()?);
+                continue;
+            }
+            (Some(v), None) => (v, &a),
+            (None, Some(w)) => (w, &b),
+            (None, None) => return Ok(writer.out),
+        };
+        // The gaps after `value` are relative to it, which is also the last value written.
+        writer.push(value)?;
+        writer.out.extend_from_slice(&rest.bytes[rest.pos..]);
+        return Ok(writer.out);
+    }
+}
+
+/// The intersection of two encoded sets, encoded.
+pub fn intersection(a: &[u8], b: &[u8]) -> Result<Vec<u8>, VarIntError> {
+    let (mut a, mut b) = (SortedSetIter::new(a), SortedSetIter::new(b));
+    let (mut x, mut y) = (a.next().transpose()?, b.next().transpose()?);
+    let mut writer = SetWriter::default();
+    while let (Some(v), Some(w)) = (x, y) {
+        if v <= w {
+            x = a.next().transpose()?;
+        }
+        if w <= v {
+            y = b.next().transpose()?;
+        }
+        if v == w {
+            writer.push(v)?;
+        }
+    }
+    Ok(writer.out)
+}
+
+/// Encodes values as gaps as they are pushed.
+#[derive(Default)]
+struct SetWriter {
+    out: Vec<u8>,
+    last: Option<u64>,
+}
+
+impl SetWriter {
+    fn push(&mut self, value: u64) -> Result<(), VarIntError> {
+        let gap = match self.last {
+            None => value,
+            Some(last) if value > last => value - last,
+            Some(_) => return Err(VarIntError::OutOfOrder),
+        };
+        VarInt::encode_append(gap, &mut self.out);
+        self.last = Some(value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> Result<Vec<u64>, VarIntError> {
+        SortedSetIter::new(bytes).collect()
+    }
+
+    #[test]
+    fn test_sorted_set_round_trip() {
+        let values = [0, 1, 2, 0xff, 0x1_0000, u64::MAX];
+        let bytes = encode_sorted_set(&values).unwrap();
+        assert_eq!(bytes[..4], [0x00, 0x01, 0x01, 0xfd]);
+        assert_eq!(decode(&bytes), Ok(values.to_vec()));
+        assert_eq!(decode(&[]), Ok(vec![]));
+        assert_eq!(encode_sorted_set(&[3, 3]), Err(VarIntError::OutOfOrder));
+        assert_eq!(encode_sorted_set(&[3, 2]), Err(VarIntError::OutOfOrder));
+        assert_eq!(decode(&[0x03, 0x00]), Err(VarIntError::OutOfOrder));
+        let overflow = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(decode(&overflow), Err(VarIntError::Overflow));
+        assert_eq!(decode(&[0x01, 0xfd]), Err(VarIntError::Truncated));
+    }
+
+    #[test]
+    fn test_sorted_set_operations() {
+        let a: Vec<u64> = (0..200).map(|i| i * 3).collect();
+        let b: Vec<u64> = (0..150).map(|i| i * 5 + 7).collect();
+        let (ea, eb) = (
+            encode_sorted_set(&a).unwrap(),
+            encode_sorted_set(&b).unwrap(),
+        );
+        let mut expected_union: Vec<u64> = a.iter().chain(&b).copied().collect();
+        expected_union.sort();
+        expected_union.dedup();
+        let expected_intersection: Vec<u64> = a.iter().copied().filter(|v| b.contains(v)).collect();
+        for (x, y) in [(&ea, &eb), (&eb, &ea)] {
+            assert_eq!(decode(&union(x, y).unwrap()), Ok(expected_union.clone()));
+            assert_eq!(
+                decode(&intersection(x, y).unwrap()),
+                Ok(expected_intersection.clone())
+            );
+        }
+        assert_eq!(union(&ea, &[]).unwrap(), ea);
+        assert_eq!(intersection(&ea, &[]).unwrap(), []);
+        assert_eq!(union(&ea, &[0x01, 0x00]), Err(VarIntError::OutOfOrder));
+    }
+}
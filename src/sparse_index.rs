@@ -0,0 +1,281 @@
+//! Sparse index of a file of concatenated varints, kept aside from it.
+//!
+//! Every `interval`-th value is sampled with its byte offset, so that the i-th value is reached by seeking
+//! to the sample before it and decoding at most `interval - 1` values, and so is the first value at least
+//! `x` when the file is sorted. The index takes about `interval` times less space than the file.
+//!
+//! ```
+//! use bitcoin_varint::sparse_index::SparseIndex;
+//! use bitcoin_varint::VarInt;
+//! use std::io::Cursor;
+//!
+//! let values: Vec<u64> = (0..10_000).map(|i| i * i).collect();
+//! let file: Vec<u8> = values.iter().flat_map(|v| VarInt::encode(*v).unwrap()).collect();
+//! let index = SparseIndex::build(&VarInt, file.as_slice(), 64).unwrap();
+//!
+//! let mut reader = Cursor::new(&file);
+//! assert_eq!(index.seek_geq(&VarInt, &mut reader, 1_000_000).unwrap(), Some((1000, 1_000_000)));
+//! let offset = index.seek(&VarInt, &mut reader, 5000).unwrap().unwrap();
+//! assert_eq!(VarInt::decode_full(&file[offset as usize..]).unwrap().value, 25_000_000);
+//! ```
+
+use crate::bulk::skip_varints_stream_with;
+use crate::delimited::read_prefix;
+use crate::{VarCodec, VarInt, VarIntError};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Sampled values of a varint file with their offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseIndex {
+    interval: u64,
+    count: u64,
+    /// Whether the values of the file are non-decreasing, for [`seek_geq`](SparseIndex::seek_geq).
+    sorted: bool,
+    /// Byte offset and value of every `interval`-th value.
+    samples: Vec<(u64, u64)>,
+}
+
+impl SparseIndex {
+    /// Scans the values of `reader` encoded with `codec`, sampling one every `interval`, at least 1.
+    ///
+    /// Reads are one byte at a time, so a [`File`](std::fs::File) is best wrapped in a
+    /// [`BufReader`](io::BufReader).
+    pub fn build<C: VarCodec + ?Sized, R: Read>(
+        codec: &C,
+        mut reader: R,
+        interval: u64,
+    ) -> io::Result<Self> {
+        let interval = interval.max(1);
+        let mut index = SparseIndex {
+            interval,
+            count: 0,
+            sorted: true,
+            samples: Vec::new(),
+        };
+        let (mut offset, mut last) = (0, 0);
+        while let Some((value, len)) = read_prefix(codec, &mut reader)? {
+            if index.count.is_multiple_of(interval) {
+                index.samples.push((offset, value));
+            }
+            index.sorted &= value >= last;
+            last = value;
+            offset += len as u64;
+            index.count += 1;
+        }
+        Ok(index)
+    }
+
+    /// Number of values of the file.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Index and byte offset of the last sample at or before value `i`, `None` past the last value.
+    pub fn locate(&self, i: u64) -> Option<(u64, u64)> {
+        if i >= self.count {
+            return None;
+        }
+        let sample = i / self.interval;
+        Some((sample * self.interval, self.samples[sample as usize].0))
+    }
+
+    /// Index and byte offset of the last sample below `x`, or of the first value, after which the first value
+    /// at least `x` is at most `interval` values away. Fails with [`VarIntError::OutOfOrder`] when the file
+    /// isn't sorted.
+    pub fn locate_geq(&self, x: u64) -> Result<(u64, u64), VarIntError> {
+        if !self.sorted {
+            return Err(VarIntError::OutOfOrder);
+        }
+        let sample = self
+            .samples
+            .partition_point(|(_, value)| *value < x)
+            .saturating_sub(1);
+        let offset = self.samples.get(sample).map_or(0, |(offset, _)| *offset);
+        Ok((sample as u64 * self.interval, offset))
+    }
+
+    /// Positions `reader`, the indexed file, at the start of value `i` and returns its offset, or `None` past
+    /// the last value.
+    pub fn seek<C: VarCodec + ?Sized, R: Read + Seek>(
+        &self,
+        codec: &C,
+        reader: &mut R,
+        i: u64,
+    ) -> io::Result<Option<u64>> {
+        let Some((index, offset)) = self.locate(i) else {
+            return Ok(None);
+        };
+        reader.seek(SeekFrom::Start(offset))?;
+        Ok(Some(
+            offset + skip_varints_stream_with(codec, reader, i - index)?,
+        ))
+    }
+
+    /// Positions `reader`, the indexed file, at the start of the first value at least `x` and returns its
+    /// index with the value, or `None` when all values are below `x`. The file must be sorted.
+    pub fn seek_geq<C: VarCodec + ?Sized, R: Read + Seek>(
+        &self,
+        codec: &C,
+        reader: &mut R,
+        x: u64,
+    ) -> io::Result<Option<(u64, u64)>> {
+        let (mut index, mut offset) = self.locate_geq(x)?;
+        reader.seek(SeekFrom::Start(offset))?;
+        while index < self.count {
+            let (value, len) = next_value(codec, reader)?;
+            if value >= x {
+                reader.seek(SeekFrom::Start(offset))?;
+                return Ok(Some((index, value)));
+            }
+            offset += len as u64;
+            index += 1;
+        }
+        Ok(None)
+    }
+
+    /// Serializes the index as compactSizes: the interval, the number of values, whether they are sorted, the
+    /// number of samples, then the offset and value of every sample as deltas to the previous one, the
+    /// values only when sorted.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        VarInt::encode_append(self.interval, &mut out);
+        VarInt::encode_append(self.count, &mut out);
+        VarInt::encode_append(self.sorted as u64, &mut out);
+        VarInt::encode_append(self.samples.len() as u64, &mut out);
+        let (mut last_offset, mut last_value) = (0, 0);
+        for (offset, value) in &self.samples {
+            VarInt::encode_append(offset - last_offset, &mut out);
+            VarInt::encode_append(value - last_value, &mut out);
+            last_offset = *offset;
+            if self.sorted {
+                last_value = *value;
+            }
+        }
+        out
+    }
+
+    /// Deserializes an index written by [`SparseIndex::to_bytes`], returning it with the number of bytes read.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), VarIntError> {
+        let mut pos = 0;
+        let mut next = || -> Result<u64, VarIntError> {
+            let decoded = VarInt::decode_full(&bytes[pos..])?;
+            pos += decoded.len;
+            Ok(decoded.value)
+        };
+        let interval = next()?;
+        let count = next()?;
+        let sorted = match next()? {
+            0 => false,
+            1 => true,
+            _ => return Err(VarIntError::Malformed),
+        };
+        let len = next()?;
+        if interval == 0 || len != count.div_ceil(interval) {
+            return Err(VarIntError::Malformed);
+        }
+        let mut samples = Vec::with_capacity(len.min(bytes.len() as u64) as usize);
+        let (mut offset, mut value) = (0u64, 0u64);
+        for _ in 0..len {
+            offset = offset.checked_add(next()?).ok_or(VarIntError::Overflow)?;
+            let delta = next()?;
+            value = if sorted {
+                value.checked_add(delta).ok_or(VarIntError::Overflow)?
+            } else {
+                delta
+            };
+            samples.push((offset, value));
+        }
+        let index = SparseIndex {
+            interval,
+            count,
+            sorted,
+            samples,
+        };
+        Ok((index, pos))
+    }
+}
+
+fn next_value<C: VarCodec + ?Sized, R: Read>(
+    codec: &C,
+    reader: &mut R,
+) -> io::Result<(u64, usize)> {
+    read_prefix(codec, reader)?.ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn file(values: &[u64]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|v| VarInt::encode(*v).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_sparse_index_seek() {
+        let values: Vec<u64> = (0..3000u64).map(|i| i * 97).collect();
+        let bytes = file(&values);
+        let index = SparseIndex::build(&VarInt, bytes.as_slice(), 100).unwrap();
+        assert_eq!((index.len(), index.samples.len()), (3000, 30));
+        let mut reader = Cursor::new(&bytes);
+        for i in [0, 1, 99, 100, 2999] {
+            let offset = index.seek(&VarInt, &mut reader, i).unwrap().unwrap();
+            assert_eq!(reader.position(), offset);
+            assert_eq!(
+                VarInt::decode_full(&bytes[offset as usize..])
+                    .unwrap()
+                    .value,
+                values[i as usize]
+            );
+        }
+        assert_eq!(index.seek(&VarInt, &mut reader, 3000).unwrap(), None);
+
+        for (x, expected) in [
+            (0, Some((0, 0))),
+            (98, Some((2, 194))),
+            (9700, Some((100, 9700))),
+        ] {
+            assert_eq!(index.seek_geq(&VarInt, &mut reader, x).unwrap(), expected);
+        }
+        assert_eq!(index.seek_geq(&VarInt, &mut reader, 1 << 40).unwrap(), None);
+        // The reader is left on the value found.
+        index.seek_geq(&VarInt, &mut reader, 200_000 - 5).unwrap();
+        assert_eq!(reader.position() as usize, file(&values[..2062]).len());
+    }
+
+    #[test]
+    fn test_sparse_index_bytes() {
+        let sorted = SparseIndex::build(&VarInt, file(&[1, 5, 5, 9, 300]).as_slice(), 2).unwrap();
+        let unsorted = SparseIndex::build(&VarInt, file(&[9, 5, 70_000]).as_slice(), 2).unwrap();
+        for index in [&sorted, &unsorted] {
+            let bytes = index.to_bytes();
+            assert_eq!(
+                SparseIndex::from_bytes(&bytes),
+                Ok((index.clone(), bytes.len()))
+            );
+        }
+        assert_eq!(
+            sorted.to_bytes(),
+            [0x02, 0x05, 0x01, 0x03, 0x00, 0x01, 0x02, 0x04, 0x02, 0xfd, 0x27, 0x01]
+        );
+        assert_eq!(unsorted.locate_geq(10), Err(VarIntError::OutOfOrder));
+        assert_eq!(
+            SparseIndex::from_bytes(&[0x02, 0x05, 0x01, 0x02]),
+            Err(VarIntError::Malformed)
+        );
+        let empty = SparseIndex::build(&VarInt, &[][..], 8).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.locate(0), None);
+    }
+}
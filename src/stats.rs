@@ -0,0 +1,138 @@
+//! Statistics over a sequence of encoded values, to compare encodings before choosing one.
+//!
+//! ```
+//! use bitcoin_varint::stats::Stats;
+//! use bitcoin_varint::Codec;
+//!
+//! let stats = Stats::from_bytes(&Codec::CompactSize, &[0x01, 0xfd, 0x03, 0x02, 0x2a]).unwrap();
+//! assert_eq!((stats.count, stats.total_len), (3, 5));
+//! # #[cfg(feature = "leb128")]
+//! assert_eq!(stats.projected_len(Codec::Leb128), Some(4));
+//! ```
+
+use crate::codec::for_each_value;
+use crate::{Codec, VarCodec, VarIntError};
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+/// Summary of a sequence of values, see [`Stats::from_bytes`] and [`Stats::read_from`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of values.
+    pub count: u64,
+    /// Total number of bytes of the encoded values.
+    pub total_len: u64,
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    /// Number of values per encoded length.
+    pub len_histogram: BTreeMap<usize, u64>,
+    /// Total length of the values re-encoded with each of [`Codec::ALL`], `None` once a value
+    /// can't be represented by that encoding.
+    projected: [Option<u64>; Codec::ALL.len()],
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats {
+            count: 0,
+            total_len: 0,
+            min: None,
+            max: None,
+            len_histogram: BTreeMap::new(),
+            projected: [Some(0); Codec::ALL.len()],
+        }
+    }
+
+    /// Accounts for one value, encoded on `len` bytes.
+    pub fn add(&mut self, value: u64, len: usize) {
+        self.count += 1;
+        self.total_len += len as u64;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        *self.len_histogram.entry(len).or_default() += 1;
+        for (codec, projected) in Codec::ALL.iter().zip(&mut self.projected) {
+            *projected = projected
+                .zip(codec.encoded_len(value).ok())
+                .map(|(total, len)| total + len as u64);
+        }
+    }
+
+    /// Statistics of a buffer of values concatenated with `codec`.
+    pub fn from_bytes<C: VarCodec + ?Sized>(codec: &C, bytes: &[u8]) -> Result<Self, VarIntError> {
+        let mut stats = Stats::new();
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let (value, len) = codec.decode(&bytes[pos..])?;
+            stats.add(value, len);
+            pos += len;
+        }
+        Ok(stats)
+    }
+
+    /// Statistics of the values read from `reader`, without holding them in memory.
+    ///
+    /// Invalid input is an [`io::ErrorKind::InvalidData`] error, and input ending within a value
+    /// an [`io::ErrorKind::UnexpectedEof`] one.
+    pub fn read_from<C: VarCodec + ?Sized, R: Read>(codec: &C, reader: &mut R) -> io::Result<Self> {
+        let mut stats = Stats::new();
+        for_each_value(codec, reader, |value, len| {
+            stats.add(value, len);
+            Ok(())
+        })?;
+        Ok(stats)
+    }
+
+    /// Total length the values would take with `codec`, or `None` if it can't represent all of them.
+    pub fn projected_len(&self, codec: Codec) -> Option<u64> {
+        let i = Codec::ALL.iter().position(|c| *c == codec)?;
+        self.projected[i]
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bulk::encode_all;
+
+    #[test]
+    #[cfg(all(feature = "leb128", feature = "quic"))]
+    fn test_stats() {
+        let values = [0, 252, 253, 70000, 1 << 40, 1 << 63];
+        let bytes = encode_all(&values);
+        let stats = Stats::from_bytes(&Codec::CompactSize, &bytes).unwrap();
+        assert_eq!(stats.count, 6);
+        assert_eq!(stats.total_len, bytes.len() as u64);
+        assert_eq!((stats.min, stats.max), (Some(0), Some(1 << 63)));
+        let histogram: Vec<_> = stats.len_histogram.clone().into_iter().collect();
+        assert_eq!(histogram, vec![(1, 2), (3, 1), (5, 1), (9, 2)]);
+        assert_eq!(
+            stats.projected_len(Codec::CompactSize),
+            Some(bytes.len() as u64)
+        );
+        assert_eq!(
+            stats.projected_len(Codec::Leb128),
+            Some(1 + 2 + 2 + 3 + 6 + 10)
+        );
+        assert_eq!(stats.projected_len(Codec::Quic), None);
+    }
+
+    #[test]
+    fn test_stats_read_from() {
+        let bytes = encode_all(&[1, 2, 300]);
+        let stats = Stats::read_from(&Codec::CompactSize, &mut bytes.as_slice()).unwrap();
+        assert_eq!(
+            stats,
+            Stats::from_bytes(&Codec::CompactSize, &bytes).unwrap()
+        );
+        assert_eq!(Stats::new().projected_len(Codec::CompactSize), Some(0));
+
+        let err = Stats::read_from(&Codec::CompactSize, &mut &bytes[..4]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
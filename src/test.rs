@@ -1,3 +1,45 @@
+//! Helpers shared by the tests of the other modules.
+
+#[cfg(test)]
+use std::io::{self, Read, Write};
+
+/// Bytes of a string of hex digits.
+#[cfg(test)]
+pub(crate) fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// A reader or writer passing at most `limit` bytes per call to `inner`, so that values straddle calls.
+#[cfg(test)]
+#[cfg_attr(not(feature = "leb128"), allow(dead_code))]
+pub(crate) struct Trickle<T> {
+    pub inner: T,
+    pub limit: usize,
+}
+
+#[cfg(test)]
+impl<R: Read> Read for Trickle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.limit);
+        self.inner.read(&mut buf[..n])
+    }
+}
+
+#[cfg(test)]
+impl<W: Write> Write for Trickle<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.limit);
+        self.inner.write(&buf[..n])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::VarInt;
@@ -9,7 +51,7 @@ mod tests {
 
     #[test]
     fn test_varint_decode() {
-        assert_eq!(VarInt::decode(&vec![0xfd, 3, 2]).unwrap(), 515);
+        assert_eq!(VarInt::decode(&[0xfd, 3, 2]).unwrap(), 515);
     }
 
     #[test]
@@ -0,0 +1,494 @@
+#[cfg(feature = "bigsize")]
+use crate::BigSize;
+use crate::{VarCodec, VarIntError};
+
+/// A single type-length-value record borrowed from the decoded stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlvRecord<'a> {
+    pub tlv_type: u64,
+    pub value: &'a [u8],
+}
+
+/// Lightning Network TLV stream
+///
+/// A sequence of records, each made of a BigSize type, a BigSize length and the value bytes.
+/// Types must be strictly increasing, and following the "it's ok to be odd" rule,
+/// unknown even types must make the reader fail while unknown odd types are ignored.
+///
+/// https://github.com/lightning/bolts/blob/master/01-messaging.md#type-length-value-format
+#[cfg(feature = "bigsize")]
+pub struct TlvStream;
+
+#[cfg(feature = "bigsize")]
+impl TlvStream {
+    /// Encodes records given in strictly increasing type order.
+    pub fn encode(records: &[(u64, &[u8])]) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::new();
+        let mut last = None;
+        for (tlv_type, value) in records {
+            if last.is_some_and(|last| *tlv_type <= last) {
+                return Err(VarIntError::OutOfOrder);
+            }
+            last = Some(*tlv_type);
+            result.extend_from_slice(&BigSize::encode(*tlv_type));
+            let len = u64::try_from(value.len()).map_err(|_| VarIntError::TooLarge)?;
+            result.extend_from_slice(&BigSize::encode(len));
+            result.extend_from_slice(value);
+        }
+        Ok(result)
+    }
+
+    /// Iterates over the records of a stream, checking its structure (encoding, lengths and ordering).
+    pub fn iter(bytes: &[u8]) -> TlvIter<'_> {
+        TlvIter {
+            bytes,
+            last: None,
+            failed: false,
+        }
+    }
+
+    /// Decodes every record of a stream without any knowledge of the types.
+    pub fn decode(bytes: &[u8]) -> Result<Vec<TlvRecord<'_>>, VarIntError> {
+        Self::iter(bytes).collect()
+    }
+
+    /// Decodes a stream following the "it's ok to be odd" rule: records whose type is unknown to `is_known`
+    /// are skipped when odd and make the decoding fail when even.
+    pub fn decode_known(
+        bytes: &[u8],
+        is_known: impl Fn(u64) -> bool,
+    ) -> Result<Vec<TlvRecord<'_>>, VarIntError> {
+        let mut records = Vec::new();
+        for record in Self::iter(bytes) {
+            let record = record?;
+            if is_known(record.tlv_type) {
+                records.push(record);
+            } else if record.tlv_type % 2 == 0 {
+                return Err(VarIntError::UnknownEvenType(record.tlv_type));
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// Iterator over the records of a TLV stream, stops after the first error.
+#[cfg(feature = "bigsize")]
+pub struct TlvIter<'a> {
+    bytes: &'a [u8],
+    last: Option<u64>,
+    failed: bool,
+}
+
+#[cfg(feature = "bigsize")]
+impl<'a> TlvIter<'a> {
+    fn read_record(&mut self) -> Result<TlvRecord<'a>, VarIntError> {
+        let (tlv_type, type_len) = BigSize::decode(self.bytes)?;
+        let (len, len_len) = BigSize::decode(&self.bytes[type_len..])?;
+        if self.last.is_some_and(|last| tlv_type <= last) {
+            return Err(VarIntError::OutOfOrder);
+        }
+        let start = type_len + len_len;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(VarIntError::Truncated)?;
+        let value = &self.bytes[start..end];
+        self.bytes = &self.bytes[end..];
+        self.last = Some(tlv_type);
+        Ok(TlvRecord { tlv_type, value })
+    }
+}
+
+#[cfg(feature = "bigsize")]
+impl<'a> Iterator for TlvIter<'a> {
+    type Item = Result<TlvRecord<'a>, VarIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.bytes.is_empty() {
+            return None;
+        }
+        let record = self.read_record();
+        self.failed = record.is_err();
+        Some(record)
+    }
+}
+
+/// Framing of generic TLV records (type, length, value) with the type and length in any [`VarCodec`],
+/// without the ordering rules of [`TlvStream`].
+///
+/// The methods follow the contract of `tokio_util::codec::{Encoder, Decoder}`, so wrapping them takes a few lines:
+///
+/// ```ignore
+/// impl<C: VarCodec> Decoder for Framed<C> {
+///     type Item = (u64, Bytes);
+///     type Error = io::Error;
+///
+///     fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+///         let Some((record, len)) = self.0.decode(src)? else {
+///             return Ok(None);
+///         };
+///         let tlv_type = record.tlv_type;
+///         let value = src.split_to(len).split_off(len - record.value.len()).freeze();
+///         Ok(Some((tlv_type, value)))
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlvCodec<C> {
+    codec: C,
+    max_len: usize,
+}
+
+impl<C: VarCodec> TlvCodec<C> {
+    /// Frames records with `codec`, rejecting values longer than `max_len`.
+    pub fn new(codec: C, max_len: usize) -> Self {
+        TlvCodec { codec, max_len }
+    }
+
+    /// Appends a record to `dst`.
+    pub fn encode(
+        &self,
+        tlv_type: u64,
+        value: &[u8],
+        dst: &mut Vec<u8>,
+    ) -> Result<(), VarIntError> {
+        if value.len() > self.max_len {
+            return Err(VarIntError::TooLarge);
+        }
+        let len = u64::try_from(value.len()).map_err(|_| VarIntError::TooLarge)?;
+        self.codec.encode_append(tlv_type, dst)?;
+        self.codec.encode_append(len, dst)?;
+        dst.extend_from_slice(value);
+        Ok(())
+    }
+
+    /// Decodes the record at the start of `src`, returning it with its total length,
+    /// or `None` when `src` doesn't hold all of it yet.
+    ///
+    /// A length above the maximum fails with [`VarIntError::TooLarge`] as soon as the length is read,
+    /// so a peer can't make the caller buffer an oversized record.
+    pub fn decode<'a>(&self, src: &'a [u8]) -> Result<Option<(TlvRecord<'a>, usize)>, VarIntError> {
+        let header = self.codec.decode(src).and_then(|(tlv_type, type_len)| {
+            let (len, len_len) = self.codec.decode(&src[type_len..])?;
+            Ok((tlv_type, len, type_len + len_len))
+        });
+        let (tlv_type, len, start) = match header {
+            Ok(header) => header,
+            Err(VarIntError::Truncated) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let Some(len) = usize::try_from(len).ok().filter(|len| *len <= self.max_len) else {
+            #[cfg(feature = "metrics")]
+            crate::metrics::record(|r| r.frame_rejected(&VarIntError::TooLarge));
+            return Err(VarIntError::TooLarge);
+        };
+        match src[start..].get(..len) {
+            Some(value) => Ok(Some((TlvRecord { tlv_type, value }, start + len))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Generic TLV records with the type in one [`VarCodec`] and the length in another, for the protocols that are
+/// "TLV with a different varint".
+///
+/// Records may come in any type order and repeat, unlike the records of [`TlvStream`]. A record whose value is
+/// itself a TLV stream of the same format is read with [`TlvFormat::iter`] on its value, or found with
+/// [`TlvFormat::get_path`].
+///
+/// ```
+/// use bitcoin_varint::tlv::TlvFormat;
+/// use bitcoin_varint::VarInt;
+///
+/// let format = TlvFormat::new(VarInt, VarInt);
+/// let inner = format.encode(&[(7, b"hi")]).unwrap();
+/// let outer = format.encode(&[(1, &[]), (2, &inner)]).unwrap();
+/// assert_eq!(format.get_path(&outer, &[2, 7]), Ok(Some(&b"hi"[..])));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlvFormat<T, L> {
+    tag_codec: T,
+    len_codec: L,
+}
+
+impl<T: VarCodec, L: VarCodec> TlvFormat<T, L> {
+    pub fn new(tag_codec: T, len_codec: L) -> Self {
+        TlvFormat {
+            tag_codec,
+            len_codec,
+        }
+    }
+
+    /// Appends a record to `out`, returning the number of bytes written.
+    pub fn encode_append(
+        &self,
+        tlv_type: u64,
+        value: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<usize, VarIntError> {
+        let len = u64::try_from(value.len()).map_err(|_| VarIntError::TooLarge)?;
+        let start = out.len();
+        let written = self
+            .tag_codec
+            .encode_append(tlv_type, out)
+            .and_then(|type_len| Ok(type_len + self.len_codec.encode_append(len, out)?));
+        match written {
+            Ok(header_len) => {
+                out.extend_from_slice(value);
+                Ok(header_len + value.len())
+            }
+            Err(e) => {
+                out.truncate(start);
+                Err(e)
+            }
+        }
+    }
+
+    /// Encodes records in the given order.
+    pub fn encode(&self, records: &[(u64, &[u8])]) -> Result<Vec<u8>, VarIntError> {
+        let mut result = Vec::new();
+        for (tlv_type, value) in records {
+            self.encode_append(*tlv_type, value, &mut result)?;
+        }
+        Ok(result)
+    }
+
+    /// Iterates over the records of `bytes`.
+    pub fn iter<'f, 'a>(&'f self, bytes: &'a [u8]) -> TlvFormatIter<'f, 'a, T, L> {
+        TlvFormatIter {
+            format: self,
+            bytes,
+            failed: false,
+        }
+    }
+
+    /// Decodes the records whose type is known to `is_known`, skipping the others without looking at their value.
+    pub fn decode_known<'a>(
+        &self,
+        bytes: &'a [u8],
+        is_known: impl Fn(u64) -> bool,
+    ) -> Result<Vec<TlvRecord<'a>>, VarIntError> {
+        let mut records = Vec::new();
+        for record in self.iter(bytes) {
+            let record = record?;
+            if is_known(record.tlv_type) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Returns the value of the first record of type `tlv_type`, reading the records before it only.
+    pub fn get<'a>(&self, bytes: &'a [u8], tlv_type: u64) -> Result<Option<&'a [u8]>, VarIntError> {
+        for record in self.iter(bytes) {
+            let record = record?;
+            if record.tlv_type == tlv_type {
+                return Ok(Some(record.value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Follows `path` through nested streams: the value of the first record of type `path[0]` is read as a
+    /// stream, in which the first record of type `path[1]` is looked up, and so on. An empty path returns `bytes`.
+    pub fn get_path<'a>(
+        &self,
+        bytes: &'a [u8],
+        path: &[u64],
+    ) -> Result<Option<&'a [u8]>, VarIntError> {
+        let mut value = bytes;
+        for tlv_type in path {
+            match self.get(value, *tlv_type)? {
+                Some(inner) => value = inner,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Iterator over the records of a [`TlvFormat`] stream, stops after the first error.
+pub struct TlvFormatIter<'f, 'a, T, L> {
+    format: &'f TlvFormat<T, L>,
+    bytes: &'a [u8],
+    failed: bool,
+}
+
+impl<'a, T: VarCodec, L: VarCodec> TlvFormatIter<'_, 'a, T, L> {
+    /// The bytes after the records read so far.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    fn read_record(&mut self) -> Result<TlvRecord<'a>, VarIntError> {
+        let (tlv_type, type_len) = self.format.tag_codec.decode(self.bytes)?;
+        let (len, len_len) = self.format.len_codec.decode(&self.bytes[type_len..])?;
+        let start = type_len + len_len;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(VarIntError::Truncated)?;
+        let value = &self.bytes[start..end];
+        self.bytes = &self.bytes[end..];
+        Ok(TlvRecord { tlv_type, value })
+    }
+}
+
+impl<'a, T: VarCodec, L: VarCodec> Iterator for TlvFormatIter<'_, 'a, T, L> {
+    type Item = Result<TlvRecord<'a>, VarIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.bytes.is_empty() {
+            return None;
+        }
+        let record = self.read_record();
+        self.failed = record.is_err();
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "bigsize")]
+    fn test_tlv_encode() {
+        let stream = TlvStream::encode(&[(1, &[0x2a]), (253, &[])]).unwrap();
+        assert_eq!(stream, vec![0x01, 0x01, 0x2a, 0xfd, 0x00, 0xfd, 0x00]);
+        assert_eq!(
+            TlvStream::encode(&[(2, &[]), (2, &[])]),
+            Err(VarIntError::OutOfOrder)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bigsize")]
+    fn test_tlv_decode() {
+        let records = TlvStream::decode(&[0x01, 0x01, 0x2a, 0xfd, 0x00, 0xfd, 0x00]).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].tlv_type, 1);
+        assert_eq!(records[0].value, &[0x2a]);
+        assert_eq!(records[1].tlv_type, 253);
+        assert_eq!(
+            TlvStream::decode(&[0x02, 0x00, 0x01, 0x00]),
+            Err(VarIntError::OutOfOrder)
+        );
+        assert_eq!(
+            TlvStream::decode(&[0x01, 0x02, 0x2a]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(
+            TlvStream::decode(&[0x01, 0xfd]),
+            Err(VarIntError::Truncated)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bigsize")]
+    fn test_tlv_decode_known() {
+        let stream = [0x01, 0x00, 0x03, 0x00, 0x04, 0x01, 0x07];
+        let records = TlvStream::decode_known(&stream, |t| t == 4 || t == 6).unwrap();
+        assert_eq!(
+            records,
+            vec![TlvRecord {
+                tlv_type: 4,
+                value: &[0x07]
+            }]
+        );
+        assert_eq!(
+            TlvStream::decode_known(&[0x02, 0x00], |t| t == 4),
+            Err(VarIntError::UnknownEvenType(2))
+        );
+    }
+
+    #[test]
+    fn test_tlv_codec_framing() {
+        use crate::{Codec, VarInt};
+
+        let framing = TlvCodec::new(VarInt, 1024);
+        let mut stream = Vec::new();
+        framing.encode(1, b"hi", &mut stream).unwrap();
+        framing.encode(300, &[0x55; 200], &mut stream).unwrap();
+        assert_eq!(stream[..4], [0x01, 0x02, b'h', b'i']);
+
+        let (record, len) = framing.decode(&stream).unwrap().unwrap();
+        assert_eq!((record.tlv_type, record.value, len), (1, &b"hi"[..], 4));
+        // Every prefix of the next record asks for more bytes.
+        let rest = &stream[len..];
+        for end in 0..rest.len() {
+            assert_eq!(framing.decode(&rest[..end]), Ok(None));
+        }
+        let (record, len) = framing.decode(rest).unwrap().unwrap();
+        assert_eq!(
+            (record.tlv_type, record.value.len(), len),
+            (300, 200, rest.len())
+        );
+
+        let small = TlvCodec::new(Codec::CompactSize, 16);
+        assert_eq!(small.decode(&[0x01, 0x11]), Err(VarIntError::TooLarge));
+        assert_eq!(
+            small.encode(1, &[0; 17], &mut Vec::new()),
+            Err(VarIntError::TooLarge)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "leb128")]
+    fn test_tlv_format() {
+        use crate::{Leb128, VarInt};
+
+        // A one byte tag and a LEB128 length, as in many binary formats.
+        let format = TlvFormat::new(VarInt, Leb128);
+        let stream = format.encode(&[(3, &[0x55; 200]), (1, b"a")]).unwrap();
+        assert_eq!(stream[..3], [0x03, 0xc8, 0x01]);
+        let records: Vec<_> = format.iter(&stream).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].tlv_type, records[0].value.len()), (3, 200));
+        assert_eq!(
+            format.decode_known(&stream, |t| t == 1),
+            Ok(vec![TlvRecord {
+                tlv_type: 1,
+                value: b"a"
+            }])
+        );
+        assert_eq!(format.get(&stream, 1), Ok(Some(&b"a"[..])));
+        assert_eq!(format.get(&stream, 2), Ok(None));
+
+        let mut iter = format.iter(&[0x01, 0x01, 0x2a, 0x02, 0x05, 0x00]);
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(iter.remaining(), [0x02, 0x05, 0x00]);
+        assert_eq!(iter.next(), Some(Err(VarIntError::Truncated)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "quic")]
+    fn test_tlv_format_nested() {
+        use crate::quic::Quic;
+
+        let format = TlvFormat::new(Quic, Quic);
+        let inner = format.encode(&[(0x40, b"deep")]).unwrap();
+        let middle = format.encode(&[(1, b"x"), (2, &inner)]).unwrap();
+        let outer = format.encode(&[(9, &middle)]).unwrap();
+        assert_eq!(
+            format.get_path(&outer, &[9, 2, 0x40]),
+            Ok(Some(&b"deep"[..]))
+        );
+        assert_eq!(format.get_path(&outer, &[9, 3]), Ok(None));
+        assert_eq!(format.get_path(&outer, &[]), Ok(Some(&outer[..])));
+        // The value of type 1 isn't a stream.
+        assert_eq!(
+            format.get_path(&outer, &[9, 1, 0]),
+            Err(VarIntError::Truncated)
+        );
+        let mut out = vec![0xaa];
+        assert_eq!(
+            format.encode_append(1 << 62, b"", &mut out),
+            Err(VarIntError::Overflow)
+        );
+        assert_eq!(out, [0xaa]);
+    }
+}
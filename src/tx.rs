@@ -0,0 +1,193 @@
+use crate::{VarInt, VarIntError};
+
+/// The role of a compactSize field within a raw transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    InputCount,
+    ScriptSigLength { input: usize },
+    OutputCount,
+    ScriptPubKeyLength { output: usize },
+    WitnessItemCount { input: usize },
+    WitnessItemLength { input: usize, item: usize },
+}
+
+/// A compactSize field found in a raw transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactSizeField {
+    pub kind: FieldKind,
+    /// Position of the field's first byte in the transaction.
+    pub offset: usize,
+    /// Number of bytes taken by the field.
+    pub len: usize,
+    pub value: u64,
+}
+
+/// The compactSize fields of a raw transaction, as found by [`walk_transaction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxLayout {
+    pub fields: Vec<CompactSizeField>,
+    /// Whether the transaction uses the segwit serialization (marker and flag after the version).
+    pub segwit: bool,
+    /// Total size of the transaction, any bytes after it are left untouched.
+    pub len: usize,
+}
+
+/// Walks a serialized transaction and reports the position and value of every compactSize field
+/// (input count, script lengths, output count, witness counts and lengths), skipping over everything else.
+///
+/// https://developer.bitcoin.org/reference/transactions.html#raw-transaction-format
+///
+/// https://github.com/bitcoin/bips/blob/master/bip-0144.mediawiki
+pub fn walk_transaction(tx: &[u8]) -> Result<TxLayout, VarIntError> {
+    let mut cursor = Cursor {
+        bytes: tx,
+        pos: 0,
+        fields: Vec::new(),
+    };
+    cursor.skip(4)?;
+    let segwit = tx.get(4) == Some(&0x00) && tx.get(5).is_some_and(|flag| *flag != 0);
+    if segwit {
+        cursor.skip(2)?;
+    }
+
+    let inputs = cursor.read(FieldKind::InputCount)?;
+    for input in 0..inputs {
+        cursor.skip(36)?;
+        let len = cursor.read(FieldKind::ScriptSigLength { input })?;
+        cursor.skip(len)?;
+        cursor.skip(4)?;
+    }
+
+    let outputs = cursor.read(FieldKind::OutputCount)?;
+    for output in 0..outputs {
+        cursor.skip(8)?;
+        let len = cursor.read(FieldKind::ScriptPubKeyLength { output })?;
+        cursor.skip(len)?;
+    }
+
+    if segwit {
+        for input in 0..inputs {
+            let items = cursor.read(FieldKind::WitnessItemCount { input })?;
+            for item in 0..items {
+                let len = cursor.read(FieldKind::WitnessItemLength { input, item })?;
+                cursor.skip(len)?;
+            }
+        }
+    }
+
+    cursor.skip(4)?;
+    Ok(TxLayout {
+        fields: cursor.fields,
+        segwit,
+        len: cursor.pos,
+    })
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    fields: Vec<CompactSizeField>,
+}
+
+impl Cursor<'_> {
+    fn skip(&mut self, len: usize) -> Result<(), VarIntError> {
+        match self.pos.checked_add(len) {
+            Some(end) if end <= self.bytes.len() => {
+                self.pos = end;
+                Ok(())
+            }
+            _ => Err(VarIntError::Truncated),
+        }
+    }
+
+    fn read(&mut self, kind: FieldKind) -> Result<usize, VarIntError> {
+        let decoded = VarInt::decode_full(&self.bytes[self.pos..])?;
+        self.fields.push(CompactSizeField {
+            kind,
+            offset: self.pos,
+            len: decoded.len,
+            value: decoded.value,
+        });
+        self.pos += decoded.len;
+        // Counts and lengths can't exceed the remaining bytes, which also keeps the loops bounded.
+        match usize::try_from(decoded.value) {
+            Ok(value) if value <= self.bytes.len() - self.pos => Ok(value),
+            _ => Err(VarIntError::Truncated),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::hex;
+
+    #[test]
+    fn test_walk_legacy_transaction() {
+        // One input with a 2 bytes scriptSig, two outputs with 1 and 3 bytes scriptPubKey.
+        let tx = hex(&format!(
+            "01000000{}{}{}{}{}",
+            "01",
+            format!("{}00000000", "11".repeat(32)) + "02abcd" + "ffffffff",
+            "02",
+            "0100000000000000".to_string() + "0151" + "0200000000000000" + "03515253",
+            "00000000"
+        ));
+        let layout = walk_transaction(&tx).unwrap();
+        assert!(!layout.segwit);
+        assert_eq!(layout.len, tx.len());
+        let kinds: Vec<_> = layout.fields.iter().map(|f| f.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                FieldKind::InputCount,
+                FieldKind::ScriptSigLength { input: 0 },
+                FieldKind::OutputCount,
+                FieldKind::ScriptPubKeyLength { output: 0 },
+                FieldKind::ScriptPubKeyLength { output: 1 },
+            ]
+        );
+        assert_eq!(layout.fields[1].offset, 41);
+        assert_eq!(layout.fields[1].value, 2);
+        assert_eq!(layout.fields[4].value, 3);
+    }
+
+    #[test]
+    fn test_walk_segwit_transaction() {
+        let tx = hex(&format!(
+            "020000000001{}{}{}{}{}",
+            "01",
+            format!("{}00000000", "22".repeat(32)) + "00" + "fdffffff",
+            "01",
+            "0100000000000000".to_string() + "0151",
+            "0202aaaa01bb".to_string() + "00000000"
+        ));
+        let layout = walk_transaction(&tx).unwrap();
+        assert!(layout.segwit);
+        assert_eq!(layout.len, tx.len());
+        let witness: Vec<_> = layout.fields[4..]
+            .iter()
+            .map(|f| (f.kind, f.value))
+            .collect();
+        assert_eq!(
+            witness,
+            vec![
+                (FieldKind::WitnessItemCount { input: 0 }, 2),
+                (FieldKind::WitnessItemLength { input: 0, item: 0 }, 2),
+                (FieldKind::WitnessItemLength { input: 0, item: 1 }, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_truncated_transaction() {
+        assert_eq!(
+            walk_transaction(&hex("0100000001")),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(
+            walk_transaction(&hex("01000000fdffff")),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
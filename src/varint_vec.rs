@@ -0,0 +1,207 @@
+use crate::{Leb128, VarIntError};
+
+/// A growable list of u64 stored as concatenated LEB128 values
+///
+/// Small values take a single byte instead of eight, at the cost of slower random access.
+/// The backing buffer can be saved with [`VarIntVec::as_bytes`] and loaded back with [`VarIntVec::from_bytes`].
+///
+/// Random access with [`VarIntVec::get`] scans from the start, unless the vector keeps the offset
+/// of every k-th value (see [`VarIntVec::with_sampling`]), in which case at most k values are decoded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VarIntVec {
+    bytes: Vec<u8>,
+    len: usize,
+    /// Distance between two sampled values, 0 without index.
+    sampling: usize,
+    /// Offset in `bytes` of every `sampling`-th value.
+    offsets: Vec<usize>,
+}
+
+impl VarIntVec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a vector keeping the offset of every `k`-th value, which costs a usize per `k` values.
+    pub fn with_sampling(k: usize) -> Self {
+        VarIntVec {
+            sampling: k,
+            ..Self::default()
+        }
+    }
+
+    /// Builds (or removes, when `k` is 0) the offset index, e.g. after [`VarIntVec::from_bytes`].
+    pub fn set_sampling(&mut self, k: usize) {
+        self.sampling = k;
+        self.offsets.clear();
+        if k == 0 {
+            return;
+        }
+        let mut pos = 0;
+        for i in 0..self.len {
+            if i % k == 0 {
+                self.offsets.push(pos);
+            }
+            pos += Leb128::decode(&self.bytes[pos..]).map_or(0, |(_, len)| len);
+        }
+    }
+
+    pub fn push(&mut self, value: u64) {
+        if self.sampling > 0 && self.len.is_multiple_of(self.sampling) {
+            self.offsets.push(self.bytes.len());
+        }
+        Leb128::encode_append(value, &mut self.bytes);
+        self.len += 1;
+    }
+
+    /// Returns the `i`-th value.
+    pub fn get(&self, i: usize) -> Option<u64> {
+        if i >= self.len {
+            return None;
+        }
+        let (start, skip) = match self.sampling {
+            0 => (0, i),
+            k => (self.offsets[i / k], i % k),
+        };
+        let mut iter = VarIntVecIter {
+            bytes: &self.bytes[start..],
+            remaining: self.len - (i - skip),
+        };
+        iter.nth(skip)
+    }
+
+    /// Number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> VarIntVecIter<'_> {
+        VarIntVecIter {
+            bytes: &self.bytes,
+            remaining: self.len,
+        }
+    }
+
+    /// The encoded values.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Takes a buffer of concatenated LEB128 values, as returned by [`VarIntVec::as_bytes`].
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, VarIntError> {
+        let mut len = 0;
+        let mut pos = 0;
+        while pos < bytes.len() {
+            pos += Leb128::decode(&bytes[pos..])?.1;
+            len += 1;
+        }
+        Ok(VarIntVec {
+            bytes,
+            len,
+            ..Self::default()
+        })
+    }
+}
+
+impl Extend<u64> for VarIntVec {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl FromIterator<u64> for VarIntVec {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut vec = VarIntVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<'a> IntoIterator for &'a VarIntVec {
+    type Item = u64;
+    type IntoIter = VarIntVecIter<'a>;
+
+    fn into_iter(self) -> VarIntVecIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the values of a [`VarIntVec`].
+pub struct VarIntVecIter<'a> {
+    bytes: &'a [u8],
+    remaining: usize,
+}
+
+impl Iterator for VarIntVecIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // The buffer was checked when the vector was built.
+        let (value, len) = Leb128::decode(self.bytes).ok()?;
+        self.bytes = &self.bytes[len..];
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for VarIntVecIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_vec_push_iter() {
+        let mut vec = VarIntVec::new();
+        vec.push(1);
+        vec.push(300);
+        vec.extend([0, u64::MAX]);
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec.as_bytes().len(), 1 + 2 + 1 + 10);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![1, 300, 0, u64::MAX]);
+    }
+
+    #[test]
+    fn test_varint_vec_get() {
+        let values: Vec<u64> = (0..500u64).map(|i| i * i * 31).collect();
+        let mut vec = VarIntVec::with_sampling(16);
+        vec.extend(values.iter().copied());
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(vec.get(i), Some(*value));
+        }
+        assert_eq!(vec.get(values.len()), None);
+
+        let mut loaded = VarIntVec::from_bytes(vec.as_bytes().to_vec()).unwrap();
+        loaded.set_sampling(16);
+        assert_eq!(loaded, vec);
+    }
+
+    #[test]
+    fn test_varint_vec_bytes() {
+        let vec: VarIntVec = (0..1000).collect();
+        let restored = VarIntVec::from_bytes(vec.as_bytes().to_vec()).unwrap();
+        assert_eq!(restored, vec);
+        assert_eq!(restored.get(999), Some(999));
+        assert_eq!(
+            VarIntVec::from_bytes(vec![0x01, 0x80]),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
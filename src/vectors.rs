@@ -0,0 +1,102 @@
+//! Conformance test vectors, shared with the bindings.
+//!
+//! The corpus is the JSON file `vectors/varint.json`: an array of flat objects with the `codec`,
+//! the `hex` encoding, and either the decoded `value` (a decimal string, as JSON numbers can't hold
+//! every u64) or the `error` decoding must fail with. Valid vectors are also the shortest encoding of
+//! their value.
+
+use crate::{Codec, VarIntError};
+
+/// The JSON corpus itself, for bindings running the same vectors.
+pub const CORPUS: &str = include_str!("../vectors/varint.json");
+
+/// One vector of the corpus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestVector {
+    pub codec: Codec,
+    pub bytes: Vec<u8>,
+    /// The value `bytes` decode to, or the decoding error.
+    pub expected: Result<u64, VarIntError>,
+}
+
+/// Iterates over the vectors of [`CORPUS`].
+pub fn test_vectors() -> impl Iterator<Item = TestVector> {
+    objects(CORPUS).into_iter().map(|fields| {
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let codec = match field("codec") {
+            Some("compactsize") => Codec::CompactSize,
+            Some("bigsize") => Codec::BigSize,
+            Some("leb128") => Codec::Leb128,
+            Some("vlq") => Codec::Vlq,
+            Some("quic") => Codec::Quic,
+            codec => panic!("unknown codec {:?} in test vectors", codec),
+        };
+        let hex = field("hex").expect("test vector without hex");
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        let expected = match (field("value"), field("error")) {
+            (Some(value), None) => Ok(value.parse().unwrap()),
+            (None, Some("truncated")) => Err(VarIntError::Truncated),
+            (None, Some("non-canonical")) => Err(VarIntError::NonCanonical),
+            (None, Some("malformed")) => Err(VarIntError::Malformed),
+            (None, Some("overflow")) => Err(VarIntError::Overflow),
+            other => panic!("invalid test vector expectation {:?}", other),
+        };
+        TestVector {
+            codec,
+            bytes,
+            expected,
+        }
+    })
+}
+
+/// Splits the corpus into its objects, as lists of key and value.
+///
+/// Only what the corpus uses is supported: an array of flat objects of strings without escapes.
+fn objects(json: &str) -> Vec<Vec<(String, String)>> {
+    let mut objects = Vec::new();
+    let mut strings = Vec::new();
+    let mut chars = json.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => strings.push(chars.by_ref().take_while(|c| *c != '"').collect::<String>()),
+            '}' => {
+                let fields = strings.chunks(2).map(|kv| (kv[0].clone(), kv[1].clone()));
+                objects.push(fields.collect());
+                strings.clear();
+            }
+            _ => {}
+        }
+    }
+    objects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_conformance() {
+        let mut count = 0;
+        for vector in test_vectors() {
+            let decoded = vector.codec.decode(&vector.bytes);
+            match vector.expected {
+                Ok(value) => {
+                    assert_eq!(decoded, Ok((value, vector.bytes.len())), "{:?}", vector);
+                    let encoded = vector.codec.encode(value);
+                    assert_eq!(encoded, Ok(vector.bytes.clone()), "{:?}", vector);
+                }
+                Err(e) => assert_eq!(decoded, Err(e), "{:?}", vector),
+            }
+            count += 1;
+        }
+        assert_eq!(count, CORPUS.matches("\"codec\"").count());
+    }
+}
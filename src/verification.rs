@@ -0,0 +1,63 @@
+//! Kani proof harnesses, run with `cargo kani`.
+//!
+//! The decoders face untrusted input: these prove they return an error rather than panic on any
+//! slice, and that decoding an encoded value always gives it back.
+//!
+//! https://model-checking.github.io/kani/
+
+use crate::{BigSize, Leb128, VarInt};
+
+/// Longest input worth checking: no decoder looks further than `Leb128::MAX_LEN` bytes.
+const MAX_INPUT: usize = 11;
+
+fn any_slice(bytes: &[u8; MAX_INPUT]) -> &[u8] {
+    let len: usize = kani::any();
+    kani::assume(len <= MAX_INPUT);
+    &bytes[..len]
+}
+
+#[kani::proof]
+fn compact_size_decode_never_panics() {
+    let bytes: [u8; MAX_INPUT] = kani::any();
+    let input = any_slice(&bytes);
+    let _ = VarInt::decode(input);
+    let _ = VarInt::decode_full(input);
+}
+
+#[kani::proof]
+fn compact_size_round_trip() {
+    let value: u64 = kani::any();
+    let encoded = VarInt::encode(value).unwrap();
+    let decoded = VarInt::decode_full(&encoded).unwrap();
+    assert_eq!(decoded.value, value);
+    assert_eq!(decoded.len, encoded.len());
+    assert!(decoded.canonical);
+}
+
+#[kani::proof]
+fn bigsize_decode_never_panics() {
+    let bytes: [u8; MAX_INPUT] = kani::any();
+    let _ = BigSize::decode(any_slice(&bytes));
+}
+
+#[kani::proof]
+fn bigsize_round_trip() {
+    let value: u64 = kani::any();
+    let encoded = BigSize::encode(value);
+    assert_eq!(BigSize::decode(&encoded), Ok((value, encoded.len())));
+}
+
+#[kani::proof]
+#[kani::unwind(12)]
+fn leb128_decode_never_panics() {
+    let bytes: [u8; MAX_INPUT] = kani::any();
+    let _ = Leb128::decode(any_slice(&bytes));
+}
+
+#[kani::proof]
+#[kani::unwind(12)]
+fn leb128_round_trip() {
+    let value: u64 = kani::any();
+    let encoded = Leb128::encode(value);
+    assert_eq!(Leb128::decode(&encoded), Ok((value, encoded.len())));
+}
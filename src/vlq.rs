@@ -0,0 +1,98 @@
+use crate::VarIntError;
+
+/// Variable-length quantity (MIDI, ASN.1 BER tag numbers)
+///
+/// Groups of 7 bits, most significant first, the high bit of each byte telling whether another byte follows.
+/// A u64 takes at most 10 bytes.
+///
+/// https://en.wikipedia.org/wiki/Variable-length_quantity
+pub struct Vlq;
+
+impl Vlq {
+    /// Maximum number of bytes of an encoded u64.
+    pub const MAX_LEN: usize = 10;
+
+    pub fn encode(value: u64) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::MAX_LEN);
+        Self::encode_append(value, &mut result);
+        result
+    }
+
+    /// Appends the encoding of `value` to `out`, returning the number of bytes written.
+    pub fn encode_append(value: u64, out: &mut Vec<u8>) -> usize {
+        let len = Self::get_size(value);
+        for i in (1..len).rev() {
+            out.push((value >> (7 * i)) as u8 | 0x80);
+        }
+        out.push(value as u8 & 0x7f);
+        len
+    }
+
+    /// Decodes a value, returning it with the number of bytes read.
+    ///
+    /// Leading `0x80` bytes (padding) are accepted up to [`Vlq::MAX_LEN`] bytes in all, values over `u64::MAX`
+    /// and longer encodings fail with [`VarIntError::Overflow`].
+    pub fn decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> {
+        let mut value = 0u64;
+        for (i, byte) in bytes.iter().take(Self::MAX_LEN).enumerate() {
+            if value >> 57 != 0 {
+                return Err(VarIntError::Overflow);
+            }
+            value = value << 7 | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                return Ok((value, i + 1));
+            }
+        }
+        if bytes.len() >= Self::MAX_LEN {
+            Err(VarIntError::Overflow)
+        } else {
+            Err(VarIntError::Truncated)
+        }
+    }
+
+    /// Returns the bytes needed to encode this value
+    pub fn get_size(value: u64) -> usize {
+        (64 - (value | 1).leading_zeros() as usize).div_ceil(7)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vlq() {
+        let vectors: [(u64, &[u8]); 6] = [
+            (0, &[0x00]),
+            (0x7f, &[0x7f]),
+            (0x80, &[0x81, 0x00]),
+            (0x2000, &[0xc0, 0x00]),
+            (0x0fffffff, &[0xff, 0xff, 0xff, 0x7f]),
+            (
+                u64::MAX,
+                &[0x81, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f],
+            ),
+        ];
+        for (value, bytes) in vectors {
+            assert_eq!(Vlq::encode(value), bytes);
+            assert_eq!(Vlq::decode(bytes), Ok((value, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn test_vlq_errors() {
+        assert_eq!(Vlq::decode(&[0x80, 0x05]), Ok((5, 2)));
+        assert_eq!(Vlq::decode(&[0x81, 0x80]), Err(VarIntError::Truncated));
+        let mut bytes = vec![0x82];
+        bytes.extend_from_slice(&[0xff; 8]);
+        bytes.push(0x7f);
+        assert_eq!(Vlq::decode(&bytes), Err(VarIntError::Overflow));
+        // Padding up to 10 bytes in all, but not past it.
+        let mut padded = vec![0x80; 9];
+        padded.push(0x05);
+        assert_eq!(Vlq::decode(&padded), Ok((5, Vlq::MAX_LEN)));
+        padded.insert(0, 0x80);
+        assert_eq!(Vlq::decode(&padded), Err(VarIntError::Overflow));
+        assert_eq!(Vlq::decode(&padded[..10]), Err(VarIntError::Overflow));
+    }
+}
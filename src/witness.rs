@@ -0,0 +1,77 @@
+//! SegWit witness stacks: the number of items as a compactSize, then each item prefixed by its length.
+//!
+//! https://github.com/bitcoin/bips/blob/master/bip-0144.mediawiki
+
+use crate::{VarInt, VarIntError};
+
+/// Encodes a witness stack.
+pub fn encode_witness<T: AsRef<[u8]>>(items: &[T]) -> Vec<u8> {
+    let payload: usize = items.iter().map(|item| item.as_ref().len()).sum();
+    let mut result = Vec::with_capacity(9 * (items.len() + 1) + payload);
+    VarInt::encode_append(items.len() as u64, &mut result);
+    for item in items {
+        let item = item.as_ref();
+        VarInt::encode_append(item.len() as u64, &mut result);
+        result.extend_from_slice(item);
+    }
+    result
+}
+
+/// Decodes a witness stack into subslices of `bytes`, returning them with the number of bytes read.
+pub fn decode_witness_ref(bytes: &[u8]) -> Result<(Vec<&[u8]>, usize), VarIntError> {
+    let (count, mut pos) = VarInt::decode_len(bytes)?;
+    // Every item takes at least its length byte, which bounds the allocation by the input size.
+    if count > bytes.len() - pos {
+        return Err(VarIntError::Truncated);
+    }
+    let mut items = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (len, prefix_len) = VarInt::decode_len(&bytes[pos..])?;
+        pos += prefix_len;
+        let item = bytes
+            .get(pos..pos.saturating_add(len))
+            .ok_or(VarIntError::Truncated)?;
+        items.push(item);
+        pos += len;
+    }
+    Ok((items, pos))
+}
+
+/// Decodes a witness stack into owned items, returning them with the number of bytes read.
+pub fn decode_witness(bytes: &[u8]) -> Result<(Vec<Vec<u8>>, usize), VarIntError> {
+    let (items, len) = decode_witness_ref(bytes)?;
+    Ok((items.into_iter().map(<[u8]>::to_vec).collect(), len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_witness_round_trip() {
+        // P2WPKH: a 71 bytes signature and a 33 bytes public key.
+        let items = vec![vec![0x30; 71], vec![0x02; 33]];
+        let bytes = encode_witness(&items);
+        assert_eq!(bytes.len(), 1 + 1 + 71 + 1 + 33);
+        assert_eq!(bytes[..2], [0x02, 0x47]);
+        assert_eq!(decode_witness(&bytes), Ok((items, bytes.len())));
+
+        let empty: [&[u8]; 1] = [&[]];
+        assert_eq!(encode_witness(&empty), vec![0x01, 0x00]);
+        assert_eq!(encode_witness::<&[u8]>(&[]), vec![0x00]);
+    }
+
+    #[test]
+    fn test_witness_ref_and_errors() {
+        let bytes = [0x02, 0x01, 0xaa, 0x02, 0xbb, 0xcc, 0xff];
+        let (items, len) = decode_witness_ref(&bytes).unwrap();
+        assert_eq!(items, vec![&[0xaa][..], &[0xbb, 0xcc]]);
+        assert_eq!(len, 6);
+        assert_eq!(decode_witness_ref(&bytes[..5]), Err(VarIntError::Truncated));
+        // An item count larger than the input can hold is rejected before allocating.
+        assert_eq!(
+            decode_witness_ref(&[0xfe, 0xff, 0xff, 0xff, 0x7f]),
+            Err(VarIntError::Truncated)
+        );
+    }
+}
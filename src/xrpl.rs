@@ -0,0 +1,82 @@
+use crate::VarIntError;
+
+/// XRP Ledger length prefix of variable-length fields (blobs, accounts)
+///
+/// Lengths up to 192 take one byte. Lengths up to 12480 take two bytes, the first one from 193 to 240,
+/// and lengths up to 918744 take three, the first one from 241 to 254, each range starting where the
+/// previous one ends.
+///
+/// https://xrpl.org/docs/references/protocol/binary-format#length-prefixing
+pub struct Xrpl;
+
+impl Xrpl {
+    /// Largest length that can be encoded.
+    pub const MAX_LENGTH: usize = 918744;
+
+    /// Encodes a length, failing with [`VarIntError::Overflow`] above [`Xrpl::MAX_LENGTH`].
+    pub fn encode_length(len: usize) -> Result<Vec<u8>, VarIntError> {
+        match len {
+            0..=192 => Ok(vec![len as u8]),
+            193..=12480 => {
+                let len = len - 193;
+                Ok(vec![193 + (len >> 8) as u8, len as u8])
+            }
+            12481..=Self::MAX_LENGTH => {
+                let len = len - 12481;
+                Ok(vec![241 + (len >> 16) as u8, (len >> 8) as u8, len as u8])
+            }
+            _ => Err(VarIntError::Overflow),
+        }
+    }
+
+    /// Decodes a length prefix, returning it with the number of bytes read.
+    pub fn decode_length(bytes: &[u8]) -> Result<(usize, usize), VarIntError> {
+        let first = *bytes.first().ok_or(VarIntError::Truncated)? as usize;
+        match first {
+            0..=192 => Ok((first, 1)),
+            193..=240 => {
+                let second = *bytes.get(1).ok_or(VarIntError::Truncated)? as usize;
+                Ok((193 + ((first - 193) << 8) + second, 2))
+            }
+            241..=254 => {
+                let rest = bytes.get(1..3).ok_or(VarIntError::Truncated)?;
+                let len =
+                    12481 + ((first - 241) << 16) + ((rest[0] as usize) << 8) + rest[1] as usize;
+                Ok((len, 3))
+            }
+            _ => Err(VarIntError::Malformed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xrpl_length() {
+        let vectors: [(usize, &[u8]); 6] = [
+            (0, &[0x00]),
+            (192, &[0xc0]),
+            (193, &[0xc1, 0x00]),
+            (12480, &[0xf0, 0xff]),
+            (12481, &[0xf1, 0x00, 0x00]),
+            (918744, &[0xfe, 0xd4, 0x17]),
+        ];
+        for (len, bytes) in vectors {
+            assert_eq!(Xrpl::encode_length(len).unwrap(), bytes);
+            assert_eq!(Xrpl::decode_length(bytes), Ok((len, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn test_xrpl_length_errors() {
+        assert_eq!(Xrpl::encode_length(918745), Err(VarIntError::Overflow));
+        assert_eq!(Xrpl::decode_length(&[0xff]), Err(VarIntError::Malformed));
+        assert_eq!(
+            Xrpl::decode_length(&[0xf1, 0x00]),
+            Err(VarIntError::Truncated)
+        );
+        assert_eq!(Xrpl::decode_length(&[]), Err(VarIntError::Truncated));
+    }
+}
@@ -0,0 +1,76 @@
+//! Link-time check that the core decoders have no reachable panic path, in the manner of the
+//! `no_panic` crate.
+//!
+//! Each function is called through a wrapper holding a guard whose destructor references a symbol
+//! that doesn't exist. The destructor only runs while unwinding, so the symbol is only referenced,
+//! and the link only fails, if the optimizer could not prove that the function never panics.
+//! Optimizations and LTO are required, which the `no-panic` profile sets:
+//!
+//! ```text
+//! cargo test --profile no-panic --features no-panic --test no_panic
+//! ```
+#![cfg(all(feature = "no-panic", not(debug_assertions)))]
+
+use bitcoin_varint::decoder::Decoder;
+use bitcoin_varint::incremental::IncrementalDecoder;
+use bitcoin_varint::quic::Quic;
+use bitcoin_varint::vlq::Vlq;
+use bitcoin_varint::{BigSize, Decoded, Leb128, VarInt, VarIntError};
+use std::hint::black_box;
+
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        extern "C" {
+            #[link_name = "\n\nERROR: a function checked by tests/no_panic.rs can panic\n\n"]
+            fn panic_is_reachable() -> !;
+        }
+        unsafe { panic_is_reachable() }
+    }
+}
+
+macro_rules! no_panic {
+    ($($name:ident($($arg:ident: $ty:ty),*) -> $ret:ty = $call:expr;)*) => {
+        $(
+            #[inline(never)]
+            fn $name($($arg: $ty),*) -> $ret {
+                let guard = Guard;
+                let result = $call;
+                std::mem::forget(guard);
+                result
+            }
+        )*
+    };
+}
+
+no_panic! {
+    varint_decode_full(bytes: &[u8]) -> Result<Decoded, VarIntError> = VarInt::decode_full(bytes);
+    varint_decode_constant_time(window: &[u8; 9]) -> Decoded = VarInt::decode_constant_time(window);
+    varint_encoded_len(value: u64) -> usize = VarInt::encoded_len(value);
+    varint_decode_u32(bytes: &[u8]) -> Result<(u32, usize), VarIntError> = VarInt::decode_u32(bytes);
+    decoder_decode(decoder: &Decoder, bytes: &[u8]) -> Result<(u64, usize), VarIntError> = decoder.decode(bytes);
+    bigsize_decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> = BigSize::decode(bytes);
+    leb128_decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> = Leb128::decode(bytes);
+    vlq_decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> = Vlq::decode(bytes);
+    quic_decode(bytes: &[u8]) -> Result<(u64, usize), VarIntError> = Quic::decode(bytes);
+    incremental_feed(decoder: &mut IncrementalDecoder, bytes: &[u8]) -> Result<(usize, Option<u64>), VarIntError> = decoder.feed(bytes);
+}
+
+#[test]
+fn test_no_panic() {
+    let bytes: &[u8] = black_box(&[0xfd, 0x03, 0x02, 0x00]);
+    assert_eq!(varint_decode_full(bytes).unwrap().value, 515);
+    assert_eq!(varint_decode_constant_time(&[0x2a; 9]).value, 42);
+    assert_eq!(varint_encoded_len(black_box(515)), 3);
+    assert_eq!(varint_decode_u32(bytes), Ok((515, 3)));
+    assert!(decoder_decode(&Decoder::bitcoin_core(), bytes).is_ok());
+    assert_eq!(bigsize_decode(bytes), Ok((0x0302, 3)));
+    assert_eq!(leb128_decode(bytes), Ok((509, 2)));
+    assert!(vlq_decode(bytes).is_ok());
+    assert_eq!(quic_decode(bytes), Err(VarIntError::Truncated));
+    assert_eq!(
+        incremental_feed(&mut IncrementalDecoder::new(), bytes),
+        Ok((3, Some(515)))
+    );
+}
@@ -0,0 +1,388 @@
+//! Structure-aware fuzzing of the composite decoders: TLV streams, TLV framing, delimited messages and
+//! bulk decoding.
+//!
+//! Each case builds a valid input from random records, then applies one mutation that keeps most of the
+//! structure intact (truncation, length extension or a non-canonical re-encoding) so that the decoders
+//! are driven deep into their parsing logic instead of failing on the first byte, and checks the exact
+//! outcome the mutation calls for. Random byte flips then check that decoding never panics and that
+//! whatever decodes re-encodes consistently.
+//!
+//! The cases come from a seeded generator, so failures are reproducible. Run longer with:
+//!
+//! ```text
+//! VARINT_FUZZ_ITERATIONS=1000000 cargo test --release --test structured_fuzz
+//! ```
+#![cfg(all(feature = "bigsize", feature = "leb128"))]
+
+use bitcoin_varint::bulk::{decode_all, decode_iter, encode_all};
+use bitcoin_varint::decoder::Decoder;
+use bitcoin_varint::delimited::{read_delimited, write_delimited};
+use bitcoin_varint::tlv::{TlvCodec, TlvStream};
+use bitcoin_varint::{compact_block, psbt, witness};
+use bitcoin_varint::{BigSize, Leb128, VarInt, VarIntError};
+use std::io;
+
+fn iterations() -> usize {
+    std::env::var("VARINT_FUZZ_ITERATIONS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(2000)
+}
+
+/// xorshift64, good enough to spread cases.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// A value whose encoding takes each possible length about as often.
+    fn value(&mut self) -> u64 {
+        let value = self.next();
+        match self.below(4) {
+            0 => value % 0xfd,
+            1 => 0xfd + value % (0x10000 - 0xfd),
+            2 => 0x10000 + value % (0x1_0000_0000 - 0x10000),
+            _ => value | 0x1_0000_0000,
+        }
+    }
+
+    fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+/// A record encoded at `start`, with where its length field and its value are.
+struct Layout {
+    start: usize,
+    len_at: usize,
+    value_at: usize,
+    end: usize,
+}
+
+type Records = Vec<(u64, Vec<u8>)>;
+
+/// Random strictly increasing records of a TLV stream, encoded, with the layout of each record.
+fn tlv_stream(rng: &mut Rng) -> (Records, Vec<u8>, Vec<Layout>) {
+    let mut records = Vec::new();
+    let mut tlv_type = rng.below(4) as u64;
+    for _ in 0..rng.below(6) {
+        records.push((tlv_type, rng.bytes(300)));
+        tlv_type += 1 + [rng.next() % 4, rng.next() % 0x20000][rng.below(2)];
+    }
+    let mut bytes = Vec::new();
+    let mut layout = Vec::new();
+    for (tlv_type, value) in &records {
+        let start = bytes.len();
+        bytes.extend_from_slice(&BigSize::encode(*tlv_type));
+        let len_at = bytes.len();
+        bytes.extend_from_slice(&BigSize::encode(value.len() as u64));
+        let value_at = bytes.len();
+        bytes.extend_from_slice(value);
+        layout.push(Layout {
+            start,
+            len_at,
+            value_at,
+            end: bytes.len(),
+        });
+    }
+    (records, bytes, layout)
+}
+
+/// Replaces `bytes[at..at + len]` with `replacement`.
+fn splice(bytes: &[u8], at: usize, len: usize, replacement: &[u8]) -> Vec<u8> {
+    let mut result = bytes[..at].to_vec();
+    result.extend_from_slice(replacement);
+    result.extend_from_slice(&bytes[at + len..]);
+    result
+}
+
+/// The BigSize encoding of `value` one size up from its shortest form.
+fn bigsize_padded(value: u64) -> Option<Vec<u8>> {
+    match BigSize::get_size(value) {
+        1 => Some([&[0xfd][..], &(value as u16).to_be_bytes()].concat()),
+        3 => Some([&[0xfe][..], &(value as u32).to_be_bytes()].concat()),
+        5 => Some([&[0xff][..], &value.to_be_bytes()].concat()),
+        _ => None,
+    }
+}
+
+#[test]
+fn fuzz_tlv_stream_mutations() {
+    let mut rng = Rng(0x243f6a8885a308d3);
+    for case in 0..iterations() {
+        let (records, bytes, layout) = tlv_stream(&mut rng);
+        let decoded = TlvStream::decode(&bytes).unwrap();
+        let decoded: Vec<_> = decoded
+            .iter()
+            .map(|record| (record.tlv_type, record.value.to_vec()))
+            .collect();
+        assert_eq!(decoded, records, "case {}", case);
+        if records.is_empty() {
+            continue;
+        }
+        let target = rng.below(records.len());
+        let record = &layout[target];
+
+        // Truncation: a cut inside a record fails after the records before it, a cut between records doesn't.
+        let cut = rng.below(bytes.len() + 1);
+        let results: Vec<_> = TlvStream::iter(&bytes[..cut]).collect();
+        let complete = layout.iter().take_while(|record| record.end <= cut).count();
+        if layout.iter().any(|record| record.end == cut) || cut == 0 {
+            assert_eq!(results.len(), complete, "case {} cut {}", case, cut);
+            assert!(results.iter().all(Result::is_ok));
+        } else {
+            assert_eq!(results.len(), complete + 1, "case {} cut {}", case, cut);
+            assert_eq!(results[complete], Err(VarIntError::Truncated));
+        }
+
+        // Length extension: announcing more bytes than the stream holds must not read past its end.
+        let value_len = (record.end - record.value_at) as u64;
+        let extended = value_len + 1 + rng.next() % 0x1_0000_0000;
+        let mutated = splice(
+            &bytes,
+            record.len_at,
+            record.value_at - record.len_at,
+            &BigSize::encode(extended),
+        );
+        match TlvStream::decode(&mutated) {
+            Err(_) => {}
+            // Only a record swallowing the ones after it can still decode.
+            Ok(decoded) => assert!(target + 1 < records.len() && decoded.len() <= target + 1),
+        }
+        if target + 1 == records.len() {
+            assert_eq!(TlvStream::decode(&mutated), Err(VarIntError::Truncated));
+        }
+
+        // Non-canonical type or length: BOLT 1 requires the shortest form.
+        let (at, field_len, value) = match rng.below(2) {
+            0 => (
+                record.start,
+                record.len_at - record.start,
+                records[target].0,
+            ),
+            _ => (record.len_at, record.value_at - record.len_at, value_len),
+        };
+        if let Some(padded) = bigsize_padded(value) {
+            let mutated = splice(&bytes, at, field_len, &padded);
+            let results: Vec<_> = TlvStream::iter(&mutated).collect();
+            assert_eq!(results.len(), target + 1, "case {}", case);
+            assert_eq!(results[target], Err(VarIntError::NonCanonical));
+        }
+    }
+}
+
+#[test]
+fn fuzz_tlv_codec_framing() {
+    let mut rng = Rng(0x13198a2e03707344);
+    for case in 0..iterations() {
+        let max_len = 1 + rng.below(400);
+        let framing = TlvCodec::new(VarInt, max_len);
+        let mut stream = Vec::new();
+        let mut records = Vec::new();
+        for _ in 0..1 + rng.below(4) {
+            let (tlv_type, value) = (rng.value(), rng.bytes(max_len));
+            framing.encode(tlv_type, &value, &mut stream).unwrap();
+            records.push((tlv_type, value));
+        }
+
+        // Fed in random pieces, as from a socket, every prefix asks for more and the records come out whole.
+        let mut buffer = Vec::new();
+        let mut decoded = Vec::new();
+        let mut rest = &stream[..];
+        while !rest.is_empty() {
+            let take = 1 + rng.below(rest.len().min(64));
+            buffer.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            while let Some((record, len)) = framing.decode(&buffer).unwrap() {
+                decoded.push((record.tlv_type, record.value.to_vec()));
+                buffer.drain(..len);
+            }
+        }
+        assert_eq!(decoded, records, "case {}", case);
+        assert!(buffer.is_empty());
+
+        // A length over the maximum is refused as soon as the header is in, whatever follows.
+        let mut header = VarInt::encode(rng.value()).unwrap();
+        let too_long = max_len as u64 + 1 + rng.next() % 0x10000;
+        header.extend_from_slice(&VarInt::encode(too_long).unwrap());
+        assert_eq!(framing.decode(&header), Err(VarIntError::TooLarge));
+    }
+}
+
+#[test]
+fn fuzz_delimited_mutations() {
+    let mut rng = Rng(0xa4093822299f31d0);
+    for case in 0..iterations() {
+        let payloads: Vec<_> = (0..1 + rng.below(4)).map(|_| rng.bytes(300)).collect();
+        let mut stream = Vec::new();
+        let mut ends = Vec::new();
+        for payload in &payloads {
+            write_delimited(payload, &mut stream).unwrap();
+            ends.push(stream.len());
+        }
+
+        // Truncation: whole messages are read, then a cut message is an unexpected end of input.
+        let cut = rng.below(stream.len() + 1);
+        let mut reader = &stream[..cut];
+        let complete = ends.iter().take_while(|end| **end <= cut).count();
+        for payload in &payloads[..complete] {
+            assert_eq!(
+                read_delimited(&mut reader, 300).unwrap().as_ref(),
+                Some(payload)
+            );
+        }
+        match read_delimited(&mut reader, 300) {
+            Ok(None) => assert!(cut == 0 || ends.contains(&cut), "case {}", case),
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof, "case {}", case),
+            Ok(Some(_)) => panic!("case {}: read a message past the cut", case),
+        }
+
+        // Length extension of the last message, and a length over the limit.
+        let last_start = ends.len().checked_sub(2).map_or(0, |i| ends[i]);
+        let mut extended = stream[..last_start].to_vec();
+        let last = payloads.last().unwrap();
+        let announced = last.len() + 1 + rng.below(1000);
+        extended.extend_from_slice(&Leb128::encode(announced as u64));
+        extended.extend_from_slice(last);
+        let mut reader = &extended[..];
+        for _ in 1..payloads.len() {
+            read_delimited(&mut reader, 2000).unwrap().unwrap();
+        }
+        let err = read_delimited(&mut reader, 2000).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "case {}", case);
+        let mut reader = &extended[last_start..];
+        let err = read_delimited(&mut reader, last.len()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "case {}", case);
+    }
+}
+
+#[test]
+fn fuzz_bulk_mutations() {
+    let mut rng = Rng(0x082efa98ec4e6c89);
+    let strict = Decoder::bitcoin_core();
+    for case in 0..iterations() {
+        let values: Vec<u64> = (0..1 + rng.below(20)).map(|_| rng.value()).collect();
+        let bytes = encode_all(&values);
+        let starts: Vec<usize> = values
+            .iter()
+            .scan(0, |pos, value| {
+                let start = *pos;
+                *pos += VarInt::encoded_len(*value);
+                Some(start)
+            })
+            .collect();
+        assert_eq!(decode_all(&bytes).unwrap(), values);
+
+        // Truncation inside a value reports where it starts and its index.
+        let cut = 1 + rng.below(bytes.len());
+        let index = starts.iter().rposition(|start| *start < cut).unwrap();
+        let value_end = starts.get(index + 1).copied().unwrap_or(bytes.len());
+        match decode_all(&bytes[..cut]) {
+            Ok(decoded) => {
+                assert_eq!(cut, value_end, "case {}", case);
+                assert_eq!(decoded, values[..=index]);
+            }
+            Err(err) => {
+                assert_eq!(
+                    (err.error, err.offset, err.index),
+                    (VarIntError::Truncated, starts[index], index),
+                    "case {}",
+                    case
+                );
+                assert_eq!(err.expected, Some(value_end - starts[index]));
+            }
+        }
+
+        // A value padded to a longer form decodes in lenient mode and fails in strict mode.
+        let index = rng.below(values.len());
+        let value = values[index];
+        let padded = match VarInt::encoded_len(value) {
+            1 => [&[0xfd][..], &(value as u16).to_le_bytes()].concat(),
+            3 => [&[0xfe][..], &(value as u32).to_le_bytes()].concat(),
+            5 => [&[0xff][..], &value.to_le_bytes()].concat(),
+            _ => continue,
+        };
+        let len = VarInt::encoded_len(value);
+        let mutated = splice(&bytes, starts[index], len, &padded);
+        assert_eq!(decode_all(&mutated).unwrap(), values, "case {}", case);
+        let mut pos = 0;
+        let mut strict_index = 0;
+        let error = loop {
+            match strict.decode(&mutated[pos..]) {
+                Ok((_, len)) => {
+                    pos += len;
+                    strict_index += 1;
+                }
+                Err(e) => break e,
+            }
+        };
+        // Values above Bitcoin Core's limit are refused for their size before their form.
+        if values[..index].iter().all(|value| *value <= 0x0200_0000) {
+            assert_eq!(strict_index, index, "case {}", case);
+            assert!(matches!(
+                error,
+                VarIntError::NonCanonical | VarIntError::TooLarge
+            ));
+        }
+    }
+}
+
+#[test]
+fn fuzz_byte_flips() {
+    let mut rng = Rng(0xbe5466cf34e90c6c);
+    for case in 0..iterations() {
+        let (records, mut bytes, _) = tlv_stream(&mut rng);
+        let values: Vec<u64> = (0..rng.below(8)).map(|_| rng.value()).collect();
+        bytes.extend_from_slice(&encode_all(&values));
+        let items: Vec<Vec<u8>> = records.into_iter().map(|(_, value)| value).collect();
+        bytes.extend_from_slice(&witness::encode_witness(&items));
+        for _ in 0..1 + rng.below(4) {
+            if !bytes.is_empty() {
+                let at = rng.below(bytes.len());
+                bytes[at] ^= 1 << rng.below(8);
+            }
+        }
+
+        // Nothing panics, lengths stay within the input and whatever decodes re-encodes to the same bytes.
+        if let Ok(records) = TlvStream::decode(&bytes) {
+            let records: Vec<_> = records.iter().map(|r| (r.tlv_type, r.value)).collect();
+            assert_eq!(TlvStream::encode(&records).unwrap(), bytes, "case {}", case);
+        }
+        // CompactSizes are read leniently, so a flip can only make an encoding longer than the shortest.
+        if let Ok((items, len)) = witness::decode_witness(&bytes) {
+            let encoded = witness::encode_witness(&items);
+            assert!(encoded.len() <= len, "case {}", case);
+            assert_eq!(
+                witness::decode_witness(&encoded),
+                Ok((items, encoded.len()))
+            );
+        }
+        if let Ok((indexes, len)) = compact_block::decode_indexes(&bytes) {
+            assert!(len <= bytes.len());
+            assert!(indexes.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        if let Ok((pairs, len)) = psbt::read_map(&bytes) {
+            assert!(len <= bytes.len() && pairs.len() < len.max(1));
+        }
+        let mut pos = 0;
+        for value in decode_iter(&bytes) {
+            match value {
+                Ok(value) => pos += VarInt::encoded_len(value),
+                Err(err) => {
+                    assert!(err.offset >= pos && err.offset < bytes.len());
+                    break;
+                }
+            }
+        }
+    }
+}